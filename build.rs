@@ -0,0 +1,438 @@
+//! Converts the images and fonts in `assets/` into Rust statics.
+//! 
+//! - `*.pbm` (P1 or P4) and `*.png` images become a `Bitmap`. Set PBM pixels
+//!   and dark, opaque PNG pixels are drawn, i.e. lit on the OLED.
+//! - `*.bdf` fonts become a `Font`. They have to fit in a single page, so no
+//!   taller than 8 pixels, and only the printable ASCII range is kept.
+//! - A directory of same-sized images becomes an `Animation`, one frame per
+//!   image in file name order. A frame is shown for 100ms, or for as many
+//!   milliseconds as the number after the last `-` in its name says, so
+//!   `03-250.pbm` is up for 250ms. Frames are stored as the bytes that
+//!   changed since the one before, unless the whole image is smaller.
+//! 
+//! Each asset is named after its file, so `assets/blaster.pbm` turns into
+//! `crate::assets::BLASTER`. The generated code is included by `src/assets.rs`.
+//! The pixel data goes into flash through `progmem!`, and the handles are
+//! `const`s, so none of it takes up SRAM.
+
+use std::env;
+use std::fmt::Write;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const ASSET_DIR: &str = "assets";
+
+/// How long an animation frame is shown if its file name doesn't say.
+const DEFAULT_FRAME_MS: u16 = 100;
+
+/// The run count that marks a key frame, as in `ssd1306::animation`.
+const KEY_FRAME: u8 = 0xFF;
+
+/// Bytes in front of each run of a delta frame: page, column and length.
+const RUN_HEADER_LEN: usize = 3;
+
+/// A decoded image, one `Vec` of pixels per row.
+struct Image {
+    width: usize,
+    height: usize,
+    rows: Vec<Vec<bool>>,
+}
+
+fn main() {
+    println!("cargo:rerun-if-changed={}", ASSET_DIR);
+    
+    let mut code = String::new();
+    for path in &list_dir(Path::new(ASSET_DIR)) {
+        println!("cargo:rerun-if-changed={}", path.display());
+        
+        let result = if path.is_dir() {
+            animation(path)
+        } else if let Some(image) = load_image(path) {
+            image.map(|image| bitmap(path, &image))
+        } else if has_extension(path, "bdf") {
+            fs::read_to_string(path).map_err(|err| err.to_string()).and_then(|text| font(path, &text))
+        } else {
+            continue;
+        };
+        
+        match result {
+            Ok(item) => code.push_str(&item),
+            Err(err) => panic!("{}: {}", path.display(), err),
+        }
+    }
+    
+    let out = Path::new(&env::var("OUT_DIR").unwrap()).join("assets.rs");
+    fs::write(out, code).expect("can't write generated assets");
+}
+
+/// The entries of `dir`, sorted to keep the output stable between builds.
+/// A missing directory is empty.
+fn list_dir(dir: &Path) -> Vec<PathBuf> {
+    let mut paths: Vec<PathBuf> = match fs::read_dir(dir) {
+        Ok(entries) => entries.map(|entry| entry.expect("can't read asset directory").path()).collect(),
+        Err(_) => Vec::new(),
+    };
+    paths.sort();
+    paths
+}
+
+fn has_extension(path: &Path, extension: &str) -> bool {
+    path.extension().and_then(|ext| ext.to_str()).is_some_and(|ext| ext.eq_ignore_ascii_case(extension))
+}
+
+/// Decode `path` if it's an image, i.e. a PBM or PNG file.
+fn load_image(path: &Path) -> Option<Result<Image, String>> {
+    if has_extension(path, "pbm") {
+        Some(fs::read(path).map_err(|err| err.to_string()).and_then(|bytes| parse_pbm(&bytes)))
+    } else if has_extension(path, "png") {
+        Some(parse_png(path))
+    } else {
+        None
+    }
+}
+
+/// `assets/blaster.pbm` -> `BLASTER`
+fn static_name(path: &Path) -> String {
+    let stem = path.file_stem().and_then(|stem| stem.to_str()).unwrap_or("asset");
+    let mut name: String = stem
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_uppercase() } else { '_' })
+        .collect();
+    if name.starts_with(|c: char| c.is_ascii_digit()) {
+        name.insert(0, '_');
+    }
+    name
+}
+
+/// Pack an image into pages of 8 rows, one byte per column, LSB at the top.
+fn pack(image: &Image) -> Vec<u8> {
+    let mut data = Vec::new();
+    for page in 0..image.height.div_ceil(8) {
+        for x in 0..image.width {
+            let mut byte = 0;
+            for bit in 0..8 {
+                let y = page * 8 + bit;
+                if y < image.height && image.rows[y][x] {
+                    byte |= 1 << bit;
+                }
+            }
+            data.push(byte);
+        }
+    }
+    data
+}
+
+fn byte_list(data: &[u8]) -> String {
+    let mut list = String::new();
+    for line in data.chunks(16) {
+        list.push_str("        ");
+        for byte in line {
+            write!(list, "0x{:02X}, ", byte).unwrap();
+        }
+        list.truncate(list.trim_end().len());
+        list.push('\n');
+    }
+    list
+}
+
+fn bitmap(path: &Path, image: &Image) -> String {
+    assert!(image.width <= 255 && image.height <= 255, "{}: image too large", path.display());
+    
+    format!(
+        "/// Generated from `{}`.\npub const {}: Bitmap = Bitmap {{\n    width: {},\n    height: {},\n    data: crate::progmem!(&[\n{}    ]),\n}};\n\n",
+        path.display(),
+        static_name(path),
+        image.width,
+        image.height,
+        byte_list(&pack(image)),
+    )
+}
+
+/// How long the frame in `path` is shown, from the number after the last
+/// `-` in its name.
+fn frame_duration(path: &Path) -> Result<u16, String> {
+    let stem = path.file_stem().and_then(|stem| stem.to_str()).unwrap_or("");
+    match stem.rsplit_once('-') {
+        Some((_, ms)) => ms.parse().map_err(|_| format!("bad frame duration {:?}", ms)),
+        None => Ok(DEFAULT_FRAME_MS),
+    }
+}
+
+/// The runs of bytes that differ between two packed frames, preceded by the
+/// run count. `None` if there are too many runs to count.
+fn delta(previous: &[u8], frame: &[u8], width: usize) -> Option<Vec<u8>> {
+    let mut runs = Vec::new();
+    let mut count = 0;
+    
+    for (page, (old, new)) in previous.chunks(width).zip(frame.chunks(width)).enumerate() {
+        let mut x = 0;
+        while x < width {
+            if old[x] == new[x] {
+                x += 1;
+                continue;
+            }
+            
+            // keep going over short stretches of unchanged bytes, they're
+            // cheaper to resend than a new run header
+            let start = x;
+            let mut last = x;
+            while x < width && x - last <= RUN_HEADER_LEN {
+                if old[x] != new[x] {
+                    last = x;
+                }
+                x += 1;
+            }
+            
+            runs.extend([page as u8, start as u8, (last + 1 - start) as u8]);
+            runs.extend(&new[start..=last]);
+            count += 1;
+            x = last + 1;
+        }
+    }
+    
+    if count >= KEY_FRAME as usize {
+        return None;
+    }
+    runs.insert(0, count as u8);
+    Some(runs)
+}
+
+fn animation(dir: &Path) -> Result<String, String> {
+    let mut size = None;
+    let mut previous: Option<Vec<u8>> = None;
+    let mut data = Vec::new();
+    
+    for path in list_dir(dir) {
+        let Some(image) = load_image(&path) else { continue };
+        println!("cargo:rerun-if-changed={}", path.display());
+        let image = image.map_err(|err| format!("{}: {}", path.display(), err))?;
+        
+        match size {
+            None => size = Some((image.width, image.height)),
+            Some(size) if size != (image.width, image.height) => {
+                return Err(format!("{} is {}x{}, the first frame is {}x{}", path.display(), image.width, image.height, size.0, size.1));
+            },
+            Some(_) => {},
+        }
+        
+        let frame = pack(&image);
+        let mut key = vec![KEY_FRAME];
+        key.extend(&frame);
+        
+        // the first frame is always a key frame, so looping starts clean
+        let encoded = match previous.as_deref().and_then(|previous| delta(previous, &frame, image.width)) {
+            Some(delta) if delta.len() < key.len() => delta,
+            _ => key,
+        };
+        
+        data.extend(frame_duration(&path)?.to_le_bytes());
+        data.extend(encoded);
+        previous = Some(frame);
+    }
+    
+    let (width, height) = size.ok_or("no frames")?;
+    if width > 255 || height > 255 {
+        return Err("frames too large".into());
+    }
+    
+    Ok(format!(
+        "/// Generated from `{}/`.\npub const {}: Animation = Animation {{\n    width: {},\n    height: {},\n    data: crate::progmem!(&[\n{}    ]),\n}};\n\n",
+        dir.display(),
+        static_name(dir),
+        width,
+        height,
+        byte_list(&data),
+    ))
+}
+
+/// Parse a plain (P1) or raw (P4) PBM. A set bit is a drawn pixel.
+fn parse_pbm(bytes: &[u8]) -> Result<Image, String> {
+    let mut pos = 0;
+    
+    // header tokens, skipping whitespace and comments
+    let next_token = |pos: &mut usize| -> Option<String> {
+        loop {
+            while *pos < bytes.len() && bytes[*pos].is_ascii_whitespace() {
+                *pos += 1;
+            }
+            if *pos < bytes.len() && bytes[*pos] == b'#' {
+                while *pos < bytes.len() && bytes[*pos] != b'\n' {
+                    *pos += 1;
+                }
+                continue;
+            }
+            break;
+        }
+        let start = *pos;
+        while *pos < bytes.len() && !bytes[*pos].is_ascii_whitespace() {
+            *pos += 1;
+        }
+        (start < *pos).then(|| String::from_utf8_lossy(&bytes[start..*pos]).into_owned())
+    };
+    
+    let magic = next_token(&mut pos).ok_or("empty file")?;
+    let width: usize = next_token(&mut pos).and_then(|w| w.parse().ok()).ok_or("bad width")?;
+    let height: usize = next_token(&mut pos).and_then(|h| h.parse().ok()).ok_or("bad height")?;
+    
+    let mut rows = vec![vec![false; width]; height];
+    match magic.as_str() {
+        "P1" => {
+            let mut bits = bytes[pos..].iter().filter(|b| matches!(b, b'0' | b'1'));
+            for row in rows.iter_mut() {
+                for pixel in row.iter_mut() {
+                    *pixel = *bits.next().ok_or("not enough pixels")? == b'1';
+                }
+            }
+        },
+        "P4" => {
+            // exactly one whitespace byte separates the header from the data
+            let data = &bytes[pos + 1..];
+            let stride = width.div_ceil(8);
+            if data.len() < stride * height {
+                return Err("not enough pixels".into());
+            }
+            for (y, row) in rows.iter_mut().enumerate() {
+                for (x, pixel) in row.iter_mut().enumerate() {
+                    *pixel = data[y * stride + x / 8] & (0x80 >> (x % 8)) != 0;
+                }
+            }
+        },
+        other => return Err(format!("unsupported PBM format {}", other)),
+    }
+    
+    Ok(Image { width, height, rows })
+}
+
+fn parse_png(path: &Path) -> Result<Image, String> {
+    let file = fs::File::open(path).map_err(|err| err.to_string())?;
+    let mut decoder = png::Decoder::new(file);
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
+    let mut reader = decoder.read_info().map_err(|err| err.to_string())?;
+    
+    let mut buffer = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buffer).map_err(|err| err.to_string())?;
+    let channels = info.color_type.samples();
+    
+    let rows = buffer
+        .chunks(info.line_size)
+        .take(info.height as usize)
+        .map(|line| {
+            line.chunks(channels).take(info.width as usize).map(|pixel| {
+                let (luma, alpha) = match pixel {
+                    [luma] => (*luma as u32, 255),
+                    [luma, alpha] => (*luma as u32, *alpha),
+                    [r, g, b] => ((*r as u32 + *g as u32 + *b as u32) / 3, 255),
+                    [r, g, b, alpha] => ((*r as u32 + *g as u32 + *b as u32) / 3, *alpha),
+                    _ => (255, 0),
+                };
+                alpha >= 128 && luma < 128
+            }).collect()
+        })
+        .collect();
+    
+    Ok(Image { width: info.width as usize, height: info.height as usize, rows })
+}
+
+/// Turn a BDF font into a `Font`, with every glyph in a cell the size of the
+/// font's bounding box.
+fn font(path: &Path, text: &str) -> Result<String, String> {
+    let numbers = |line: &str| -> Vec<i32> {
+        line.split_whitespace().skip(1).filter_map(|n| n.parse().ok()).collect()
+    };
+    
+    let mut bounds = None;
+    let mut glyphs = std::collections::BTreeMap::new();
+    let mut lines = text.lines();
+    
+    while let Some(line) = lines.next() {
+        if line.starts_with("FONTBOUNDINGBOX") {
+            if let [w, h, x, y] = numbers(line)[..] {
+                bounds = Some((w, h, x, y));
+            }
+        }
+        if !line.starts_with("STARTCHAR") {
+            continue;
+        }
+        
+        let (mut encoding, mut bbx) = (-1, (0, 0, 0, 0));
+        let (mut rows, mut in_bitmap) = (Vec::new(), false);
+        for line in lines.by_ref() {
+            if line.starts_with("ENCODING") {
+                encoding = numbers(line).first().copied().unwrap_or(-1);
+            } else if line.starts_with("BBX") {
+                if let [w, h, x, y] = numbers(line)[..] {
+                    bbx = (w, h, x, y);
+                }
+            } else if line.starts_with("BITMAP") {
+                in_bitmap = true;
+            } else if line.starts_with("ENDCHAR") {
+                break;
+            } else if in_bitmap {
+                rows.push(hex_row(line)?);
+            }
+        }
+        
+        if (0x20..=0x7E).contains(&encoding) {
+            glyphs.insert(encoding as u8, (bbx, rows));
+        }
+    }
+    
+    let (cell_w, cell_h, cell_x, cell_y) = bounds.ok_or("no FONTBOUNDINGBOX")?;
+    if cell_h > 8 {
+        return Err(format!("font is {} pixels tall, fonts can be at most 8", cell_h));
+    }
+    if !(1..=255).contains(&cell_w) {
+        return Err(format!("font is {} pixels wide, fonts can be 1 to 255", cell_w));
+    }
+    let (&first, _) = glyphs.first_key_value().ok_or("no printable ASCII glyphs")?;
+    let (&last, _) = glyphs.last_key_value().unwrap();
+    
+    let mut data = Vec::new();
+    for code in first..=last {
+        let mut image = Image {
+            width: cell_w as usize,
+            height: cell_h as usize,
+            rows: vec![vec![false; cell_w as usize]; cell_h as usize],
+        };
+        
+        if let Some(&((w, h, x, y), ref rows)) = glyphs.get(&code) {
+            let top = (cell_h + cell_y) - (y + h);
+            for (dy, bytes) in rows.iter().enumerate() {
+                for dx in 0..w {
+                    let (px, py) = (x - cell_x + dx, top + dy as i32);
+                    let set = bytes.get(dx as usize / 8).is_some_and(|byte| byte & (0x80 >> (dx % 8)) != 0);
+                    if set && (0..cell_w).contains(&px) && (0..cell_h).contains(&py) {
+                        image.rows[py as usize][px as usize] = true;
+                    }
+                }
+            }
+        }
+        
+        data.extend(pack(&image));
+    }
+    
+    let fallback = if glyphs.contains_key(&b'?') { '?' } else { first as char };
+    Ok(format!(
+        "/// Generated from `{}`.\npub const {}: Font = Font {{\n    glyph_width: {},\n    first: {:?},\n    last: {:?},\n    fallback: {:?},\n    glyphs: crate::progmem!(&[\n{}    ]),\n}};\n\n",
+        path.display(),
+        static_name(path),
+        cell_w,
+        first as char,
+        last as char,
+        fallback,
+        byte_list(&data),
+    ))
+}
+
+/// Parse a row of a BDF glyph bitmap: two hex digits per byte, with the
+/// leftmost pixel in the top bit of the first byte. Any number of bytes, so
+/// any glyph width.
+fn hex_row(line: &str) -> Result<Vec<u8>, String> {
+    let digits = line.trim();
+    if !digits.len().is_multiple_of(2) || !digits.bytes().all(|digit| digit.is_ascii_hexdigit()) {
+        return Err(format!("bad bitmap row {:?}", digits));
+    }
+    
+    // the digits were checked above, so this can't fail
+    Ok((0..digits.len()).step_by(2).map(|start| u8::from_str_radix(&digits[start..start + 2], 16).unwrap()).collect())
+}
@@ -0,0 +1,53 @@
+#!/bin/sh
+# Size report for the firmware image, and a check that the flash data stays
+# in flash.
+#
+# Builds a release image, prints how much flash and SRAM it uses, and fails if
+# anything made by `progmem!` (bitmaps, fonts, animations, console and error
+# strings) ended up in .data, where the startup code would copy it into SRAM.
+# It also fails on any text in .data, which is what a string that should have
+# been a `pm_str!` looks like. CI runs this on every push, see
+# .github/workflows/firmware.yml.
+#
+# Needs avr-size, avr-nm and avr-objcopy from the AVR binutils, and strings.
+
+set -eu
+
+cd "$(dirname "$0")/.."
+
+cargo build --release
+
+ELF=target/avr-atmega328p/release/nerf-gun-firmware.elf
+
+avr-size --format=avr --mcu=atmega328p "$ELF"
+
+# every `progmem!` makes a static called FLASH. Its section letter from nm
+# should be T/t (.progmem.data is part of .text), never D/d (.data).
+symbols=$(avr-nm --demangle --defined-only "$ELF" | grep -E '::FLASH(::h[0-9a-f]+)?$' || true)
+in_data=$(echo "$symbols" | awk '$2 ~ /^[dD]$/ { print $3 }')
+
+if [ -z "$symbols" ]; then
+    echo "no progmem data found in $ELF" >&2
+    exit 1
+fi
+
+echo
+echo "$(echo "$symbols" | wc -l) progmem statics"
+
+if [ -n "$in_data" ]; then
+    echo "progmem data in .data:" >&2
+    echo "$in_data" >&2
+    exit 1
+fi
+
+# plain string literals have no symbol of their own, so look for their text
+data=$(mktemp)
+trap 'rm -f "$data"' EXIT
+avr-objcopy --output-target=binary --only-section=.data "$ELF" "$data"
+text=$(strings --all --bytes=6 "$data")
+
+if [ -n "$text" ]; then
+    echo "text in .data, use pm_str! for it:" >&2
+    echo "$text" >&2
+    exit 1
+fi
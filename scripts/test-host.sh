@@ -0,0 +1,28 @@
+#!/bin/sh
+# Runs the library's tests (the display driver and the flash data) on this
+# machine instead of the AVR.
+#
+# .cargo/config.toml targets the AVR and builds `core` from source for it, and
+# cargo has no way to switch build-std back off from the command line. Cargo
+# only reads that config when it's run from inside the repository, so this
+# runs it from outside, with the host's own target:
+#
+#     cd / && cargo test --manifest-path <repo>/Cargo.toml --lib --all-features --target <host>
+#
+# The display tests compare what they draw with the images in
+# src/ssd1306/golden/. After changing what something looks like on purpose,
+# run with --update-golden to overwrite them with the new pictures, and check
+# the diff. Any other arguments are passed on to `cargo test`.
+
+set -eu
+
+repo=$(cd "$(dirname "$0")/.." && pwd)
+host=$(rustc -vV | sed -n 's/^host: //p')
+
+if [ "${1:-}" = "--update-golden" ]; then
+    export UPDATE_GOLDEN=1
+    shift
+fi
+
+cd /
+exec cargo test --manifest-path "$repo/Cargo.toml" --lib --all-features --target "$host" "$@"
@@ -0,0 +1,15 @@
+//! Images and fonts converted from `assets/` by the build script.
+//! 
+//! Add a `.pbm`, `.png` or `.bdf` file there and it shows up here as a static
+//! named after the file, e.g. `assets/blaster.pbm` is [`BLASTER`]. A
+//! directory of frames becomes an animation, e.g. `assets/reload/` is
+//! [`RELOAD`]. The data is kept in flash, see [`crate::progmem`].
+
+// not every asset is used in every build, and there may be no fonts at all
+#![allow(dead_code, unused_imports)]
+
+use crate::ssd1306::animation::Animation;
+use crate::ssd1306::bitmap::Bitmap;
+use crate::ssd1306::font::Font;
+
+include!(concat!(env!("OUT_DIR"), "/assets.rs"));
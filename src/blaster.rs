@@ -0,0 +1,69 @@
+//! State shared between the subsystems and the HUD.
+//! 
+//! Subsystems write their readings here, often from interrupt handlers, and
+//! the main loop takes a [`snapshot`] to show them. Fields only exist when
+//! their subsystem is compiled in.
+
+use avr_device::interrupt;
+use core::cell::Cell;
+
+#[cfg(feature = "fire-control")]
+use crate::settings::FireMode;
+
+#[derive(Debug, Clone, Copy)]
+pub struct BlasterState {
+    /// Darts left in the magazine.
+    #[cfg(feature = "ammo-counter")]
+    pub ammo: u8,
+    
+    #[cfg(feature = "fire-control")]
+    pub fire_mode: FireMode,
+    
+    /// Whether the rev button is held and the flywheels are powered.
+    pub revving: bool,
+    
+    /// Battery pack voltage, in millivolts.
+    #[cfg(feature = "battery-monitor")]
+    pub battery_mv: u16,
+    
+    #[cfg(feature = "tachometer")]
+    pub flywheel_rpm: u16,
+    
+    /// Speed of the last dart through the chronograph, in feet per second.
+    /// 0 until the first one.
+    #[cfg(feature = "chronograph")]
+    pub dart_velocity: u16,
+}
+
+impl BlasterState {
+    const INITIAL: BlasterState = BlasterState {
+        #[cfg(feature = "ammo-counter")]
+        ammo: 0,
+        #[cfg(feature = "fire-control")]
+        fire_mode: FireMode::Single,
+        revving: false,
+        #[cfg(feature = "battery-monitor")]
+        battery_mv: 0,
+        #[cfg(feature = "tachometer")]
+        flywheel_rpm: 0,
+        #[cfg(feature = "chronograph")]
+        dart_velocity: 0,
+    };
+}
+
+static STATE: interrupt::Mutex<Cell<BlasterState>> = interrupt::Mutex::new(Cell::new(BlasterState::INITIAL));
+
+/// Change the shared state. Safe to call from interrupt handlers.
+pub fn update(f: impl FnOnce(&mut BlasterState)) {
+    interrupt::free(|cs| {
+        let cell = STATE.borrow(cs);
+        let mut state = cell.get();
+        f(&mut state);
+        cell.set(state);
+    })
+}
+
+/// A copy of the current state.
+pub fn snapshot() -> BlasterState {
+    interrupt::free(|cs| STATE.borrow(cs).get())
+}
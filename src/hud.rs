@@ -0,0 +1,182 @@
+//! The default screen: a dashboard of the blaster's state.
+//! 
+//! Every readout belongs to a subsystem and is only there when that subsystem
+//! is compiled in, see the features in `Cargo.toml`. The layout doesn't move
+//! around when some are missing, their spots just stay blank.
+//! 
+//! ```text
+//! BURST            87% [|||]
+//! 
+//!  888   RPM   32000
+//!  8 8
+//!  888   FPS     112
+//!  8 8
+//! 
+//! REV              7.41V
+//! ```
+
+use crate::blaster::BlasterState;
+use crate::ssd1306::command::Page;
+use crate::ssd1306::error::InvalidParameter;
+use crate::ssd1306::ui::{Align, Area, Label, Widget};
+use crate::ssd1306::Display;
+
+#[cfg(feature = "ammo-counter")]
+use crate::ssd1306::digits::{BigNumber, DigitHeight};
+#[cfg(feature = "battery-monitor")]
+use crate::ssd1306::ui::BatteryGauge;
+#[cfg(any(feature = "battery-monitor", feature = "tachometer", feature = "chronograph"))]
+use crate::ssd1306::ui::Number;
+
+/// Cells in series in the battery pack.
+#[cfg(feature = "battery-monitor")]
+const BATTERY_CELLS: u16 = 2;
+
+/// Charge left in a LiPo pack at `millivolts`, roughly: linear from 3.3V per
+/// cell (empty) to 4.2V per cell (full).
+#[cfg(feature = "battery-monitor")]
+fn battery_percent(millivolts: u16) -> u8 {
+    const EMPTY: u16 = 3300 * BATTERY_CELLS;
+    const FULL: u16 = 4200 * BATTERY_CELLS;
+    
+    let charge = millivolts.clamp(EMPTY, FULL) - EMPTY;
+    (charge as u32 * 100 / (FULL - EMPTY) as u32) as u8
+}
+
+/// Whether the pack is too flat to use. 0 millivolts means there's no reading
+/// yet, so it isn't.
+#[cfg(feature = "battery-monitor")]
+pub fn battery_flat(millivolts: u16) -> bool {
+    millivolts != 0 && battery_percent(millivolts) == 0
+}
+
+pub struct Hud {
+    #[cfg(feature = "fire-control")]
+    fire_mode: Label,
+    
+    #[cfg(feature = "battery-monitor")]
+    battery_percent: Number,
+    #[cfg(feature = "battery-monitor")]
+    battery_gauge: BatteryGauge,
+    #[cfg(feature = "battery-monitor")]
+    battery_voltage: Number,
+    
+    #[cfg(feature = "ammo-counter")]
+    ammo: BigNumber,
+    
+    #[cfg(feature = "tachometer")]
+    rpm_label: Label,
+    #[cfg(feature = "tachometer")]
+    rpm: Number,
+    
+    #[cfg(feature = "chronograph")]
+    velocity_label: Label,
+    #[cfg(feature = "chronograph")]
+    velocity: Number,
+    
+    rev: Label,
+}
+
+impl Hud {
+    pub const fn new() -> Self {
+        Self {
+            #[cfg(feature = "fire-control")]
+            fire_mode: Label::new(Area::new(Page::Page0, 0, 60, 1), Align::Left, crate::pm_str!("")),
+            
+            #[cfg(feature = "battery-monitor")]
+            battery_percent: Number::new(Area::new(Page::Page0, 64, 36, 1), Align::Right)
+                .with_unit(crate::pm_str!("%")),
+            #[cfg(feature = "battery-monitor")]
+            battery_gauge: BatteryGauge::new(Area::new(Page::Page0, 104, 24, 1)),
+            #[cfg(feature = "battery-monitor")]
+            battery_voltage: Number::new(Area::new(Page::Page7, 80, 48, 1), Align::Right)
+                .with_decimals(2)
+                .with_unit(crate::pm_str!("V")),
+            
+            #[cfg(feature = "ammo-counter")]
+            ammo: BigNumber::new(Page::Page2, 0, DigitHeight::Px32),
+            
+            #[cfg(feature = "tachometer")]
+            rpm_label: Label::new(Area::new(Page::Page2, 72, 20, 1), Align::Left, crate::pm_str!("RPM")),
+            #[cfg(feature = "tachometer")]
+            rpm: Number::new(Area::new(Page::Page2, 92, 36, 1), Align::Right),
+            
+            #[cfg(feature = "chronograph")]
+            velocity_label: Label::new(Area::new(Page::Page4, 72, 20, 1), Align::Left, crate::pm_str!("FPS")),
+            #[cfg(feature = "chronograph")]
+            velocity: Number::new(Area::new(Page::Page4, 92, 36, 1), Align::Right),
+            
+            rev: Label::new(Area::new(Page::Page7, 0, 40, 1), Align::Left, crate::pm_str!("")),
+        }
+    }
+    
+    /// Redraw everything on the next [`show`](Hud::show), e.g. after the
+    /// screen was cleared.
+    pub fn invalidate(&mut self) {
+        #[cfg(feature = "fire-control")]
+        self.fire_mode.invalidate();
+        #[cfg(feature = "battery-monitor")]
+        {
+            self.battery_percent.invalidate();
+            self.battery_gauge.invalidate();
+            self.battery_voltage.invalidate();
+        }
+        #[cfg(feature = "ammo-counter")]
+        self.ammo.invalidate();
+        #[cfg(feature = "tachometer")]
+        {
+            self.rpm_label.invalidate();
+            self.rpm.invalidate();
+        }
+        #[cfg(feature = "chronograph")]
+        {
+            self.velocity_label.invalidate();
+            self.velocity.invalidate();
+        }
+        self.rev.invalidate();
+    }
+    
+    /// Show `state`, redrawing only the readouts that changed.
+    pub fn show<D>(&mut self, display: &mut D, state: &BlasterState) -> Result<(), D::Error>
+    where D: Display, D::Error: From<InvalidParameter> {
+        #[cfg(feature = "fire-control")]
+        {
+            self.fire_mode.set(state.fire_mode.name());
+            self.fire_mode.draw(display)?;
+        }
+        
+        #[cfg(feature = "battery-monitor")]
+        {
+            let percent = battery_percent(state.battery_mv);
+            self.battery_percent.set(percent as i32);
+            self.battery_percent.draw(display)?;
+            self.battery_gauge.set_percent(percent);
+            self.battery_gauge.draw(display)?;
+            // shown in hundredths of a volt
+            self.battery_voltage.set((state.battery_mv / 10) as i32);
+            self.battery_voltage.draw(display)?;
+        }
+        
+        #[cfg(feature = "ammo-counter")]
+        self.ammo.update(display, state.ammo as u16)?;
+        
+        #[cfg(feature = "tachometer")]
+        {
+            self.rpm_label.draw(display)?;
+            self.rpm.set(state.flywheel_rpm as i32);
+            self.rpm.draw(display)?;
+        }
+        
+        #[cfg(feature = "chronograph")]
+        {
+            self.velocity_label.draw(display)?;
+            self.velocity.set(state.dart_velocity as i32);
+            self.velocity.draw(display)?;
+        }
+        
+        self.rev.set(if state.revving { crate::pm_str!("REV") } else { crate::pm_str!("IDLE") });
+        self.rev.draw(display)?;
+        
+        Ok(())
+    }
+}
@@ -0,0 +1,23 @@
+//! The parts of the firmware that don't touch the ATmega328p directly: the
+//! display driver and the flash data it draws from.
+//! 
+//! They build for the host as well as for the AVR, so their tests run on a
+//! normal PC. `.cargo/config.toml` would build them for the AVR, so cargo has
+//! to be run from outside the repository, with the host's target:
+//! 
+//! ```text
+//! cd / && cargo test --manifest-path <repo>/Cargo.toml --lib --all-features --target x86_64-unknown-linux-gnu
+//! ```
+//! 
+//! `scripts/test-host.sh` does exactly this for whatever the host is.
+//! 
+//! Some of the display tests draw on an emulated panel and compare the
+//! picture with the images in `src/ssd1306/golden/`. `scripts/test-host.sh
+//! --update-golden` (or `UPDATE_GOLDEN=1` with the command above) rewrites
+//! them from what the tests draw.
+
+#![no_std]
+#![cfg_attr(target_arch = "avr", feature(asm_experimental_arch))]
+
+pub mod progmem;
+pub mod ssd1306;
@@ -7,13 +7,138 @@
 extern crate arduino_hal;
 
 use arduino_hal::simple_pwm::{Prescaler, Timer0Pwm};
-use avr_hal_generic::port::{Pin, mode};
 
+use hud::Hud;
+use settings::Settings;
+use ssd1306::animation::{Animation, Player};
+use ssd1306::command::Page;
+use ssd1306::menu::{Event, Input, MenuState};
+use ssd1306::mode::HorizontalAddressing;
+use ssd1306::size::DisplaySize;
+use ssd1306::transfer::{BackgroundFrame, TransferError};
+use ssd1306::transition::{Effect, Transition};
+use ssd1306::ui;
+
+// the driver lives in the library, so its tests can run on the host
+use nerf_gun_firmware::{pm_str, progmem, ssd1306};
+
+mod assets;
+mod blaster;
+mod hud;
 mod utils;
 mod rev_motors;
-mod ssd1306;
+mod settings;
+mod twi;
+
+/// Main loop period, in milliseconds.
+const TICK_MS: u16 = 10;
+
+/// Main loop ticks between HUD refreshes, so it updates at 10Hz.
+const HUD_REFRESH_TICKS: u8 = 10;
+
+/// Which way up the OLED is mounted in this blaster.
+const DISPLAY_ORIENTATION: ssd1306::orientation::Orientation =
+    ssd1306::orientation::Orientation::new(ssd1306::orientation::Rotation::Deg0);
+
+type Size = ssd1306::size::DisplaySize128x64;
+
+/// Horizontal addressing, so the HUD frame can go out as one data stream.
+type Oled<I> = ssd1306::SSD1306<ssd1306::I2cInterface<I>, Size, HorizontalAddressing>;
+
+/// RAM for the HUD frame. It's handed to [`BackgroundFrame`] once, in
+/// [`setup_display`].
+static mut HUD_FRAME: [u8; Size::BUFFER_SIZE] = [0; Size::BUFFER_SIZE];
+
+/// The OLED, and the frame the HUD is drawn into.
+/// 
+/// Everything that covers the whole screen (the HUD, and clearing it) goes
+/// through the frame and is sent from the TWI interrupt, so it doesn't hold
+/// up the main loop. Menus and animations only change a few bytes at a time,
+/// and are written straight to the display.
+struct Screen {
+    oled: Oled<twi::Twi>,
+    frame: BackgroundFrame<Size>,
+    twi: twi::Twi,
+    
+    /// Set to blank the screen the next time it's drawn.
+    clear_requested: bool,
+}
+
+impl Screen {
+    /// Take the frame back once the TWI is done sending it.
+    fn collect_frame(&mut self) -> Result<(), TransferError> {
+        match self.twi.take_finished() {
+            Some(transfer) => self.frame.finish(transfer),
+            None => Ok(()),
+        }
+    }
+    
+    /// Start sending the frame, if anything was drawn into it.
+    fn send_frame(&mut self) {
+        if let Some(transfer) = self.frame.transfer() {
+            // it's still dirty, so it goes out next tick instead
+            if let Err(transfer) = self.twi.queue(transfer, None) {
+                let _ = self.frame.finish(transfer);
+            }
+        }
+    }
+    
+    /// Blank the frame, if it was asked for. Returns whether it was.
+    fn clear_if_requested(&mut self) -> Result<bool, ssd1306::Ssd1306Error<TransferError>> {
+        if !self.clear_requested {
+            return Ok(false);
+        }
+        if let Some(mut frame) = self.frame.target(&mut self.oled) {
+            ui::clear(&mut frame)?;
+            self.clear_requested = false;
+        }
+        Ok(true)
+    }
+}
 
-fn setup(dp: arduino_hal::Peripherals) -> Pin<mode::Output, arduino_hal::hal::port::PB5> {
+fn setup_display(mut twi: twi::Twi) -> Result<Screen, ssd1306::Ssd1306Error<TransferError>> {
+    let address = ssd1306::I2cInterface::probe(&mut twi)?;
+    
+    let interface = ssd1306::I2cInterface::new(twi, address);
+    let mut display = ssd1306::SSD1306::new(interface, ssd1306::size::DisplaySize128x64)
+        .with_orientation(DISPLAY_ORIENTATION);
+    display.initialize()?;
+    
+    // SAFETY: this only runs once, so there's never a second reference
+    let buffer = unsafe { &mut *core::ptr::addr_of_mut!(HUD_FRAME) };
+    
+    // the frame starts out blank, and clears the display when it's first sent
+    Ok(Screen {
+        oled: display.set_address_mode::<HorizontalAddressing>()?,
+        frame: BackgroundFrame::new(address, buffer)?,
+        twi,
+        clear_requested: false,
+    })
+}
+
+/// Menu buttons on the serial console, until the blaster has real ones.
+fn console_input(key: u8) -> Option<Input> {
+    match key {
+        b'w' | b'k' => Some(Input::Up),
+        b's' | b'j' => Some(Input::Down),
+        b'\r' | b'\n' | b' ' => Some(Input::Select),
+        0x08 | 0x7F | 0x1B | b'q' => Some(Input::Back),
+        _ => None,
+    }
+}
+
+/// Clear the screen and start `animation` in the middle of it.
+fn start_animation(player: &mut Player, screen: Option<&mut Screen>, animation: Animation, looping: bool) {
+    if let Some(screen) = screen {
+        screen.clear_requested = true;
+    }
+    
+    let page = Page::try_from(Size::PAGES.saturating_sub(animation.pages()) / 2).unwrap_or(Page::Page0);
+    let col = Size::WIDTH.saturating_sub(animation.width) / 2;
+    player.play(animation, page, col, looping);
+}
+
+fn setup(dp: arduino_hal::Peripherals) -> Option<Screen> {
     let pins = arduino_hal::pins!(dp);
     
     utils::print::put_console(arduino_hal::default_serial!(dp, pins, 57600));
@@ -25,37 +150,152 @@ fn setup(dp: arduino_hal::Peripherals) -> Pin<mode::Output, arduino_hal::hal::po
     // setup all the rev motors
     rev_motors::setup(pins.d2, pins.d5, &pwm_timer, &dp.EXINT);
     
-    let i2c = arduino_hal::i2c::I2c::new(
+    let twi = twi::setup(
         dp.TWI,
         pins.a4.into_pull_up_input(),
         pins.a5.into_pull_up_input(),
         400_000,
     );
     
-    let mut display = ssd1306::SSD1306::new(0x3C, i2c);
-    display.initialize().unwrap();
-    display.send_command(ssd1306::command::Command::SetAddressMode(ssd1306::command::AddressMode::Horizontal));
-    display.send_data(&[0x00; 1024]).unwrap();
-    display.send_command(ssd1306::command::Command::SetStartLine(32)).unwrap();
-    display.send_command(ssd1306::command::Command::ColumnStart(32)).unwrap();
-    display.send_data(&[0x00, 0x66, 0x99, 0x99, 0x7E, 0x24, 0x24, 0x24, 0x24, 0x3C, 0x24, 0x18, 0x00, 0x10, 0x24]);
-    
     // SAFETY: this is the only thread running, so it's safe to enable interrupts.
     unsafe { avr_device::interrupt::enable() };
     
+    // the display is optional, so keep going without it
+    // NOTE: this needs interrupts enabled, the TWI is interrupt-driven
+    let display = match setup_display(twi) {
+        Ok(display) => Some(display),
+        Err(err) => {
+            print!("Display setup failed: ");
+            println!("{}", err.as_str());
+            None
+        },
+    };
+    
     println!("Firmware startup complete!");
     
-    return pins.d13.into_output();
+    return display;
 }
 
 #[arduino_hal::entry]
 fn main() -> ! {
     let dp = arduino_hal::Peripherals::take().unwrap();
     
-    let mut blink_led = setup(dp);
+    let mut display = setup(dp);
+    
+    let mut settings = Settings::DEFAULT;
+    if settings.apply(display.as_mut().map(|screen| &mut screen.oled)).is_err() {
+        println!("Applying settings failed");
+    }
+    #[cfg(feature = "ammo-counter")]
+    blaster::update(|state| state.ammo = settings.magazine_size);
+    
+    let mut hud = Hud::new();
+    let mut menu = MenuState::new(&settings::MENU);
+    let mut menu_open = false;
+    let mut player = Player::new();
+    let mut transition = Transition::new();
+    let mut ticks: u8 = 0;
+    
+    start_animation(&mut player, display.as_mut(), assets::BOOT, false);
     
     loop {
-        blink_led.toggle();
-        arduino_hal::delay_ms(1000);
+        let key = utils::print::read_byte();
+        
+        // there's no magazine switch yet, so 'r' on the console stands in
+        #[cfg(feature = "ammo-counter")]
+        if key == Some(b'r') && !menu_open {
+            blaster::update(|state| state.ammo = settings.magazine_size);
+            start_animation(&mut player, display.as_mut(), assets::RELOAD, false);
+        }
+        
+        if let Some(input) = key.and_then(console_input) {
+            if !menu_open {
+                // any button opens the menu, even in the middle of an animation
+                menu_open = true;
+                menu.reset();
+                player.stop();
+                transition.start(Effect::Slide, settings.contrast);
+            } else {
+                match menu.handle(input, &mut settings) {
+                    Event::Changed => {
+                        if settings.apply(display.as_mut().map(|screen| &mut screen.oled)).is_err() {
+                            println!("Applying settings failed");
+                        }
+                    },
+                    Event::Closed => {
+                        menu_open = false;
+                        transition.start(Effect::Wipe, settings.contrast);
+                    },
+                    Event::Moved | Event::Ignored => {},
+                }
+            }
+        }
+        
+        ticks = (ticks + 1) % HUD_REFRESH_TICKS;
+        
+        // a flat pack stays flat until it's swapped, which power cycles the
+        // blaster, so the fault screen doesn't need to go away by itself
+        #[cfg(feature = "battery-monitor")]
+        if ticks == 0 && !menu_open && !player.is_playing() && hud::battery_flat(blaster::snapshot().battery_mv) {
+            start_animation(&mut player, display.as_mut(), assets::FAULT, true);
+        }
+        
+        if let Some(screen) = display.as_mut() {
+            let drawn = match screen.collect_frame() {
+                Err(err) => Err(ssd1306::Ssd1306Error::Bus(err)),
+                // writing to the display would have to wait for the frame,
+                // so leave it alone until the frame is out
+                Ok(()) if screen.frame.is_sending() => Ok(()),
+                // TICK_MS is close enough, the loop's own work isn't counted
+                Ok(()) => transition.update(&mut screen.oled, TICK_MS).and_then(|hidden| {
+                    if hidden {
+                        // the old screen is gone, draw the new one from scratch
+                        menu.invalidate();
+                        hud.invalidate();
+                        ticks = 0;
+                        screen.clear_requested = true;
+                    } else if transition.is_hiding() {
+                        return Ok(());
+                    }
+                    
+                    // the menu and animations go straight to the display, so
+                    // they have to wait for the blank frame to go out first
+                    if screen.clear_if_requested()? && (menu_open || player.is_playing()) {
+                        return Ok(());
+                    }
+                    
+                    // the menu redraws straight after input, animations when
+                    // their next frame is due and the HUD at a fixed rate
+                    if menu_open {
+                        menu.draw(&mut screen.oled, &settings).map(|_| ())
+                    } else if player.is_playing() {
+                        let drawn = player.update(&mut screen.oled, TICK_MS);
+                        // back to the HUD once it's over
+                        if !player.is_playing() {
+                            transition.start(Effect::Fade, settings.contrast);
+                        }
+                        drawn.map(|_| ())
+                    } else if ticks == 0 {
+                        match screen.frame.target(&mut screen.oled) {
+                            Some(mut frame) => hud.show(&mut frame, &blaster::snapshot()),
+                            None => Ok(()),
+                        }
+                    } else {
+                        Ok(())
+                    }
+                }),
+            };
+            
+            // stop trying rather than flood the bus
+            if let Err(err) = drawn {
+                print!("Display failed: ");
+                println!("{}", err.as_str());
+                display = None;
+            } else {
+                screen.send_frame();
+            }
+        }
+        
+        arduino_hal::delay_ms(TICK_MS);
     }
 }
@@ -0,0 +1,207 @@
+//! Read-only data kept in program memory (flash).
+//! 
+//! On AVR every `static`, including the `&[u8]`s and `&str`s it points to,
+//! is copied from flash into SRAM by the startup code, so a 1 KiB image would
+//! take half of the ATmega328p's 2 KiB. Data placed in the `.progmem.data`
+//! section stays in flash instead, but can then only be read with `lpm`, not
+//! through a normal pointer. [`PmBytes`] and [`PmStr`] wrap such data and do
+//! the reads.
+//! 
+//! ```ignore
+//! const LOGO: PmBytes = progmem!(&[0x00, 0x66, 0x99]);
+//! println!("Setting up firmware..."); // plain console strings use pm_str!
+//! ```
+//! 
+//! Everywhere else (host tests) the data is a normal static, read normally.
+//! `scripts/size-report.sh` checks that none of it ends up in `.data`, and
+//! CI runs it on every push.
+
+use core::ops::Range;
+
+/// Put a byte slice into flash, as a [`PmBytes`].
+/// 
+/// The argument has to be a constant `&[u8]`. Wrap it in a `const` rather
+/// than a `static` (e.g. `const LOGO: PmBytes = progmem!(...)`), otherwise
+/// the handle itself still ends up in SRAM.
+#[macro_export]
+macro_rules! progmem {
+    ($data:expr) => {{
+        const DATA: &[u8] = $data;
+        
+        #[cfg_attr(target_arch = "avr", link_section = ".progmem.data")]
+        static FLASH: [u8; DATA.len()] = $crate::progmem::to_array(DATA);
+        
+        // SAFETY: FLASH is in program memory, and never goes away
+        unsafe { $crate::progmem::PmBytes::from_raw_parts(&FLASH as *const [u8; DATA.len()] as *const u8, DATA.len()) }
+    }};
+}
+
+/// Put a string into flash, as a [`PmStr`].
+#[macro_export]
+macro_rules! pm_str {
+    ($s:expr) => {{
+        const S: &str = $s;
+        let bytes = $crate::progmem!(S.as_bytes());
+        // SAFETY: the bytes come from a `str`
+        unsafe { $crate::progmem::PmStr::from_utf8_unchecked(bytes) }
+    }};
+}
+
+/// Used by [`progmem!`] to turn its argument into an array.
+#[doc(hidden)]
+pub const fn to_array<const N: usize>(data: &[u8]) -> [u8; N] {
+    let mut array = [0; N];
+    let mut index = 0;
+    while index < N {
+        array[index] = data[index];
+        index += 1;
+    }
+    array
+}
+
+/// Read one byte of program memory.
+#[cfg(target_arch = "avr")]
+unsafe fn read_byte(address: *const u8) -> u8 {
+    let byte;
+    // the ATmega328p has 32 KiB of flash, so Z reaches all of it and there's
+    // no need for `elpm`
+    core::arch::asm!(
+        "lpm {byte}, Z",
+        byte = out(reg) byte,
+        in("Z") address,
+        options(pure, readonly, nostack, preserves_flags),
+    );
+    byte
+}
+
+#[cfg(not(target_arch = "avr"))]
+unsafe fn read_byte(address: *const u8) -> u8 {
+    address.read()
+}
+
+/// A `[u8]` in program memory. Cheap to copy, like a slice reference.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PmBytes {
+    start: *const u8,
+    len: usize,
+}
+
+// SAFETY: the bytes are in flash, so nothing can change them
+unsafe impl Sync for PmBytes {}
+unsafe impl Send for PmBytes {}
+
+impl PmBytes {
+    /// # Safety
+    /// 
+    /// `start` has to point at `len` bytes of program memory that live
+    /// forever. Use [`progmem!`] instead.
+    pub const unsafe fn from_raw_parts(start: *const u8, len: usize) -> Self {
+        Self { start, len }
+    }
+    
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+    
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+    
+    /// The byte at `index`, if there is one.
+    pub fn get(&self, index: usize) -> Option<u8> {
+        // SAFETY: the index is in bounds
+        (index < self.len).then(|| unsafe { read_byte(self.start.add(index)) })
+    }
+    
+    /// The bytes in `range`. Panics if it's out of bounds, like slicing does.
+    pub fn slice(&self, range: Range<usize>) -> PmBytes {
+        assert!(range.start <= range.end && range.end <= self.len, "progmem slice out of range");
+        // SAFETY: still within the same flash data
+        unsafe { PmBytes::from_raw_parts(self.start.add(range.start), range.end - range.start) }
+    }
+    
+    /// Copy as many bytes as fit into `buffer`, and return the part of it
+    /// that was filled.
+    pub fn load_into<'b>(&self, buffer: &'b mut [u8]) -> &'b [u8] {
+        let len = self.len.min(buffer.len());
+        for (index, byte) in buffer[..len].iter_mut().enumerate() {
+            // SAFETY: index < self.len
+            *byte = unsafe { read_byte(self.start.add(index)) };
+        }
+        &buffer[..len]
+    }
+    
+    /// Iterate over pieces of at most `size` bytes, like `<[u8]>::chunks`.
+    pub fn chunks(&self, size: usize) -> impl Iterator<Item = PmBytes> {
+        assert!(size > 0, "chunk size must be non-zero");
+        let bytes = *self;
+        (0..self.len).step_by(size).map(move |start| bytes.slice(start..(start + size).min(bytes.len)))
+    }
+    
+    pub fn iter(&self) -> impl Iterator<Item = u8> {
+        let bytes = *self;
+        // SAFETY: index < self.len
+        (0..self.len).map(move |index| unsafe { read_byte(bytes.start.add(index)) })
+    }
+}
+
+/// A `str` in program memory.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PmStr(PmBytes);
+
+impl PmStr {
+    /// # Safety
+    /// 
+    /// `bytes` has to be valid UTF-8. Use [`pm_str!`] instead.
+    pub const unsafe fn from_utf8_unchecked(bytes: PmBytes) -> Self {
+        Self(bytes)
+    }
+    
+    pub const fn as_bytes(&self) -> PmBytes {
+        self.0
+    }
+    
+    pub const fn len(&self) -> usize {
+        self.0.len()
+    }
+    
+    pub const fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+    
+    /// Write the string out a few bytes at a time, without ever having all of
+    /// it in SRAM.
+    pub fn write_to<W: ufmt::uWrite + ?Sized>(&self, out: &mut W) -> Result<(), W::Error> {
+        self.try_for_each_chunk(|chunk| out.write_str(chunk))
+    }
+    
+    /// Load the string into SRAM a few bytes at a time, and hand each piece
+    /// to `f`.
+    pub fn try_for_each_chunk<E>(&self, mut f: impl FnMut(&str) -> Result<(), E>) -> Result<(), E> {
+        let mut buffer = [0; 16];
+        let mut rest = self.0;
+        
+        while !rest.is_empty() {
+            let mut len = rest.len().min(buffer.len());
+            // don't cut a character in half
+            while rest.get(len).is_some_and(|byte| byte & 0xC0 == 0x80) {
+                len -= 1;
+            }
+            
+            let chunk = rest.slice(0..len).load_into(&mut buffer);
+            // SAFETY: the string is valid UTF-8, and chunks end on character
+            // boundaries
+            f(unsafe { core::str::from_utf8_unchecked(chunk) })?;
+            rest = rest.slice(len..rest.len());
+        }
+        
+        Ok(())
+    }
+}
+
+/// Lets flash strings be formatted, e.g. `println!("{}", pm_str!("..."))`.
+impl ufmt::uDisplay for PmStr {
+    fn fmt<W: ufmt::uWrite + ?Sized>(&self, f: &mut ufmt::Formatter<'_, W>) -> Result<(), W::Error> {
+        self.try_for_each_chunk(|chunk| f.write_str(chunk))
+    }
+}
@@ -1,10 +1,11 @@
 use arduino_hal::simple_pwm::{IntoPwmPin, Timer0Pwm};
 use avr_device::interrupt;
 use avr_hal_generic::port::{Pin, mode};
-use core::cell::RefCell;
+use core::cell::{Cell, RefCell};
 
 
-const REV_POWER: u8 = 127; // 50% duty cycle
+/// Duty cycle the rev motors run at. Changed from the settings menu.
+static REV_POWER: interrupt::Mutex<Cell<u8>> = interrupt::Mutex::new(Cell::new(127)); // 50% duty cycle
 
 type RevButtonPinType = Pin<mode::Input<mode::Floating>, arduino_hal::hal::port::PD2>;
 static REV_BUTTON_PIN: interrupt::Mutex<RefCell<Option<RevButtonPinType>>> = interrupt::Mutex::new(RefCell::new(None));
@@ -22,7 +23,7 @@ fn INT0() {
     interrupt::free(|cs| {
         if let Some(rev_pin) = REV_BUTTON_PIN.borrow(cs).borrow_mut().as_mut() {
             if let Some(motor_pin) = REV_MOTOR_PIN.borrow(cs).borrow_mut().as_mut() {
-                set_rev_motors(rev_pin, motor_pin)
+                set_rev_motors(rev_pin, motor_pin, REV_POWER.borrow(cs).get())
             }
             else { panic!("Motor pin not available!") }
         }
@@ -31,13 +32,29 @@ fn INT0() {
 }
 
 #[inline(always)]
-fn set_rev_motors(rev_pin: &mut RevButtonPinType, motor_pin: &mut RevMotorPinType) {
-    if rev_pin.is_high() {
+fn set_rev_motors(rev_pin: &mut RevButtonPinType, motor_pin: &mut RevMotorPinType, power: u8) {
+    let revving = rev_pin.is_high();
+    if revving {
         motor_pin.enable();
-        motor_pin.set_duty(REV_POWER);
+        motor_pin.set_duty(power);
     } else {
         motor_pin.disable();
     }
+    crate::blaster::update(|state| state.revving = revving);
+}
+
+/// Change the duty cycle of the rev motors. If they're running it takes
+/// effect straight away.
+pub fn set_power(power: u8) {
+    interrupt::free(|cs| {
+        REV_POWER.borrow(cs).set(power);
+        
+        if let Some(rev_pin) = REV_BUTTON_PIN.borrow(cs).borrow_mut().as_mut() {
+            if let Some(motor_pin) = REV_MOTOR_PIN.borrow(cs).borrow_mut().as_mut() {
+                set_rev_motors(rev_pin, motor_pin, power)
+            }
+        }
+    });
 }
 
 #[inline(always)]
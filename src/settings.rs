@@ -0,0 +1,140 @@
+//! Settings that can be changed in the field, from the on-screen menu.
+//! 
+//! They only live in RAM, so a power cycle brings back [`Settings::DEFAULT`].
+
+use crate::progmem::PmStr;
+use crate::rev_motors;
+use crate::ssd1306::menu::{Action, ChoicePicker, Item, ItemKind, Menu, NumberEditor};
+use crate::ssd1306::Display;
+
+/// How the trigger fires.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FireMode {
+    Single,
+    Burst,
+    Auto,
+}
+
+impl FireMode {
+    const ALL: [FireMode; 3] = [FireMode::Single, FireMode::Burst, FireMode::Auto];
+    
+    pub fn name(self) -> PmStr {
+        FIRE_MODES[self as usize]
+    }
+}
+
+/// Names of the fire modes, in the order of [`FireMode::ALL`].
+const FIRE_MODES: &[PmStr] = &[crate::pm_str!("SINGLE"), crate::pm_str!("BURST"), crate::pm_str!("AUTO")];
+
+pub struct Settings {
+    /// Rev motor speed, in percent.
+    pub rev_power: u8,
+    
+    pub fire_mode: FireMode,
+    
+    /// Darts in a full magazine.
+    pub magazine_size: u8,
+    
+    pub contrast: u8,
+    pub inverted: bool,
+}
+
+impl Settings {
+    pub const DEFAULT: Settings = Settings {
+        rev_power: 50,
+        fire_mode: FireMode::Single,
+        magazine_size: 18,
+        contrast: 0x5F,
+        inverted: false,
+    };
+    
+    /// Push the settings out to the hardware. Call this after the menu
+    /// reports a change.
+    pub fn apply<D: Display>(&self, display: Option<&mut D>) -> Result<(), D::Error> {
+        rev_motors::set_power((self.rev_power as u16 * 255 / 100) as u8);
+        #[cfg(feature = "fire-control")]
+        crate::blaster::update(|state| state.fire_mode = self.fire_mode);
+        
+        if let Some(display) = display {
+            display.set_contrast(self.contrast)?;
+            display.set_inverted(self.inverted)?;
+        }
+        
+        Ok(())
+    }
+}
+
+const OFF_ON: &[PmStr] = &[crate::pm_str!("OFF"), crate::pm_str!("ON")];
+
+const DISPLAY_MENU: Menu<Settings> = Menu {
+    title: crate::pm_str!("DISPLAY"),
+    items: &[
+        Item {
+            label: crate::pm_str!("Contrast"),
+            kind: ItemKind::Number(NumberEditor {
+                min: 0,
+                max: 255,
+                step: 16,
+                unit: crate::pm_str!(""),
+                get: |settings| settings.contrast as i32,
+                set: |settings, value| settings.contrast = value as u8,
+            }),
+        },
+        Item {
+            label: crate::pm_str!("Invert"),
+            kind: ItemKind::Choice(ChoicePicker {
+                options: OFF_ON,
+                get: |settings| settings.inverted as usize,
+                set: |settings, index| settings.inverted = index == 1,
+            }),
+        },
+    ],
+};
+
+/// The menu tree, opened from the main screen.
+pub const MENU: Menu<Settings> = Menu {
+    title: crate::pm_str!("SETTINGS"),
+    items: &[
+        Item {
+            label: crate::pm_str!("Rev power"),
+            kind: ItemKind::Number(NumberEditor {
+                min: 10,
+                max: 100,
+                step: 5,
+                unit: crate::pm_str!("%"),
+                get: |settings| settings.rev_power as i32,
+                set: |settings, value| settings.rev_power = value as u8,
+            }),
+        },
+        Item {
+            label: crate::pm_str!("Fire mode"),
+            kind: ItemKind::Choice(ChoicePicker {
+                options: FIRE_MODES,
+                get: |settings| settings.fire_mode as usize,
+                set: |settings, index| settings.fire_mode = FireMode::ALL[index],
+            }),
+        },
+        Item {
+            label: crate::pm_str!("Magazine"),
+            kind: ItemKind::Number(NumberEditor {
+                min: 1,
+                max: 99,
+                step: 1,
+                unit: crate::pm_str!(""),
+                get: |settings| settings.magazine_size as i32,
+                set: |settings, value| settings.magazine_size = value as u8,
+            }),
+        },
+        Item {
+            label: crate::pm_str!("Display"),
+            kind: ItemKind::Submenu(&DISPLAY_MENU),
+        },
+        Item {
+            label: crate::pm_str!("Reset all"),
+            kind: ItemKind::Action(Action {
+                confirm: Some(crate::pm_str!("Reset settings?")),
+                run: |settings| *settings = Settings::DEFAULT,
+            }),
+        },
+    ],
+};
@@ -0,0 +1,185 @@
+//! Frame animations kept in flash, played without blocking the main loop.
+//! 
+//! Animations are normally generated from a directory of frames in `assets/`
+//! by the build script, see `crate::assets`. A [`Player`] draws the frames
+//! as they come due; call [`Player::update`] from the main loop (or anything
+//! else that runs regularly) with the time since the last call.
+//! 
+//! ```ignore
+//! let mut player = Player::new();
+//! player.play(assets::RELOAD, Page::Page2, 52, false);
+//! loop {
+//!     // handle input...
+//!     player.update(&mut display, TICK_MS)?;
+//!     delay_ms(TICK_MS);
+//! }
+//! ```
+
+use crate::progmem::PmBytes;
+
+use super::command::Page;
+use super::display::Display;
+use super::error::InvalidParameter;
+use super::ui::Area;
+
+/// A frame's run count, for a key frame.
+pub const KEY_FRAME: u8 = 0xFF;
+
+/// A `width` x `height` animation.
+/// 
+/// `data` holds the frames back to back. Each one starts with how long it's
+/// shown, in milliseconds (a little-endian `u16`), and a run count:
+/// 
+/// - [`KEY_FRAME`]: the whole image follows, a page at a time like a
+///   [`Bitmap`](super::Bitmap).
+/// - anything else: that many runs of changed bytes follow, each one a page
+///   and a column (relative to the animation) and a length, then the bytes.
+///   The rest of the image stays as the previous frame left it.
+/// 
+/// The first frame has to be a key frame, so that looping starts from a
+/// known image.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Animation {
+    pub width: u8,
+    pub height: u8,
+    pub data: PmBytes,
+}
+
+impl Animation {
+    /// Number of pages the animation spans.
+    pub const fn pages(&self) -> u8 {
+        self.height.div_ceil(8)
+    }
+    
+    /// `len` bytes of `data` from `start`, if the data is that long.
+    fn bytes(&self, start: usize, len: usize) -> Result<PmBytes, InvalidParameter> {
+        if start + len > self.data.len() {
+            return Err(InvalidParameter::BufferLength);
+        }
+        Ok(self.data.slice(start..start + len))
+    }
+    
+    /// Draw the frame at `offset` in `data` into `area`. Returns how long it
+    /// should be shown and where the next frame starts.
+    fn draw_frame<D>(&self, display: &mut D, area: Area, offset: usize) -> Result<(u16, usize), D::Error>
+    where D: Display, D::Error: From<InvalidParameter> {
+        let header = self.bytes(offset, 3)?;
+        let duration = u16::from_le_bytes([header.get(0).unwrap_or(0), header.get(1).unwrap_or(0)]);
+        let runs = header.get(2).unwrap_or(0);
+        let mut offset = offset + 3;
+        
+        if runs == KEY_FRAME {
+            for page in 0..area.pages {
+                let target = Page::try_from(area.page as u8 + page)?;
+                display.write_page_flash(target, area.col, self.bytes(offset, area.width as usize)?)?;
+                offset += area.width as usize;
+            }
+            return Ok((duration, offset));
+        }
+        
+        for _ in 0..runs {
+            let run = self.bytes(offset, 3)?;
+            let (page, col, len) = (run.get(0).unwrap_or(0), run.get(1).unwrap_or(0), run.get(2).unwrap_or(0));
+            if page >= area.pages {
+                return Err(InvalidParameter::Page.into());
+            }
+            if col as usize + len as usize > area.width as usize {
+                return Err(InvalidParameter::Column.into());
+            }
+            
+            let target = Page::try_from(area.page as u8 + page)?;
+            display.write_page_flash(target, area.col + col, self.bytes(offset + 3, len as usize)?)?;
+            offset += 3 + len as usize;
+        }
+        
+        Ok((duration, offset))
+    }
+}
+
+/// Plays one [`Animation`] at a time.
+/// 
+/// Frames are never skipped, since a delta frame only makes sense on top of
+/// the one before it. If updates come late, the animation runs slow.
+pub struct Player {
+    animation: Option<Animation>,
+    area: Area,
+    looping: bool,
+    
+    /// Where the next frame starts in the animation's data.
+    next: usize,
+    
+    /// Time left until the next frame is due.
+    wait_ms: u16,
+}
+
+impl Player {
+    pub const fn new() -> Self {
+        Self {
+            animation: None,
+            area: Area::new(Page::Page0, 0, 0, 0),
+            looping: false,
+            next: 0,
+            wait_ms: 0,
+        }
+    }
+    
+    /// Start `animation` with its top left corner at column `col` of `page`,
+    /// replacing whatever was playing. The first frame is drawn by the next
+    /// [`update`](Self::update).
+    /// 
+    /// A looping animation plays until [`stop`](Self::stop)ped. Otherwise the
+    /// last frame stays up for its duration, then the player goes idle and
+    /// leaves it on the display.
+    pub fn play(&mut self, animation: Animation, page: Page, col: u8, looping: bool) {
+        self.animation = Some(animation);
+        self.area = Area::new(page, col, animation.width, animation.pages());
+        self.looping = looping;
+        self.next = 0;
+        self.wait_ms = 0;
+    }
+    
+    /// Stop playing. Whatever frame is up stays on the display.
+    pub fn stop(&mut self) {
+        self.animation = None;
+    }
+    
+    pub fn is_playing(&self) -> bool {
+        self.animation.is_some()
+    }
+    
+    /// Let `elapsed_ms` pass, and draw the next frame if it's due. Returns
+    /// whether anything was drawn.
+    /// 
+    /// At most one frame is drawn per call, so this never holds up the
+    /// caller for longer than one frame's writes.
+    pub fn update<D>(&mut self, display: &mut D, elapsed_ms: u16) -> Result<bool, D::Error>
+    where D: Display, D::Error: From<InvalidParameter> {
+        let Some(animation) = self.animation else { return Ok(false) };
+        
+        self.wait_ms = self.wait_ms.saturating_sub(elapsed_ms);
+        if self.wait_ms > 0 {
+            return Ok(false);
+        }
+        
+        if self.next >= animation.data.len() {
+            if !self.looping {
+                self.animation = None;
+                return Ok(false);
+            }
+            self.next = 0;
+        }
+        
+        self.area.check::<D::Size>()?;
+        let (duration, next) = animation.draw_frame(display, self.area, self.next)?;
+        self.next = next;
+        self.wait_ms = duration;
+        
+        Ok(true)
+    }
+}
+
+impl Default for Player {
+    fn default() -> Self {
+        Self::new()
+    }
+}
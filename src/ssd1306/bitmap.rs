@@ -0,0 +1,70 @@
+//! Monochrome images in the display's page order.
+//! 
+//! Bitmaps are normally generated from the files in `assets/` by the build
+//! script, see `crate::assets`. Their data is kept in flash.
+
+use crate::progmem::PmBytes;
+
+use super::canvas::Canvas;
+use super::command::Page;
+use super::display::Display;
+use super::error::InvalidParameter;
+use super::size::DisplaySize;
+
+/// A `width` x `height` image, stored a page (8 rows) at a time: `width`
+/// bytes for rows 0-7, then `width` bytes for rows 8-15 and so on, LSB at
+/// the top. The last page is padded with zero rows.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bitmap {
+    pub width: u8,
+    pub height: u8,
+    pub data: PmBytes,
+}
+
+impl Bitmap {
+    /// Number of pages the image spans.
+    pub const fn pages(&self) -> u8 {
+        self.height.div_ceil(8)
+    }
+    
+    /// The bytes of page `page` of the image, one per column.
+    pub fn page(&self, page: u8) -> PmBytes {
+        let start = page as usize * self.width as usize;
+        self.data.slice(start..start + self.width as usize)
+    }
+    
+    /// Whether the pixel at `(x, y)` is set. Pixels outside the image aren't.
+    pub fn get_pixel(&self, x: u8, y: u8) -> bool {
+        if x >= self.width || y >= self.height {
+            return false;
+        }
+        
+        let index = (y / 8) as usize * self.width as usize + x as usize;
+        self.data.get(index).is_some_and(|byte| byte & (1 << (y % 8)) != 0)
+    }
+    
+    /// Draw the image with its top left corner at `(x, y)`.
+    pub fn draw(&self, canvas: &mut dyn Canvas, x: u8, y: u8) {
+        for dy in 0..self.height {
+            for dx in 0..self.width {
+                canvas.set_pixel(x.saturating_add(dx), y.saturating_add(dy), self.get_pixel(dx, dy));
+            }
+        }
+    }
+    
+    /// Write the image straight to `display`, with its top left corner at
+    /// column `col` of `page`. One write per page.
+    pub fn write_to<D>(&self, display: &mut D, page: Page, col: u8) -> Result<(), D::Error>
+    where D: Display, D::Error: From<InvalidParameter> {
+        if page as u8 + self.pages() > D::Size::PAGES {
+            return Err(InvalidParameter::Page.into());
+        }
+        
+        for offset in 0..self.pages() {
+            let target = Page::try_from(page as u8 + offset)?;
+            display.write_page_flash(target, col, self.page(offset))?;
+        }
+        
+        Ok(())
+    }
+}
@@ -0,0 +1,40 @@
+//! Drawing API shared by the framebuffer and the strip renderer.
+//! 
+//! Screens are built from [`Drawable`]s that only ever talk to a [`Canvas`],
+//! so the same screen can be drawn into a full [`FrameBuffer`] or rendered a
+//! page at a time by [`strip::render`] on builds that can't spare the RAM.
+//! 
+//! [`FrameBuffer`]: super::FrameBuffer
+//! [`strip::render`]: super::strip::render
+
+/// Something that can be drawn on pixel by pixel.
+pub trait Canvas {
+    /// Width and height of the whole canvas, in pixels.
+    fn size(&self) -> (u8, u8);
+    
+    /// Turn the pixel at `(x, y)` on or off. Pixels outside the canvas (or
+    /// outside the part of it that's currently being rendered) are ignored.
+    fn set_pixel(&mut self, x: u8, y: u8, on: bool);
+    
+    /// Turn every pixel on or off.
+    fn fill(&mut self, on: bool);
+    
+    /// Turn every pixel off.
+    fn clear(&mut self) {
+        self.fill(false)
+    }
+}
+
+/// Part of a screen.
+/// 
+/// In strip mode this gets drawn once per page, onto a canvas that only keeps
+/// the pixels of that page, so it has to draw the same thing every time.
+pub trait Drawable {
+    fn draw(&self, canvas: &mut dyn Canvas);
+}
+
+impl<F> Drawable for F where F: Fn(&mut dyn Canvas) {
+    fn draw(&self, canvas: &mut dyn Canvas) {
+        self(canvas)
+    }
+}
@@ -1,3 +1,5 @@
+use super::error::InvalidParameter;
+
 
 #[derive(Debug, Copy, Clone)]
 pub enum Command {
@@ -46,7 +48,8 @@ pub enum Command {
     /// 
     /// `interval`: Time interval between each scroll step (in terms of frame frequency).
     /// 
-    /// TODO: `start_page` and `end_page`
+    /// `start_page` and `end_page`: The band of pages that scrolls. Pages
+    /// outside of it stay put.
     SetupHorizontalScroll {
         /// Direction of scrolling (right/left).
         direction: HorizontalScroll,
@@ -54,16 +57,15 @@ pub enum Command {
         /// Time interval between each scroll step (in terms of frame frequency).
         interval: ScrollInterval,
         
-        /// "Define start page address". (TODO: what does this mean?)
+        /// First page of the scrolling band.
         start_page: Page,
         
-        /// "Define end page address".
+        /// Last page of the scrolling band.
         /// 
         /// `start_page` must be less than or equal to `end_page`.
-        /// 
-        /// TODO: make this unsafe somehow?
+        /// `SSD1306::scroll` checks this before sending anything.
         end_page: Page
-    },
+    }, // 0x26 | 0x27
     
     /// ### Continuous Vertical and Horizontal Scroll Setup.
     /// 
@@ -80,25 +82,25 @@ pub enum Command {
         /// Time interval between each scroll step (in terms of frame frequency).
         interval: ScrollInterval,
         
-        /// TODO: doc
+        /// First page of the horizontally scrolling band.
         start_page: Page,
         
-        /// TODO: doc
+        /// Last page of the horizontally scrolling band.
         end_page: Page,
         
         /// The vertical scrolling offset. (0-63)
         offset: u8
-    },
+    }, // 0x29 | 0x2A
     
     /// ### Activate/Deactivate Scrolling.
     /// 
     /// Starts or ends scrolling configured by the `SetupHorizontalScroll` and
-    /// `VerticalHorizontalScrollSetup` commands.
+    /// `SetupVerticalAndHorizontalScroll` commands.
     /// 
     /// `false`: Deactivate scrolling.
     /// 
     /// `true`: Activate scrolling.
-    ///
+    /// 
     /// **Note:**
     /// 1. After deactivating scrolling, the RAM data needs to be rewritten.
     /// 2. Activation of scrolling must occur *directly* after the setup.  
@@ -116,8 +118,8 @@ pub enum Command {
     /// **Note:**
     /// 1. `top + bottom <= MUX_RATIO`.
     /// 2. `bottom <= MUX_RATIO`.
-    /// 3. ​`offset` (from `VerticalHorizontalScrollSetup`) `< bottom`.
-    /// 4. Set Display Start Line (from `SetDisplayStartLine`) `<= bottom`.
+    /// 3. `offset` (from `SetupVerticalAndHorizontalScroll`) `< bottom`.
+    /// 4. Set Display Start Line (from `SetStartLine`) `< bottom`.
     /// 5. The last row of the scroll area shifts to the first row of the scroll area.
     /// 6. For `MUX_RATIO=64` display:
     ///     * `top = 0`, `bottom = 64` : whole area scrolls
@@ -127,74 +129,6 @@ pub enum Command {
     SetupVerticalScrollArea { top: u8, bottom: u8 }, // 0xA3
     
     
-    // Addressing Setting Commands
-    // ===========================
-    
-    /// ### Set Lower Column Start Address for Page Addressing Mode.
-    /// 
-    /// Set the lower nibble of the column start address register for
-    /// Page Addressing Mode using `.0[3:0]` as data bits. The initial
-    /// display line register is reset to 0000b after RESET.
-    /// 
-    /// **NOTE:** This command is only for page addressing mode.
-    LowerColumnStart(u8), // 0x00-0x0F
-    
-    /// ### Set Higher Column Start Address for Page Addressing Mode.
-    /// 
-    /// Set the higher nibble of the column start address register for
-    /// Page Addressing Mode using `.0[3:0]` as data bits. The initial
-    /// display line register is reset to 0000b after RESET.
-    /// 
-    /// **NOTE:** This command is only for page addressing mode.
-    UpperColumnStart(u8), // 0x10-0x1F
-    
-    /// ### Set Column Start Address for Page Addressing Mode.
-    /// 
-    /// Convenience command to set the entire column start address register
-    /// for Page Addressing Mode in a single command.
-    /// 
-    /// **NOTE:** This command is only for page addressing mode.
-    ColumnStart(u8), // LowerColumnStart + UpperColumnStart
-    
-    /// ### Set Memory Addressing Mode.
-    /// 
-    /// `AddressMode::Horizontal`:
-    /// 
-    /// `AddressMode::Vertical`:
-    /// 
-    /// `AddressMode::Page`: (RESET)
-    SetAddressMode(AddressMode), // 0x20
-    
-    /// ### Set Column Address.
-    /// 
-    /// Setup column start and end address
-    /// 
-    /// `start`: Column start address, range: 0-127 (RESET=0)
-    /// 
-    /// `end`: Column end address, range: 0-127 (RESET=127)
-    /// 
-    /// **NOTE:** This is only for horizontal/vertical addressing mode.
-    SetColumnAddress{start_col: u8, end_col: u8}, // 0x21
-    
-    /// ### Set Page Address.
-    /// 
-    /// Setup page start and end address
-    /// 
-    /// `start_page`: Page start address (RESET=Page::Page0)
-    /// 
-    /// `end_page`: Page end address (RESET=Page::Page7)
-    /// 
-    /// **NOTE:** This is only for page addressing mode.
-    SetPageAddress{start_page: Page, end_page: Page}, // 0x22
-    
-    /// ### Set Page Start Address for Page Addressing Mode.
-    /// 
-    /// Set GDDRAM Page Start Address (PAGE0~PAGE7) for Page Addressing Mode.
-    /// 
-    /// **NOTE:** This command is only for page addressing mode.
-    PageStart(Page), // 0xB0-0xB7
-    
-    
     // Hardware Configuration (Panel resolution & layout related) Commands
     // ==================================================================
     
@@ -218,15 +152,36 @@ pub enum Command {
     
     /// ### Set COM Output Scan Direction
     /// 
-    /// TODO
+    /// `false`: normal mode, scan from COM0 to COM[N-1] (RESET)
+    /// 
+    /// `true`: remapped mode, scan from COM[N-1] to COM0
+    /// 
+    /// **NOTE:** This takes effect immediately, flipping the displayed
+    /// image vertically without rewriting the RAM.
     SetComScanDir(bool), // 0xC0 | 0xC8
     
-    // SetupVerticalScroll(...), // 0x28 | 0x29
-    EnableScroll(bool),
-    // SetupVerticalScrollArea(u8, u8), // 0xA3
-    // InternalIref(bool, bool), // 0xAD
-    // DisplayOffset(u8), // 0xD3
-    // ComPinConfig(u8), // 0xDA
+    /// ### Set Display Offset.
+    /// 
+    /// Set the vertical shift by COM, from 0-63. (RESET=0)
+    DisplayOffset(u8), // 0xD3
+    
+    /// ### Set COM Pins Hardware Configuration.
+    /// 
+    /// `alternative`: `false` for sequential COM pin configuration,
+    /// `true` for alternative COM pin configuration (RESET).
+    /// 
+    /// `remap`: `true` to enable COM left/right remap. (RESET=false)
+    /// 
+    /// **NOTE:** This depends on how the panel is wired to the controller,
+    /// so the right value comes from the module's datasheet.
+    ComPinConfig{alternative: bool, remap: bool}, // 0xDA
+    
+    /// ### Set Internal IREF.
+    /// 
+    /// Select where the segment reference current comes from. Only
+    /// available on newer revisions of the controller.
+    /// (RESET = `IrefSelection::External`)
+    InternalIref(IrefSelection), // 0xAD
     
     
     // Timing & Driving Scheme Setting Command Table
@@ -237,10 +192,15 @@ pub enum Command {
     /// both values are 0-15, real divide ratio is `divide_ratio+1`
     DisplayClockDiv{oscillator_freq: u8, divide_ratio: u8}, // 0xD5
     
-    /// top nibble is phase2, bottom nibble is phase1
-    PreChargePeriod(u8), // 0xD9
+    /// ### Set Pre-charge Period.
+    /// 
+    /// Both phases are in units of DCLK, from 1-15. (RESET=2 for both)
+    PreChargePeriod{phase1: u8, phase2: u8}, // 0xD9
     
-    // VcomhDeselect(VcomhLevel), // 0xDB
+    /// ### Set VCOMH Deselect Level.
+    /// 
+    /// (RESET = `VcomhLevel::V077`)
+    VcomhDeselect(VcomhLevel), // 0xDB
     
     /// ### Command for no operation (NOP).
     NoOp, // 0xE3
@@ -259,6 +219,225 @@ pub enum Command {
     ChargePump(bool), // 0x8D
 }
 
+/// Addressing commands that only apply in page addressing mode.
+/// 
+/// These can only be sent through an `SSD1306` in `PageAddressing` mode.
+#[derive(Debug, Copy, Clone)]
+pub enum PageCommand {
+    /// ### Set Lower Column Start Address for Page Addressing Mode.
+    /// 
+    /// Set the lower nibble of the column start address register for
+    /// Page Addressing Mode using `.0[3:0]` as data bits. The initial
+    /// display line register is reset to 0000b after RESET.
+    LowerColumnStart(u8), // 0x00-0x0F
+    
+    /// ### Set Higher Column Start Address for Page Addressing Mode.
+    /// 
+    /// Set the higher nibble of the column start address register for
+    /// Page Addressing Mode using `.0[3:0]` as data bits. The initial
+    /// display line register is reset to 0000b after RESET.
+    UpperColumnStart(u8), // 0x10-0x1F
+    
+    /// ### Set Column Start Address for Page Addressing Mode.
+    /// 
+    /// Convenience command to set the entire column start address register
+    /// for Page Addressing Mode in a single command.
+    ColumnStart(u8), // LowerColumnStart + UpperColumnStart
+    
+    /// ### Set Page Start Address for Page Addressing Mode.
+    /// 
+    /// Set GDDRAM Page Start Address (PAGE0~PAGE7) for Page Addressing Mode.
+    PageStart(Page), // 0xB0-0xB7
+}
+
+/// Addressing commands that only apply in horizontal/vertical addressing mode.
+/// 
+/// These can only be sent through an `SSD1306` in `HorizontalAddressing` or
+/// `VerticalAddressing` mode.
+#[derive(Debug, Copy, Clone)]
+pub enum WindowCommand {
+    /// ### Set Column Address.
+    /// 
+    /// Setup column start and end address
+    /// 
+    /// `start`: Column start address, range: 0-127 (RESET=0)
+    /// 
+    /// `end`: Column end address, range: 0-127 (RESET=127)
+    SetColumnAddress{start_col: u8, end_col: u8}, // 0x21
+    
+    /// ### Set Page Address.
+    /// 
+    /// Setup page start and end address
+    /// 
+    /// `start_page`: Page start address (RESET=Page::Page0)
+    /// 
+    /// `end_page`: Page end address (RESET=Page::Page7)
+    SetPageAddress{start_page: Page, end_page: Page}, // 0x22
+}
+
+/// The bytes of a single encoded command, without the I2C control byte.
+#[derive(Debug, Clone, Copy)]
+pub struct EncodedCommand {
+    bytes: [u8; EncodedCommand::MAX_LEN],
+    len: u8,
+}
+
+impl EncodedCommand {
+    /// Length of the longest command (`SetupHorizontalScroll`).
+    pub const MAX_LEN: usize = 7;
+    
+    pub(super) const fn new(src: &[u8]) -> Self {
+        let mut bytes = [0; Self::MAX_LEN];
+        let mut i = 0;
+        while i < src.len() {
+            bytes[i] = src[i];
+            i += 1;
+        }
+        Self { bytes, len: src.len() as u8 }
+    }
+    
+    pub const fn len(&self) -> usize {
+        self.len as usize
+    }
+    
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+    
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes[..self.len()]
+    }
+}
+
+macro_rules! cmd {
+    [$($x:expr),+ $(,)?] => { EncodedCommand::new(&[$($x),+]) }
+}
+
+impl Command {
+    /// Encode the command into the bytes the controller expects.
+    pub const fn encode(self) -> EncodedCommand {
+        match self {
+            // Fundamental commands
+            Command::SetContrast{contrast} => cmd![0x81, contrast],
+            Command::AllPixelsOn(enable) => cmd![0xA4 | (enable as u8)],
+            Command::InvertDisplay(enable) => cmd![0xA6 | (enable as u8)],
+            Command::DisplayEnable(enable) => cmd![0xAE | (enable as u8)],
+            
+            // Scrolling commands
+            Command::SetupHorizontalScroll {
+                direction: dir,
+                interval,
+                start_page: start,
+                end_page: end,
+            } => cmd![0x26 | dir as u8, 0x00, start as u8, interval as u8, end as u8, 0x00, 0xFF],
+            Command::SetupVerticalAndHorizontalScroll {
+                direction: vdir,
+                interval,
+                start_page: start,
+                end_page: end,
+                offset,
+            } => cmd![0x28 | vdir as u8, 0x00, start as u8, interval as u8, end as u8, offset & 0x3F],
+            Command::EnableScrolling(enable) => cmd![0x2E | (enable as u8)],
+            Command::SetupVerticalScrollArea { top, bottom } => cmd![0xA3, top & 0x3F, bottom & 0x7F],
+            
+            // Hardware Configuration Commands
+            Command::SetStartLine(line) => cmd![0x40 | (line & 0x3F)],
+            Command::SegmentRemap(remap) => cmd![0xA0 | (remap as u8)],
+            Command::SetMultiplexRatio { ratio } => cmd![0xA8, ratio & 0x3F],
+            Command::SetComScanDir(remap) => cmd![0xC0 | ((remap as u8) << 3)],
+            Command::DisplayOffset(offset) => cmd![0xD3, offset & 0x3F],
+            Command::ComPinConfig { alternative, remap } => {
+                cmd![0xDA, 0x02 | ((alternative as u8) << 4) | ((remap as u8) << 5)]
+            }
+            Command::InternalIref(selection) => cmd![0xAD, (selection as u8) << 4],
+            
+            // Timing & Driving Scheme Setting Commands
+            Command::DisplayClockDiv {
+                oscillator_freq: osc,
+                divide_ratio: div
+            } => cmd![0xD5, ((osc & 0x0F) << 4) | (div & 0x0F)],
+            Command::PreChargePeriod { phase1, phase2 } => {
+                cmd![0xD9, ((phase2 & 0x0F) << 4) | (phase1 & 0x0F)]
+            }
+            Command::VcomhDeselect(level) => cmd![0xDB, (level as u8) << 4],
+            
+            // No operation
+            Command::NoOp => cmd![0xE3],
+            
+            // Charge pump commands
+            Command::ChargePump(enable) => cmd![0x8D, 0x10 | ((enable as u8) << 2)],
+        }
+    }
+}
+
+impl PageCommand {
+    /// Encode the command into the bytes the controller expects.
+    pub const fn encode(self) -> EncodedCommand {
+        match self {
+            // written like the others so the opcode shows
+            #[allow(clippy::identity_op)]
+            PageCommand::LowerColumnStart(addr) => cmd![0x00 | (addr & 0x0F)],
+            PageCommand::UpperColumnStart(addr) => cmd![0x10 | (addr & 0x0F)],
+            PageCommand::ColumnStart(addr) => cmd![0x0F & addr, 0x10 | ((addr >> 4) & 0x0F)],
+            PageCommand::PageStart(page) => cmd![0xB0 | (page as u8)],
+        }
+    }
+}
+
+impl WindowCommand {
+    /// Encode the command into the bytes the controller expects.
+    pub const fn encode(self) -> EncodedCommand {
+        match self {
+            WindowCommand::SetColumnAddress{start_col, end_col} => cmd![0x21, start_col & 0x7F, end_col & 0x7F],
+            WindowCommand::SetPageAddress{start_page, end_page} => cmd![0x22, start_page as u8, end_page as u8],
+        }
+    }
+}
+
+impl AddressMode {
+    /// Encode the Set Memory Addressing Mode (0x20) command for this mode.
+    /// 
+    /// This is only sent by the driver itself, since it has to keep track of
+    /// the mode. See `SSD1306::set_address_mode`.
+    pub const fn encode(self) -> EncodedCommand {
+        cmd![0x20, self as u8]
+    }
+}
+
+/// Total length of `commands` once encoded.
+pub const fn stream_len(commands: &[EncodedCommand]) -> usize {
+    let mut len = 0;
+    let mut i = 0;
+    while i < commands.len() {
+        len += commands[i].len();
+        i += 1;
+    }
+    len
+}
+
+/// Concatenate `commands` into a single command stream. This is a `const fn`
+/// so fixed sequences can be built at compile time.
+/// 
+/// `N` must be `stream_len(commands)`.
+pub const fn command_stream<const N: usize>(commands: &[EncodedCommand]) -> [u8; N] {
+    assert!(N == stream_len(commands), "command stream length mismatch");
+    
+    let mut stream = [0; N];
+    let mut len = 0;
+    let mut i = 0;
+    while i < commands.len() {
+        let encoded = commands[i];
+        let mut j = 0;
+        while j < encoded.len() {
+            stream[len] = encoded.bytes[j];
+            len += 1;
+            j += 1;
+        }
+        i += 1;
+    }
+    stream
+}
+
 /// Which direction to scroll (left or right).
 #[derive(Debug, Clone, Copy)]
 pub enum HorizontalScroll {
@@ -275,10 +454,10 @@ pub enum HorizontalScroll {
 #[derive(Debug, Clone, Copy)]
 pub enum VerticalHorizontalScroll {
     /// Scroll vertically and to the left
-    VerticalLeft = 0b01,
+    VerticalLeft = 0b10,
     
     /// Scroll vertically and to the right
-    VerticalRight = 0b10,
+    VerticalRight = 0b01,
 }
 
 /// How many frames to wait between each scroll step.
@@ -303,7 +482,17 @@ pub enum ScrollInterval {
 }
 
 
-#[derive(Debug, Clone, Copy)]
+/// ### Memory Addressing Mode.
+/// 
+/// `AddressMode::Horizontal`: the column pointer advances after every
+/// byte, wrapping to the next page at the end of the column window.
+/// 
+/// `AddressMode::Vertical`: the page pointer advances after every byte,
+/// wrapping to the next column at the end of the page window.
+/// 
+/// `AddressMode::Page`: the column pointer advances after every byte and
+/// wraps within the current page. (RESET)
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum AddressMode {
     /// Horizontal mode
     Horizontal = 0b00,
@@ -314,6 +503,29 @@ pub enum AddressMode {
 }
 
 
+/// VCOMH deselect level, as a fraction of VCC.
+#[derive(Debug, Clone, Copy)]
+pub enum VcomhLevel {
+    /// ~0.65 x VCC
+    V065 = 0b000,
+    /// ~0.77 x VCC (default)
+    V077 = 0b010,
+    /// ~0.83 x VCC
+    V083 = 0b011,
+    /// Undocumented level that most vendor init sequences use.
+    Auto = 0b100,
+}
+
+/// Source of the segment reference current.
+#[derive(Debug, Clone, Copy)]
+pub enum IrefSelection {
+    /// External IREF resistor (default)
+    External = 0b00,
+    /// Internal IREF, 19uA (max output 150uA)
+    Internal19uA = 0b10,
+    /// Internal IREF, 30uA (max output 240uA)
+    Internal30uA = 0b11,
+}
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Page {
@@ -326,19 +538,20 @@ pub enum Page {
     Page6 = 0b110,
     Page7 = 0b111,
 }
-impl From<u8> for Page {
-    fn from(val: u8) -> Page {
-        match val >> 3 {
-            0 => Page::Page0,
-            1 => Page::Page1,
-            2 => Page::Page2,
-            3 => Page::Page3,
-            4 => Page::Page4,
-            5 => Page::Page5,
-            6 => Page::Page6,
-            7 => Page::Page7,
-            _ => panic!("Page too high"),
+impl TryFrom<u8> for Page {
+    type Error = InvalidParameter;
+    
+    fn try_from(val: u8) -> Result<Page, InvalidParameter> {
+        match val {
+            0 => Ok(Page::Page0),
+            1 => Ok(Page::Page1),
+            2 => Ok(Page::Page2),
+            3 => Ok(Page::Page3),
+            4 => Ok(Page::Page4),
+            5 => Ok(Page::Page5),
+            6 => Ok(Page::Page6),
+            7 => Ok(Page::Page7),
+            _ => Err(InvalidParameter::Page),
         }
     }
 }
-
@@ -0,0 +1,299 @@
+//! Parsing encoded commands back into [`Command`]s, the inverse of `encode`.
+//! 
+//! Useful for logging bus traffic, and for replaying init sequences captured
+//! from other modules:
+//! 
+//! ```ignore
+//! let (command, len) = decode::Command::decode(&captured)?;
+//! for command in decode::commands(&captured[len..]) {
+//!     match command? { ... }
+//! }
+//! ```
+
+use crate::pm_str;
+use crate::progmem::PmStr;
+
+use super::command::{
+    self, AddressMode, EncodedCommand, HorizontalScroll, IrefSelection, Page, PageCommand, ScrollInterval,
+    VcomhLevel, VerticalHorizontalScroll, WindowCommand,
+};
+use super::error::DecodeError;
+
+/// Any command the controller understands, in whichever of the driver's
+/// enums it's sent as. The addressing commands are kept apart there, so that
+/// only the ones for the current addressing mode can be sent.
+#[derive(Debug, Clone, Copy)]
+pub enum Command {
+    /// Everything that works in any addressing mode.
+    General(command::Command),
+    Page(PageCommand),
+    Window(WindowCommand),
+    AddressMode(AddressMode),
+}
+
+impl Command {
+    /// Decode the command at the start of `bytes`, returning it and the
+    /// number of bytes it took. Dummy bytes in the scroll setups aren't
+    /// checked.
+    pub fn decode(bytes: &[u8]) -> Result<(Command, usize), DecodeError> {
+        let opcode = *bytes.first().ok_or(DecodeError::Empty)?;
+        match opcode {
+            0x00..=0x1F | 0xB0..=0xB7 => decode_page(bytes).map(|(command, len)| (Command::Page(command), len)),
+            0x20 => decode_address_mode(bytes).map(|(mode, len)| (Command::AddressMode(mode), len)),
+            0x21 | 0x22 => decode_window(bytes).map(|(command, len)| (Command::Window(command), len)),
+            _ => decode_general(bytes).map(|(command, len)| (Command::General(command), len)),
+        }
+    }
+    
+    pub fn encode(self) -> EncodedCommand {
+        match self {
+            Command::General(command) => command.encode(),
+            Command::Page(command) => command.encode(),
+            Command::Window(command) => command.encode(),
+            Command::AddressMode(mode) => mode.encode(),
+        }
+    }
+    
+    /// The command's name, for printing over the console.
+    pub fn name(&self) -> PmStr {
+        use command::Command as General;
+        
+        match self {
+            Command::General(command) => match command {
+                General::SetContrast { .. } => pm_str!("SetContrast"),
+                General::AllPixelsOn(_) => pm_str!("AllPixelsOn"),
+                General::InvertDisplay(_) => pm_str!("InvertDisplay"),
+                General::DisplayEnable(_) => pm_str!("DisplayEnable"),
+                General::SetupHorizontalScroll { .. } => pm_str!("SetupHorizontalScroll"),
+                General::SetupVerticalAndHorizontalScroll { .. } => pm_str!("SetupVerticalAndHorizontalScroll"),
+                General::EnableScrolling(_) => pm_str!("EnableScrolling"),
+                General::SetupVerticalScrollArea { .. } => pm_str!("SetupVerticalScrollArea"),
+                General::SetStartLine(_) => pm_str!("SetStartLine"),
+                General::SegmentRemap(_) => pm_str!("SegmentRemap"),
+                General::SetMultiplexRatio { .. } => pm_str!("SetMultiplexRatio"),
+                General::SetComScanDir(_) => pm_str!("SetComScanDir"),
+                General::DisplayOffset(_) => pm_str!("DisplayOffset"),
+                General::ComPinConfig { .. } => pm_str!("ComPinConfig"),
+                General::InternalIref(_) => pm_str!("InternalIref"),
+                General::DisplayClockDiv { .. } => pm_str!("DisplayClockDiv"),
+                General::PreChargePeriod { .. } => pm_str!("PreChargePeriod"),
+                General::VcomhDeselect(_) => pm_str!("VcomhDeselect"),
+                General::NoOp => pm_str!("NoOp"),
+                General::ChargePump(_) => pm_str!("ChargePump"),
+            },
+            Command::Page(command) => match command {
+                PageCommand::LowerColumnStart(_) => pm_str!("LowerColumnStart"),
+                PageCommand::UpperColumnStart(_) => pm_str!("UpperColumnStart"),
+                PageCommand::ColumnStart(_) => pm_str!("ColumnStart"),
+                PageCommand::PageStart(_) => pm_str!("PageStart"),
+            },
+            Command::Window(command) => match command {
+                WindowCommand::SetColumnAddress { .. } => pm_str!("SetColumnAddress"),
+                WindowCommand::SetPageAddress { .. } => pm_str!("SetPageAddress"),
+            },
+            Command::AddressMode(_) => pm_str!("SetAddressMode"),
+        }
+    }
+}
+
+/// Decode a whole command stream (without the I2C control byte).
+/// 
+/// Stops after the first error, since there's no telling where the next
+/// command would start.
+pub fn commands(stream: &[u8]) -> impl Iterator<Item = Result<Command, DecodeError>> + '_ {
+    let mut rest = Some(stream);
+    core::iter::from_fn(move || {
+        let bytes = rest.filter(|bytes| !bytes.is_empty())?;
+        match Command::decode(bytes) {
+            Ok((command, len)) => {
+                rest = Some(&bytes[len..]);
+                Some(Ok(command))
+            },
+            Err(err) => {
+                rest = None;
+                Some(Err(err))
+            },
+        }
+    })
+}
+
+/// The first `len` bytes of `bytes`, or `Truncated` if there aren't that many.
+fn take(bytes: &[u8], len: usize) -> Result<&[u8], DecodeError> {
+    bytes.get(..len).ok_or(DecodeError::Truncated)
+}
+
+fn page(bits: u8) -> Page {
+    match bits & 0x07 {
+        0 => Page::Page0,
+        1 => Page::Page1,
+        2 => Page::Page2,
+        3 => Page::Page3,
+        4 => Page::Page4,
+        5 => Page::Page5,
+        6 => Page::Page6,
+        _ => Page::Page7,
+    }
+}
+
+fn scroll_interval(bits: u8) -> ScrollInterval {
+    match bits & 0x07 {
+        0b000 => ScrollInterval::Frames5,
+        0b001 => ScrollInterval::Frames64,
+        0b010 => ScrollInterval::Frames128,
+        0b011 => ScrollInterval::Frames256,
+        0b100 => ScrollInterval::Frames3,
+        0b101 => ScrollInterval::Frames4,
+        0b110 => ScrollInterval::Frames25,
+        _ => ScrollInterval::Frames2,
+    }
+}
+
+/// Decode a command that works in any addressing mode.
+fn decode_general(bytes: &[u8]) -> Result<(command::Command, usize), DecodeError> {
+    use command::Command;
+    
+    let opcode = *bytes.first().ok_or(DecodeError::Empty)?;
+    
+    let (command, len) = match opcode {
+        // Fundamental commands
+        0x81 => (Command::SetContrast { contrast: take(bytes, 2)?[1] }, 2),
+        0xA4 | 0xA5 => (Command::AllPixelsOn(opcode & 1 != 0), 1),
+        0xA6 | 0xA7 => (Command::InvertDisplay(opcode & 1 != 0), 1),
+        0xAE | 0xAF => (Command::DisplayEnable(opcode & 1 != 0), 1),
+        
+        // Scrolling commands
+        0x26 | 0x27 => {
+            let bytes = take(bytes, 7)?;
+            let direction = if opcode & 1 != 0 { HorizontalScroll::Left } else { HorizontalScroll::Right };
+            (Command::SetupHorizontalScroll {
+                direction,
+                interval: scroll_interval(bytes[3]),
+                start_page: page(bytes[2]),
+                end_page: page(bytes[4]),
+            }, 7)
+        },
+        0x29 | 0x2A => {
+            let bytes = take(bytes, 6)?;
+            let direction = if opcode == 0x29 {
+                VerticalHorizontalScroll::VerticalRight
+            } else {
+                VerticalHorizontalScroll::VerticalLeft
+            };
+            (Command::SetupVerticalAndHorizontalScroll {
+                direction,
+                interval: scroll_interval(bytes[3]),
+                start_page: page(bytes[2]),
+                end_page: page(bytes[4]),
+                offset: bytes[5] & 0x3F,
+            }, 6)
+        },
+        0x2E | 0x2F => (Command::EnableScrolling(opcode & 1 != 0), 1),
+        0xA3 => {
+            let bytes = take(bytes, 3)?;
+            (Command::SetupVerticalScrollArea { top: bytes[1] & 0x3F, bottom: bytes[2] & 0x7F }, 3)
+        },
+        
+        // Hardware Configuration Commands
+        0x40..=0x7F => (Command::SetStartLine(opcode & 0x3F), 1),
+        0xA0 | 0xA1 => (Command::SegmentRemap(opcode & 1 != 0), 1),
+        0xA8 => (Command::SetMultiplexRatio { ratio: take(bytes, 2)?[1] & 0x3F }, 2),
+        0xC0 | 0xC8 => (Command::SetComScanDir(opcode & 0x08 != 0), 1),
+        0xD3 => (Command::DisplayOffset(take(bytes, 2)?[1] & 0x3F), 2),
+        0xDA => {
+            let config = take(bytes, 2)?[1];
+            (Command::ComPinConfig { alternative: config & 0x10 != 0, remap: config & 0x20 != 0 }, 2)
+        },
+        0xAD => {
+            let selection = match (take(bytes, 2)?[1] >> 4) & 0x03 {
+                0b00 => IrefSelection::External,
+                0b10 => IrefSelection::Internal19uA,
+                0b11 => IrefSelection::Internal30uA,
+                _ => return Err(DecodeError::InvalidParameter),
+            };
+            (Command::InternalIref(selection), 2)
+        },
+        
+        // Timing & Driving Scheme Setting Commands
+        0xD5 => {
+            let value = take(bytes, 2)?[1];
+            (Command::DisplayClockDiv { oscillator_freq: value >> 4, divide_ratio: value & 0x0F }, 2)
+        },
+        0xD9 => {
+            let value = take(bytes, 2)?[1];
+            (Command::PreChargePeriod { phase1: value & 0x0F, phase2: value >> 4 }, 2)
+        },
+        0xDB => {
+            let level = match (take(bytes, 2)?[1] >> 4) & 0x07 {
+                0b000 => VcomhLevel::V065,
+                0b010 => VcomhLevel::V077,
+                0b011 => VcomhLevel::V083,
+                0b100 => VcomhLevel::Auto,
+                _ => return Err(DecodeError::InvalidParameter),
+            };
+            (Command::VcomhDeselect(level), 2)
+        },
+        
+        // No operation
+        0xE3 => (Command::NoOp, 1),
+        
+        // Charge pump commands
+        0x8D => (Command::ChargePump(take(bytes, 2)?[1] & 0x04 != 0), 2),
+        
+        _ => return Err(DecodeError::UnknownOpcode(opcode)),
+    };
+    
+    Ok((command, len))
+}
+
+/// Decode a page addressing command.
+/// 
+/// `ColumnStart` is sent as a `LowerColumnStart` and an `UpperColumnStart`,
+/// so that's what it decodes to.
+fn decode_page(bytes: &[u8]) -> Result<(PageCommand, usize), DecodeError> {
+    let opcode = *bytes.first().ok_or(DecodeError::Empty)?;
+    
+    let command = match opcode {
+        0x00..=0x0F => PageCommand::LowerColumnStart(opcode & 0x0F),
+        0x10..=0x1F => PageCommand::UpperColumnStart(opcode & 0x0F),
+        0xB0..=0xB7 => PageCommand::PageStart(page(opcode)),
+        _ => return Err(DecodeError::UnknownOpcode(opcode)),
+    };
+    
+    Ok((command, 1))
+}
+
+/// Decode a horizontal/vertical addressing command.
+fn decode_window(bytes: &[u8]) -> Result<(WindowCommand, usize), DecodeError> {
+    let opcode = *bytes.first().ok_or(DecodeError::Empty)?;
+    
+    let command = match opcode {
+        0x21 => {
+            let bytes = take(bytes, 3)?;
+            WindowCommand::SetColumnAddress { start_col: bytes[1] & 0x7F, end_col: bytes[2] & 0x7F }
+        },
+        0x22 => {
+            let bytes = take(bytes, 3)?;
+            WindowCommand::SetPageAddress { start_page: page(bytes[1]), end_page: page(bytes[2]) }
+        },
+        _ => return Err(DecodeError::UnknownOpcode(opcode)),
+    };
+    
+    Ok((command, 3))
+}
+
+/// Decode a Set Memory Addressing Mode (0x20) command.
+fn decode_address_mode(bytes: &[u8]) -> Result<(AddressMode, usize), DecodeError> {
+    let opcode = *bytes.first().ok_or(DecodeError::Empty)?;
+    if opcode != 0x20 {
+        return Err(DecodeError::UnknownOpcode(opcode));
+    }
+    
+    let mode = match take(bytes, 2)?[1] & 0x03 {
+        0b00 => AddressMode::Horizontal,
+        0b01 => AddressMode::Vertical,
+        0b10 => AddressMode::Page,
+        _ => return Err(DecodeError::InvalidParameter),
+    };
+    
+    Ok((mode, 2))
+}
@@ -0,0 +1,189 @@
+//! Large 7-segment style digits, for numbers that have to be readable at arm's
+//! length (ammo count, chrono readout).
+//! 
+//! The digits are drawn from their segments instead of stored glyphs, so all
+//! three sizes together cost a few bytes of flash. Every column of a digit is
+//! a single `u32` of pixel rows, which makes packing it into pages cheap.
+
+use super::canvas::Canvas;
+use super::command::Page;
+use super::display::Display;
+use super::error::InvalidParameter;
+use super::size::DisplaySize;
+
+/// Segments lit for each digit, `a` (top) in bit 0 through `g` (middle) in
+/// bit 6. Index 10 is a blank.
+const SEGMENTS: [u8; 11] = [0x3F, 0x06, 0x5B, 0x4F, 0x66, 0x6D, 0x7D, 0x07, 0x7F, 0x6F, 0x00];
+
+/// Index into `SEGMENTS` for a blank digit.
+const BLANK: u8 = 10;
+
+/// Marks a digit that isn't known to be on the display.
+const UNKNOWN: u8 = 0xFF;
+
+/// How tall the digits are. Each size spans a whole number of pages.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DigitHeight {
+    Px16,
+    Px24,
+    Px32,
+}
+
+impl DigitHeight {
+    pub const fn pages(self) -> u8 {
+        match self {
+            DigitHeight::Px16 => 2,
+            DigitHeight::Px24 => 3,
+            DigitHeight::Px32 => 4,
+        }
+    }
+    
+    pub const fn height(self) -> u8 {
+        self.pages() * 8
+    }
+    
+    /// Width of a single digit in pixels.
+    pub const fn width(self) -> u8 {
+        match self {
+            DigitHeight::Px16 => 10,
+            DigitHeight::Px24 => 14,
+            DigitHeight::Px32 => 18,
+        }
+    }
+    
+    /// Thickness of the segments.
+    const fn thickness(self) -> u8 {
+        self.pages()
+    }
+    
+    /// Columns from the start of one digit to the start of the next.
+    pub const fn advance(self) -> u8 {
+        self.width() + self.thickness()
+    }
+}
+
+/// Pixel rows of column `x` of a digit, bit 0 at the top. `digit` is 0-9, or
+/// anything else for a blank.
+pub fn digit_column(height: DigitHeight, digit: u8, x: u8) -> u32 {
+    let segments = SEGMENTS.get(digit as usize).copied().unwrap_or(0);
+    let lit = |segment: u8| segments & (1 << segment) != 0;
+    
+    let (t, w, h) = (height.thickness(), height.width(), height.height());
+    let mid = h / 2;
+    // rows start..end, as a bitmask
+    let rows = |start: u8, end: u8| (u32::MAX >> (32 - (end - start))) << start;
+    
+    let mut column = 0;
+    if x >= w {
+        return column;
+    }
+    
+    // horizontal segments span the whole width
+    if lit(0) { column |= rows(0, t) }
+    if lit(6) { column |= rows(mid - t / 2, mid - t / 2 + t) }
+    if lit(3) { column |= rows(h - t, h) }
+    
+    if x < t {
+        if lit(5) { column |= rows(0, mid) }
+        if lit(4) { column |= rows(mid, h) }
+    }
+    if x >= w - t {
+        if lit(1) { column |= rows(0, mid) }
+        if lit(2) { column |= rows(mid, h) }
+    }
+    
+    column
+}
+
+/// Draw `digit` with its top left corner at `(x, y)`.
+pub fn draw_digit(canvas: &mut dyn Canvas, x: u8, y: u8, height: DigitHeight, digit: u8) {
+    for dx in 0..height.width() {
+        let column = digit_column(height, digit, dx);
+        for dy in 0..height.height() {
+            canvas.set_pixel(x.saturating_add(dx), y.saturating_add(dy), column & (1 << dy) != 0);
+        }
+    }
+}
+
+/// A 3 digit number (0-999) drawn straight to the display, that only redraws
+/// the digits that changed.
+/// 
+/// Leading zeros are left blank, and values past 999 show as 999.
+pub struct BigNumber {
+    page: Page,
+    col: u8,
+    height: DigitHeight,
+    
+    /// What's currently on the display, hundreds first.
+    shown: [u8; 3],
+}
+
+impl BigNumber {
+    /// A number with its top left corner at column `col` of `page`.
+    pub const fn new(page: Page, col: u8, height: DigitHeight) -> Self {
+        Self { page, col, height, shown: [UNKNOWN; 3] }
+    }
+    
+    /// Columns taken by all 3 digits.
+    pub const fn width(&self) -> u8 {
+        self.height.advance() * 2 + self.height.width()
+    }
+    
+    /// Forget what's on the display, so the next update redraws every digit.
+    pub fn invalidate(&mut self) {
+        self.shown = [UNKNOWN; 3];
+    }
+    
+    /// Show `value`, writing only the digits that changed.
+    pub fn update<D>(&mut self, display: &mut D, value: u16) -> Result<(), D::Error>
+    where D: Display, D::Error: From<InvalidParameter> {
+        if self.page as u8 + self.height.pages() > D::Size::PAGES {
+            return Err(InvalidParameter::Page.into());
+        }
+        if self.col as usize + self.width() as usize > D::Size::WIDTH as usize {
+            return Err(InvalidParameter::Column.into());
+        }
+        
+        let value = value.min(999);
+        let digits = [
+            if value >= 100 { (value / 100) as u8 } else { BLANK },
+            if value >= 10 { (value / 10 % 10) as u8 } else { BLANK },
+            (value % 10) as u8,
+        ];
+        
+        for (index, &digit) in digits.iter().enumerate() {
+            if self.shown[index] == digit {
+                continue;
+            }
+            
+            let col = self.col + index as u8 * self.height.advance();
+            // the gap to the next digit is blanked along with the digit, so
+            // nothing is left over from whatever was there before
+            let columns = if index < 2 { self.height.advance() } else { self.height.width() };
+            self.write_digit(display, col, columns, digit)?;
+            self.shown[index] = digit;
+        }
+        
+        Ok(())
+    }
+    
+    fn write_digit<D>(&self, display: &mut D, col: u8, width: u8, digit: u8) -> Result<(), D::Error>
+    where D: Display, D::Error: From<InvalidParameter> {
+        let mut columns = [0; DigitHeight::Px32.advance() as usize];
+        let columns = &mut columns[..width as usize];
+        for (x, column) in columns.iter_mut().enumerate() {
+            *column = digit_column(self.height, digit, x as u8);
+        }
+        
+        let mut data = [0; DigitHeight::Px32.advance() as usize];
+        for offset in 0..self.height.pages() {
+            let page = Page::try_from(self.page as u8 + offset)?;
+            for (byte, column) in data.iter_mut().zip(columns.iter()) {
+                *byte = (column >> (8 * offset)) as u8;
+            }
+            display.write_page(page, col, &data[..columns.len()])?;
+        }
+        
+        Ok(())
+    }
+}
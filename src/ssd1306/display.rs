@@ -0,0 +1,53 @@
+//! Controller-agnostic view of a monochrome OLED.
+//! 
+//! Both the SSD1306 and the SH1106 keep the picture in pages of 8 rows, one
+//! byte per column with the LSB at the top. Everything above the command
+//! level only needs to be able to write runs of those bytes, which is what
+//! [`Display`] provides.
+
+use crate::progmem::PmBytes;
+
+use super::command::Page;
+use super::orientation::Orientation;
+use super::size::DisplaySize;
+
+/// A display that can be written to a page at a time.
+pub trait Display {
+    type Error;
+    
+    /// Geometry of the attached panel.
+    type Size: DisplaySize;
+    
+    /// Write `data` into `page`, starting at column `col`.
+    /// 
+    /// `data` must fit in the page, i.e. `col + data.len() <= WIDTH`.
+    fn write_page(&mut self, page: Page, col: u8, data: &[u8]) -> Result<(), Self::Error>;
+    
+    /// [`write_page`](Display::write_page), streaming `data` straight from
+    /// flash.
+    fn write_page_flash(&mut self, page: Page, col: u8, data: PmBytes) -> Result<(), Self::Error>;
+    
+    /// Write a whole frame, `PAGES` pages of `WIDTH` bytes each.
+    fn write_frame(&mut self, frame: &[u8]) -> Result<(), Self::Error> {
+        let width = Self::Size::WIDTH as usize;
+        
+        for (index, page) in frame.chunks(width).take(Self::Size::PAGES as usize).enumerate() {
+            if let Ok(page_number) = Page::try_from(index as u8) {
+                self.write_page(page_number, 0, page)?;
+            }
+        }
+        
+        Ok(())
+    }
+    
+    /// Turn the panel on or off (sleep mode). The RAM is kept either way.
+    fn set_display_on(&mut self, on: bool) -> Result<(), Self::Error>;
+    
+    fn set_contrast(&mut self, contrast: u8) -> Result<(), Self::Error>;
+    
+    fn set_inverted(&mut self, inverted: bool) -> Result<(), Self::Error>;
+    
+    /// Change which way up the picture is shown. Depending on the controller
+    /// the RAM may have to be rewritten afterwards.
+    fn set_orientation(&mut self, orientation: Orientation) -> Result<(), Self::Error>;
+}
@@ -0,0 +1,364 @@
+//! A host-side model of an SSD1306 on a 128x64 module, for testing drawing
+//! code without a panel.
+//! 
+//! The emulator is an I2C bus: hand it to an [`I2cInterface`] in place of the
+//! real one and it decodes the exact byte stream the driver would send (0x00
+//! command and 0x40 data transactions) into GDDRAM and register state. The
+//! visible picture can then be rendered to a PBM or PNG and compared against
+//! a stored golden image:
+//! 
+//! ```ignore
+//! let mut display = SSD1306::new(I2cInterface::new(Emulator::new(), 0x3C), DisplaySize128x64);
+//! display.initialize()?;
+//! // ... draw ...
+//! display.release().release().assert_matches_golden("hud");
+//! ```
+//! 
+//! Golden images live in `golden/` next to this file. A missing or different
+//! image fails the test and writes the actual picture next to the golden one
+//! (`<name>.actual.pbm`/`.png`); run `scripts/test-host.sh --update-golden`
+//! to accept it.
+//! 
+//! The module is modelled like the common 0.96" ones: mounted so the picture
+//! is upright with the segment remap on and the COM scan reversed, which is
+//! what [`Rotation::Deg0`](super::orientation::Rotation::Deg0) sets up. The
+//! COM pin configuration is assumed to match the panel, and scrolling is
+//! tracked but not animated.
+//! 
+//! [`I2cInterface`]: super::I2cInterface
+
+extern crate std;
+
+use std::format;
+use std::path::PathBuf;
+use std::string::String;
+use std::vec::Vec;
+
+use embedded_hal::blocking::i2c;
+
+use super::interface::ADDRESSES;
+
+const WIDTH: usize = 128;
+const HEIGHT: usize = 64;
+const PAGES: usize = HEIGHT / 8;
+
+/// Longest command, the horizontal scroll setup.
+const MAX_COMMAND_LEN: usize = 7;
+
+/// How the RAM pointer moves after each data byte.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Addressing {
+    Horizontal,
+    Vertical,
+    Page,
+}
+
+/// What the next byte of a transaction is.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Expect {
+    Control,
+    Command { continuous: bool },
+    Data { continuous: bool },
+}
+
+/// An SSD1306 and its 128x64 panel.
+pub struct Emulator {
+    /// GDDRAM by page and *segment*. With the segment remap on, column 0 is
+    /// stored in segment 127.
+    ram: [[u8; WIDTH]; PAGES],
+    
+    /// Bytes of a command still waiting for its parameters.
+    pending: [u8; MAX_COMMAND_LEN],
+    pending_len: usize,
+    
+    addressing: Addressing,
+    col: u8,
+    page: u8,
+    col_start: u8,
+    col_end: u8,
+    page_start: u8,
+    page_end: u8,
+    
+    display_on: bool,
+    all_on: bool,
+    inverted: bool,
+    contrast: u8,
+    segment_remap: bool,
+    com_reversed: bool,
+    start_line: u8,
+    display_offset: u8,
+    mux_ratio: u8,
+    scrolling: bool,
+}
+
+impl Emulator {
+    /// A controller straight out of reset: display off, page addressing,
+    /// RAM cleared (the real RAM comes up random).
+    pub fn new() -> Self {
+        Self {
+            ram: [[0; WIDTH]; PAGES],
+            pending: [0; MAX_COMMAND_LEN],
+            pending_len: 0,
+            addressing: Addressing::Page,
+            col: 0,
+            page: 0,
+            col_start: 0,
+            col_end: WIDTH as u8 - 1,
+            page_start: 0,
+            page_end: PAGES as u8 - 1,
+            display_on: false,
+            all_on: false,
+            inverted: false,
+            contrast: 0x7F,
+            segment_remap: false,
+            com_reversed: false,
+            start_line: 0,
+            display_offset: 0,
+            mux_ratio: 63,
+            scrolling: false,
+        }
+    }
+    
+    /// Handle one I2C write transaction, control bytes included.
+    pub fn transaction(&mut self, bytes: &[u8]) {
+        let mut expect = Expect::Control;
+        
+        for &byte in bytes {
+            expect = match expect {
+                Expect::Control => {
+                    // Co (bit 7) clear: the rest of the transaction is all
+                    // commands or all data. D/C (bit 6) picks which.
+                    let continuous = byte & 0x80 == 0;
+                    if byte & 0x40 == 0 {
+                        Expect::Command { continuous }
+                    } else {
+                        Expect::Data { continuous }
+                    }
+                },
+                Expect::Command { continuous } => {
+                    self.command_byte(byte);
+                    if continuous { expect } else { Expect::Control }
+                },
+                Expect::Data { continuous } => {
+                    self.data_byte(byte);
+                    if continuous { expect } else { Expect::Control }
+                },
+            };
+        }
+    }
+    
+    fn command_byte(&mut self, byte: u8) {
+        self.pending[self.pending_len] = byte;
+        self.pending_len += 1;
+        
+        if self.pending_len == command_len(self.pending[0]) {
+            let command = self.pending;
+            self.pending_len = 0;
+            self.command(&command);
+        }
+    }
+    
+    fn command(&mut self, command: &[u8]) {
+        match command[0] {
+            0x00..=0x0F => self.col = (self.col & 0xF0) | (command[0] & 0x0F),
+            0x10..=0x1F => self.col = (self.col & 0x0F) | ((command[0] & 0x07) << 4),
+            0x20 => {
+                self.addressing = match command[1] & 0x03 {
+                    0b00 => Addressing::Horizontal,
+                    0b01 => Addressing::Vertical,
+                    _ => Addressing::Page,
+                }
+            },
+            0x21 => {
+                self.col_start = command[1] & 0x7F;
+                self.col_end = command[2] & 0x7F;
+                self.col = self.col_start;
+            },
+            0x22 => {
+                self.page_start = command[1] & 0x07;
+                self.page_end = command[2] & 0x07;
+                self.page = self.page_start;
+            },
+            0x26 | 0x27 | 0x29 | 0x2A | 0xA3 => {},
+            0x2E => self.scrolling = false,
+            0x2F => self.scrolling = true,
+            0x40..=0x7F => self.start_line = command[0] & 0x3F,
+            0x81 => self.contrast = command[1],
+            0x8D | 0xAD | 0xD5 | 0xD9 | 0xDA | 0xDB | 0xE3 => {},
+            0xA0 | 0xA1 => self.segment_remap = command[0] & 1 != 0,
+            0xA4 | 0xA5 => self.all_on = command[0] & 1 != 0,
+            0xA6 | 0xA7 => self.inverted = command[0] & 1 != 0,
+            0xA8 => self.mux_ratio = command[1] & 0x3F,
+            0xAE | 0xAF => self.display_on = command[0] & 1 != 0,
+            0xB0..=0xB7 => self.page = command[0] & 0x07,
+            0xC0 | 0xC8 => self.com_reversed = command[0] & 0x08 != 0,
+            0xD3 => self.display_offset = command[1] & 0x3F,
+            other => panic!("unknown command 0x{:02X}", other),
+        }
+    }
+    
+    fn data_byte(&mut self, byte: u8) {
+        let seg = if self.segment_remap { WIDTH as u8 - 1 - self.col } else { self.col };
+        self.ram[self.page as usize][seg as usize] = byte;
+        
+        match self.addressing {
+            Addressing::Page => {
+                self.col = if self.col == WIDTH as u8 - 1 { 0 } else { self.col + 1 };
+            },
+            Addressing::Horizontal => {
+                if self.col >= self.col_end {
+                    self.col = self.col_start;
+                    self.page = if self.page >= self.page_end { self.page_start } else { self.page + 1 };
+                } else {
+                    self.col += 1;
+                }
+            },
+            Addressing::Vertical => {
+                if self.page >= self.page_end {
+                    self.page = self.page_start;
+                    self.col = if self.col >= self.col_end { self.col_start } else { self.col + 1 };
+                } else {
+                    self.page += 1;
+                }
+            },
+        }
+    }
+    
+    /// The RAM byte column `col` of `page` was written to, taking the
+    /// segment remap into account.
+    pub fn ram_byte(&self, page: u8, col: u8) -> u8 {
+        let seg = if self.segment_remap { WIDTH as u8 - 1 - col } else { col };
+        self.ram[page as usize][seg as usize]
+    }
+    
+    pub fn addressing(&self) -> Addressing {
+        self.addressing
+    }
+    
+    pub fn display_on(&self) -> bool {
+        self.display_on
+    }
+    
+    pub fn contrast(&self) -> u8 {
+        self.contrast
+    }
+    
+    pub fn inverted(&self) -> bool {
+        self.inverted
+    }
+    
+    pub fn start_line(&self) -> u8 {
+        self.start_line
+    }
+    
+    pub fn scrolling(&self) -> bool {
+        self.scrolling
+    }
+    
+    /// Whether the pixel at `(x, y)` of the panel is lit, as seen by someone
+    /// looking at it. `(0, 0)` is the top left corner.
+    pub fn pixel(&self, x: usize, y: usize) -> bool {
+        if !self.display_on || x >= WIDTH || y >= HEIGHT {
+            return false;
+        }
+        
+        // the top row is driven by COM63, the left column by SEG127
+        let com = (HEIGHT - 1 - y) as u8;
+        if com > self.mux_ratio {
+            return false;
+        }
+        if self.all_on {
+            return true;
+        }
+        
+        let scan = if self.com_reversed { self.mux_ratio - com } else { com };
+        let row = (scan as usize + self.start_line as usize + self.display_offset as usize) % HEIGHT;
+        let seg = WIDTH - 1 - x;
+        let set = self.ram[row / 8][seg] & (1 << (row % 8)) != 0;
+        set != self.inverted
+    }
+    
+    /// The visible picture as a plain (P1) PBM, one line per row.
+    pub fn render_pbm(&self) -> String {
+        let mut pbm = format!("P1\n{} {}\n", WIDTH, HEIGHT);
+        for y in 0..HEIGHT {
+            pbm.extend((0..WIDTH).map(|x| if self.pixel(x, y) { '1' } else { '0' }));
+            pbm.push('\n');
+        }
+        pbm
+    }
+    
+    /// The visible picture as an 8-bit greyscale PNG. Lit pixels get brighter
+    /// with the contrast setting.
+    pub fn render_png(&self) -> Vec<u8> {
+        let lit = 64 + (self.contrast as u32 * 191 / 255) as u8;
+        let mut pixels = Vec::with_capacity(WIDTH * HEIGHT);
+        for y in 0..HEIGHT {
+            pixels.extend((0..WIDTH).map(|x| if self.pixel(x, y) { lit } else { 0 }));
+        }
+        
+        let mut png = Vec::new();
+        let mut encoder = png::Encoder::new(&mut png, WIDTH as u32, HEIGHT as u32);
+        encoder.set_color(png::ColorType::Grayscale);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header().unwrap();
+        writer.write_image_data(&pixels).unwrap();
+        writer.finish().unwrap();
+        png
+    }
+    
+    /// Compare the visible picture with `golden/<name>.pbm`, and panic if
+    /// it's different. See the module docs.
+    pub fn assert_matches_golden(&self, name: &str) {
+        let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("src/ssd1306/golden");
+        let golden = dir.join(format!("{}.pbm", name));
+        let actual = self.render_pbm();
+        
+        std::fs::create_dir_all(&dir).unwrap();
+        if std::env::var_os("UPDATE_GOLDEN").is_some() {
+            std::fs::write(&golden, &actual).unwrap();
+            return;
+        }
+        
+        let expected = std::fs::read_to_string(&golden).unwrap_or_default();
+        if expected != actual {
+            std::fs::write(dir.join(format!("{}.actual.pbm", name)), &actual).unwrap();
+            std::fs::write(dir.join(format!("{}.actual.png", name)), self.render_png()).unwrap();
+            panic!(
+                "display doesn't match {}, the actual picture was written next to it (run scripts/test-host.sh --update-golden to accept it)",
+                golden.display(),
+            );
+        }
+    }
+}
+
+impl Default for Emulator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl i2c::Write for Emulator {
+    /// Writes to any address the SSD1306 can't be strapped to are NACKed.
+    type Error = ();
+    
+    fn write(&mut self, address: u8, bytes: &[u8]) -> Result<(), ()> {
+        if !ADDRESSES.contains(&address) {
+            return Err(());
+        }
+        
+        self.transaction(bytes);
+        Ok(())
+    }
+}
+
+/// Length of the command starting with `opcode`, parameters included.
+fn command_len(opcode: u8) -> usize {
+    match opcode {
+        0x26 | 0x27 => 7,
+        0x29 | 0x2A => 6,
+        0x21 | 0x22 | 0xA3 => 3,
+        0x20 | 0x81 | 0x8D | 0xA8 | 0xAD | 0xD3 | 0xD5 | 0xD9 | 0xDA | 0xDB => 2,
+        _ => 1,
+    }
+}
@@ -0,0 +1,109 @@
+use crate::pm_str;
+use crate::progmem::PmStr;
+
+/// Errors returned by the display driver.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Ssd1306Error<E> {
+    /// The bus reported an error, e.g. the display NACKed a byte.
+    Bus(E),
+    
+    /// No display answered at any of the probed addresses.
+    NotPresent,
+    
+    /// A parameter was out of range. Nothing was sent.
+    InvalidParameter(InvalidParameter),
+}
+
+/// Which parameter was out of range.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum InvalidParameter {
+    /// Page numbers go from 0-7.
+    Page,
+    
+    /// A start page was after its end page.
+    PageRange,
+    
+    /// Column numbers go from 0-127.
+    Column,
+    
+    /// A start column was after its end column.
+    ColumnRange,
+    
+    /// `top + bottom` of the vertical scroll area is larger than the MUX ratio.
+    ScrollArea,
+    
+    /// A vertical scroll area was given for a scroll that only moves
+    /// horizontally.
+    ScrollAreaNotVertical,
+    
+    /// The display start line isn't inside the vertical scroll area.
+    StartLine,
+    
+    /// The vertical scroll offset doesn't fit inside the scrolling area.
+    ScrollOffset,
+    
+    /// A buffer's length doesn't match the area it's written to.
+    BufferLength,
+}
+
+/// Why a byte stream couldn't be decoded into a command.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DecodeError {
+    /// There were no bytes left.
+    Empty,
+    
+    /// The stream ended before all of the command's parameters.
+    Truncated,
+    
+    /// The byte isn't the start of a command of the type being decoded.
+    UnknownOpcode(u8),
+    
+    /// A parameter has a value the command can't take, e.g. a reserved
+    /// VCOMH level.
+    InvalidParameter,
+}
+
+impl<E> From<InvalidParameter> for Ssd1306Error<E> {
+    fn from(parameter: InvalidParameter) -> Self {
+        Ssd1306Error::InvalidParameter(parameter)
+    }
+}
+
+impl<E> Ssd1306Error<E> {
+    /// Short description of the error, for printing over the console. It's
+    /// kept in flash, like every other console string.
+    pub fn as_str(&self) -> PmStr {
+        match self {
+            Ssd1306Error::Bus(_) => pm_str!("bus error"),
+            Ssd1306Error::NotPresent => pm_str!("display not present"),
+            Ssd1306Error::InvalidParameter(parameter) => parameter.as_str(),
+        }
+    }
+}
+
+impl InvalidParameter {
+    pub fn as_str(&self) -> PmStr {
+        match self {
+            InvalidParameter::Page => pm_str!("invalid page"),
+            InvalidParameter::PageRange => pm_str!("invalid page range"),
+            InvalidParameter::Column => pm_str!("invalid column"),
+            InvalidParameter::ColumnRange => pm_str!("invalid column range"),
+            InvalidParameter::ScrollArea => pm_str!("scroll area larger than display"),
+            InvalidParameter::ScrollAreaNotVertical => pm_str!("scroll area on a horizontal scroll"),
+            InvalidParameter::StartLine => pm_str!("start line outside scroll area"),
+            InvalidParameter::ScrollOffset => pm_str!("scroll offset outside scroll area"),
+            InvalidParameter::BufferLength => pm_str!("buffer length doesn't match area"),
+        }
+    }
+}
+
+impl DecodeError {
+    pub fn as_str(&self) -> PmStr {
+        match self {
+            DecodeError::Empty => pm_str!("no command"),
+            DecodeError::Truncated => pm_str!("command cut short"),
+            DecodeError::UnknownOpcode(_) => pm_str!("unknown opcode"),
+            DecodeError::InvalidParameter => pm_str!("invalid command parameter"),
+        }
+    }
+}
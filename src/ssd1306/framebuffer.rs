@@ -0,0 +1,208 @@
+//! RAM copy of the display, for drawing pixel by pixel.
+//! 
+//! Drawing only touches the buffer. Every page remembers which columns changed
+//! since the last flush, so [`FrameBuffer::flush`] can send just those instead
+//! of the whole frame.
+
+use core::marker::PhantomData;
+
+use super::canvas::{Canvas, Drawable};
+use super::command::Page;
+use super::display::Display;
+use super::size::{DisplaySize, DisplaySize128x64};
+
+/// Size of the largest frame any supported panel needs (128x64).
+pub const MAX_BUFFER_SIZE: usize = 1024;
+
+/// Inclusive range of columns changed on a page.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DirtyColumns {
+    pub start: u8,
+    pub end: u8,
+}
+
+impl DirtyColumns {
+    fn include(range: Option<Self>, start: u8, end: u8) -> Self {
+        match range {
+            Some(range) => Self { start: range.start.min(start), end: range.end.max(end) },
+            None => Self { start, end },
+        }
+    }
+}
+
+/// Framebuffer for a panel of size `S`.
+/// 
+/// The buffer is laid out like the controller's RAM: `PAGES` pages of `WIDTH`
+/// bytes, one byte per column with the LSB at the top. It's always 1 KiB,
+/// smaller panels just use the start of it.
+pub struct FrameBuffer<S = DisplaySize128x64> {
+    buffer: [u8; MAX_BUFFER_SIZE],
+    dirty: [Option<DirtyColumns>; 8],
+    size: PhantomData<S>,
+}
+
+impl<S: DisplaySize> FrameBuffer<S> {
+    /// A blank framebuffer. Every page starts out dirty, so the first flush
+    /// clears whatever was left in the display's RAM.
+    pub const fn new() -> Self {
+        let mut dirty = [None; 8];
+        let mut page = 0;
+        while page < S::PAGES as usize {
+            dirty[page] = Some(DirtyColumns { start: 0, end: S::WIDTH - 1 });
+            page += 1;
+        }
+        
+        Self {
+            buffer: [0; MAX_BUFFER_SIZE],
+            dirty,
+            size: PhantomData,
+        }
+    }
+    
+    /// Turn the pixel at `(x, y)` on or off. Pixels outside the panel are
+    /// ignored.
+    pub fn set_pixel(&mut self, x: u8, y: u8, on: bool) {
+        if x >= S::WIDTH || y >= S::HEIGHT {
+            return;
+        }
+        
+        let index = (y / 8) as usize * S::WIDTH as usize + x as usize;
+        let mask = 1 << (y % 8);
+        let byte = if on { self.buffer[index] | mask } else { self.buffer[index] & !mask };
+        
+        if byte != self.buffer[index] {
+            self.buffer[index] = byte;
+            self.mark_dirty(y / 8, x, x);
+        }
+    }
+    
+    /// Whether the pixel at `(x, y)` is on. Pixels outside the panel are off.
+    pub fn get_pixel(&self, x: u8, y: u8) -> bool {
+        if x >= S::WIDTH || y >= S::HEIGHT {
+            return false;
+        }
+        
+        let index = (y / 8) as usize * S::WIDTH as usize + x as usize;
+        self.buffer[index] & (1 << (y % 8)) != 0
+    }
+    
+    /// Turn every pixel off.
+    pub fn clear(&mut self) {
+        self.fill(false)
+    }
+    
+    /// Turn every pixel on or off.
+    pub fn fill(&mut self, on: bool) {
+        let byte = if on { 0xFF } else { 0x00 };
+        
+        for page in 0..S::PAGES {
+            let start = page as usize * S::WIDTH as usize;
+            let columns = &mut self.buffer[start..start + S::WIDTH as usize];
+            
+            // only mark the columns that actually change
+            let first = columns.iter().position(|&column| column != byte);
+            let last = columns.iter().rposition(|&column| column != byte);
+            if let (Some(first), Some(last)) = (first, last) {
+                columns.fill(byte);
+                self.mark_dirty(page, first as u8, last as u8);
+            }
+        }
+    }
+    
+    /// The bytes of `page`, one per column.
+    pub fn page(&self, page: Page) -> &[u8] {
+        let start = page as usize * S::WIDTH as usize;
+        &self.buffer[start..start + S::WIDTH as usize]
+    }
+    
+    /// The bytes of `page`, for writing. Changes made through this aren't
+    /// marked dirty, so it's only for keeping the buffer in step with data
+    /// that's already been sent to the display some other way.
+    pub fn page_mut_untracked(&mut self, page: Page) -> &mut [u8] {
+        let start = page as usize * S::WIDTH as usize;
+        &mut self.buffer[start..start + S::WIDTH as usize]
+    }
+    
+    /// The whole frame, as it's laid out in the display's RAM.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.buffer[..S::BUFFER_SIZE]
+    }
+    
+    /// The columns of `page` that changed since the last flush.
+    pub fn dirty_columns(&self, page: Page) -> Option<DirtyColumns> {
+        self.dirty[page as usize]
+    }
+    
+    pub fn is_dirty(&self) -> bool {
+        self.dirty.iter().any(Option::is_some)
+    }
+    
+    /// Force the whole frame to be sent on the next flush, e.g. after the
+    /// display's RAM has been lost to scrolling.
+    pub fn mark_all_dirty(&mut self) {
+        for page in 0..S::PAGES {
+            self.mark_dirty(page, 0, S::WIDTH - 1);
+        }
+    }
+    
+    fn mark_dirty(&mut self, page: u8, start: u8, end: u8) {
+        let dirty = &mut self.dirty[page as usize];
+        *dirty = Some(DirtyColumns::include(*dirty, start, end));
+    }
+    
+    /// Draw `drawables` into the buffer, in order (later ones on top).
+    pub fn draw(&mut self, drawables: &[&dyn Drawable]) {
+        for drawable in drawables {
+            drawable.draw(self);
+        }
+    }
+    
+    /// Send everything that changed since the last flush to `display`.
+    /// 
+    /// Each dirty page only sends its changed columns. In horizontal
+    /// addressing mode that's a `SetColumnAddress`/`SetPageAddress` window
+    /// per page, and a completely dirty frame goes out as one data stream.
+    /// Pages stay dirty until they've been sent, so a failed flush can just
+    /// be retried.
+    pub fn flush<D>(&mut self, display: &mut D) -> Result<(), D::Error>
+    where D: Display<Size = S> {
+        let full = DirtyColumns { start: 0, end: S::WIDTH - 1 };
+        if self.dirty[..S::PAGES as usize].iter().all(|&dirty| dirty == Some(full)) {
+            display.write_frame(self.as_bytes())?;
+            self.dirty = [None; 8];
+            return Ok(());
+        }
+        
+        for index in 0..S::PAGES {
+            let (Some(columns), Ok(page)) = (self.dirty[index as usize], Page::try_from(index)) else {
+                continue
+            };
+            
+            let data = &self.page(page)[columns.start as usize..=columns.end as usize];
+            display.write_page(page, columns.start, data)?;
+            self.dirty[index as usize] = None;
+        }
+        
+        Ok(())
+    }
+}
+
+impl<S: DisplaySize> Canvas for FrameBuffer<S> {
+    fn size(&self) -> (u8, u8) {
+        (S::WIDTH, S::HEIGHT)
+    }
+    
+    fn set_pixel(&mut self, x: u8, y: u8, on: bool) {
+        FrameBuffer::set_pixel(self, x, y, on)
+    }
+    
+    fn fill(&mut self, on: bool) {
+        FrameBuffer::fill(self, on)
+    }
+}
+
+impl<S: DisplaySize> Default for FrameBuffer<S> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
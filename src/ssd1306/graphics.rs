@@ -0,0 +1,116 @@
+//! [`embedded-graphics`](https://docs.rs/embedded-graphics) support.
+//! 
+//! The framebuffer and the page strip are both `DrawTarget`s, and any
+//! embedded-graphics drawable can be put in a screen by wrapping it in a
+//! [`Graphic`], so it renders in both buffered and strip mode.
+//! 
+//! Only built with the `graphics` feature.
+
+use core::convert::Infallible;
+
+use embedded_graphics_core::draw_target::DrawTarget;
+use embedded_graphics_core::geometry::{OriginDimensions, Size};
+use embedded_graphics_core::pixelcolor::BinaryColor;
+use embedded_graphics_core::Pixel;
+
+use super::canvas::{Canvas, Drawable};
+use super::framebuffer::FrameBuffer;
+use super::size::DisplaySize;
+use super::strip::PageStrip;
+
+/// Draw the pixels that land on the canvas, and drop the rest.
+fn draw_pixels<I>(canvas: &mut dyn Canvas, pixels: I)
+where I: IntoIterator<Item = Pixel<BinaryColor>> {
+    for Pixel(point, color) in pixels {
+        if let (Ok(x), Ok(y)) = (u8::try_from(point.x), u8::try_from(point.y)) {
+            canvas.set_pixel(x, y, color.is_on());
+        }
+    }
+}
+
+impl<S: DisplaySize> OriginDimensions for FrameBuffer<S> {
+    fn size(&self) -> Size {
+        Size::new(S::WIDTH as u32, S::HEIGHT as u32)
+    }
+}
+
+impl<S: DisplaySize> DrawTarget for FrameBuffer<S> {
+    type Color = BinaryColor;
+    type Error = Infallible;
+    
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Infallible>
+    where I: IntoIterator<Item = Pixel<BinaryColor>> {
+        draw_pixels(self, pixels);
+        Ok(())
+    }
+    
+    fn clear(&mut self, color: BinaryColor) -> Result<(), Infallible> {
+        self.fill(color.is_on());
+        Ok(())
+    }
+}
+
+impl<S: DisplaySize> OriginDimensions for PageStrip<S> {
+    fn size(&self) -> Size {
+        Size::new(S::WIDTH as u32, S::HEIGHT as u32)
+    }
+}
+
+impl<S: DisplaySize> DrawTarget for PageStrip<S> {
+    type Color = BinaryColor;
+    type Error = Infallible;
+    
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Infallible>
+    where I: IntoIterator<Item = Pixel<BinaryColor>> {
+        draw_pixels(self, pixels);
+        Ok(())
+    }
+    
+    fn clear(&mut self, color: BinaryColor) -> Result<(), Infallible> {
+        self.fill(color.is_on());
+        Ok(())
+    }
+}
+
+/// Any [`Canvas`] as a `DrawTarget`, for drawing embedded-graphics objects
+/// from inside a [`Drawable`].
+pub struct CanvasTarget<'a>(pub &'a mut dyn Canvas);
+
+impl OriginDimensions for CanvasTarget<'_> {
+    fn size(&self) -> Size {
+        let (width, height) = self.0.size();
+        Size::new(width as u32, height as u32)
+    }
+}
+
+impl DrawTarget for CanvasTarget<'_> {
+    type Color = BinaryColor;
+    type Error = Infallible;
+    
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Infallible>
+    where I: IntoIterator<Item = Pixel<BinaryColor>> {
+        draw_pixels(self.0, pixels);
+        Ok(())
+    }
+    
+    fn clear(&mut self, color: BinaryColor) -> Result<(), Infallible> {
+        self.0.fill(color.is_on());
+        Ok(())
+    }
+}
+
+/// An embedded-graphics drawable as part of a screen.
+/// 
+/// ```ignore
+/// let border = Graphic(Rectangle::new(Point::zero(), Size::new(128, 64)).into_styled(style));
+/// strip::render(&mut display, &[&border])?;
+/// ```
+pub struct Graphic<T>(pub T);
+
+impl<T> Drawable for Graphic<T>
+where T: embedded_graphics_core::Drawable<Color = BinaryColor> {
+    fn draw(&self, canvas: &mut dyn Canvas) {
+        // drawing onto a canvas can't fail
+        let _ = self.0.draw(&mut CanvasTarget(canvas));
+    }
+}
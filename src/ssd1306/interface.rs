@@ -0,0 +1,250 @@
+//! Transports between the driver and the controller.
+//! 
+//! The controllers take the same command and data bytes over every bus, only
+//! the way commands are told apart from data changes: I2C prefixes each
+//! transaction with a control byte, 4-wire SPI uses a D/C pin.
+
+use embedded_hal::blocking::delay::DelayUs;
+use embedded_hal::blocking::i2c;
+use embedded_hal::blocking::spi;
+use embedded_hal::digital::v2::OutputPin;
+
+use crate::progmem::PmBytes;
+
+use super::command::{Command, EncodedCommand};
+use super::error::Ssd1306Error;
+
+/// I2C control byte announcing that the rest of the transaction is commands.
+pub const COMMAND_STREAM: u8 = 0x00;
+
+/// I2C control byte announcing that the rest of the transaction is GDDRAM data.
+pub const DATA_STREAM: u8 = 0x40;
+
+/// Longest command stream sent in one transaction. The drivers batch their
+/// commands up to this; anything longer is split over several.
+pub const MAX_COMMAND_BYTES: usize = 40;
+
+/// Data is sent in chunks of this many bytes. Over I2C that's one transaction
+/// per chunk; it's also how much of a flash buffer is loaded into SRAM at once.
+const CHUNK_SIZE: usize = 16;
+
+/// A bus a display controller hangs off.
+pub trait Interface {
+    type Error;
+    
+    /// Send encoded commands as a command stream.
+    /// 
+    /// `commands` can be any length. Anything past [`MAX_COMMAND_BYTES`] goes
+    /// out in further transactions, which the controllers don't mind even in
+    /// the middle of a command: they keep waiting for its parameters.
+    fn send_commands(&mut self, commands: &[u8]) -> Result<(), Self::Error>;
+    
+    /// Send encoded commands packed into as few command streams of up to
+    /// [`MAX_COMMAND_BYTES`] as they fit in, so long batches still only cost
+    /// a handful of bus start/stop cycles. A single command is never split
+    /// across two.
+    /// 
+    /// This is what the drivers send their commands through, each with its
+    /// own command set's encoder.
+    fn send_batched(&mut self, commands: impl IntoIterator<Item = EncodedCommand>) -> Result<(), Self::Error>
+    where Self: Sized {
+        let mut buffer = [0; MAX_COMMAND_BYTES];
+        let mut len = 0;
+        
+        for encoded in commands {
+            if len + encoded.len() > buffer.len() {
+                self.send_commands(&buffer[..len])?;
+                len = 0;
+            }
+            
+            buffer[len..len + encoded.len()].copy_from_slice(encoded.as_bytes());
+            len += encoded.len();
+        }
+        
+        if len > 0 {
+            self.send_commands(&buffer[..len])?;
+        }
+        
+        Ok(())
+    }
+    
+    /// Send bytes to be written into the display RAM.
+    fn send_data(&mut self, data: &[u8]) -> Result<(), Self::Error>;
+    
+    /// [`send_data`](Interface::send_data) from flash, a chunk at a time.
+    fn send_flash_data(&mut self, data: PmBytes) -> Result<(), Self::Error> {
+        let mut buffer = [0; CHUNK_SIZE];
+        
+        for chunk in data.chunks(CHUNK_SIZE) {
+            self.send_data(chunk.load_into(&mut buffer))?;
+        }
+        
+        Ok(())
+    }
+}
+
+/// I2C transport.
+pub struct I2cInterface<I> {
+    i2c: I,
+    address: u8,
+}
+
+/// I2C addresses an SSD1306 can be strapped to.
+pub const ADDRESSES: [u8; 2] = [0x3C, 0x3D];
+
+impl<I> I2cInterface<I> where I: i2c::Write {
+    /// Find out which of the [`ADDRESSES`] a display is answering on.
+    /// 
+    /// This sends a no-op command to each address, so it's safe to call
+    /// before the display has been initialized.
+    pub fn probe(i2c: &mut I) -> Result<u8, Ssd1306Error<I::Error>> {
+        let noop = Command::NoOp.encode();
+        let probe = [COMMAND_STREAM, noop.as_bytes()[0]];
+        
+        ADDRESSES
+            .into_iter()
+            .find(|&address| i2c.write(address, &probe).is_ok())
+            .ok_or(Ssd1306Error::NotPresent)
+    }
+    
+    pub fn new(i2c: I, address: u8) -> Self {
+        Self { i2c, address }
+    }
+    
+    /// Consume the interface and hand back the underlying bus.
+    pub fn release(self) -> I {
+        self.i2c
+    }
+}
+
+impl<I> Interface for I2cInterface<I> where I: i2c::Write {
+    type Error = I::Error;
+    
+    fn send_commands(&mut self, commands: &[u8]) -> Result<(), I::Error> {
+        let mut buffer = [COMMAND_STREAM; MAX_COMMAND_BYTES + 1];
+        
+        for chunk in commands.chunks(MAX_COMMAND_BYTES) {
+            buffer[1..=chunk.len()].copy_from_slice(chunk);
+            self.i2c.write(self.address, &buffer[..=chunk.len()])?
+        }
+        
+        Ok(())
+    }
+    
+    #[inline(always)]
+    fn send_data(&mut self, data: &[u8]) -> Result<(), I::Error> {
+        // only write to the display in small chunks of bytes
+        let mut buffer: [u8; CHUNK_SIZE + 1] = [DATA_STREAM; CHUNK_SIZE + 1];
+        
+        for chunk in data.chunks(CHUNK_SIZE) {
+            buffer[1..=chunk.len()].copy_from_slice(chunk);
+            self.i2c.write(self.address, &buffer[..=chunk.len()])?
+        }
+        
+        Ok(())
+    }
+    
+    /// Loads each chunk from flash right behind the control byte, so it's
+    /// only copied once.
+    fn send_flash_data(&mut self, data: PmBytes) -> Result<(), I::Error> {
+        let mut buffer: [u8; CHUNK_SIZE + 1] = [DATA_STREAM; CHUNK_SIZE + 1];
+        
+        for chunk in data.chunks(CHUNK_SIZE) {
+            let len = chunk.load_into(&mut buffer[1..]).len();
+            self.i2c.write(self.address, &buffer[..=len])?
+        }
+        
+        Ok(())
+    }
+}
+
+/// Errors from the SPI transport.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SpiError<SE, PE> {
+    /// The SPI peripheral failed.
+    Spi(SE),
+    
+    /// Setting one of the D/C, CS or RES pins failed.
+    Pin(PE),
+}
+
+/// 4-wire SPI transport.
+/// 
+/// The D/C pin is low for commands and high for data, CS is active low, and
+/// RES resets the controller while it's held low.
+pub struct SpiInterface<SPI, DC, CS, RST> {
+    spi: SPI,
+    dc: DC,
+    cs: CS,
+    rst: RST,
+}
+
+impl<SPI, DC, CS, RST, PE> SpiInterface<SPI, DC, CS, RST>
+where
+    SPI: spi::Write<u8>,
+    DC: OutputPin<Error = PE>,
+    CS: OutputPin<Error = PE>,
+    RST: OutputPin<Error = PE>,
+{
+    pub fn new(spi: SPI, dc: DC, cs: CS, rst: RST) -> Self {
+        Self { spi, dc, cs, rst }
+    }
+    
+    /// Consume the interface and hand back the bus and pins.
+    pub fn release(self) -> (SPI, DC, CS, RST) {
+        (self.spi, self.dc, self.cs, self.rst)
+    }
+    
+    /// Pulse the RES pin to hardware-reset the controller.
+    /// 
+    /// Over SPI this has to happen before the display is initialized.
+    pub fn reset(&mut self, delay: &mut impl DelayUs<u16>) -> Result<(), SpiError<SPI::Error, PE>> {
+        self.rst.set_high().map_err(SpiError::Pin)?;
+        delay.delay_us(1_000);
+        self.rst.set_low().map_err(SpiError::Pin)?;
+        // the datasheet asks for at least 3us
+        delay.delay_us(10);
+        self.rst.set_high().map_err(SpiError::Pin)?;
+        delay.delay_us(1_000);
+        Ok(())
+    }
+    
+    fn transfer(&mut self, data_mode: bool, bytes: &[u8]) -> Result<(), SpiError<SPI::Error, PE>> {
+        if data_mode {
+            self.dc.set_high().map_err(SpiError::Pin)?;
+        } else {
+            self.dc.set_low().map_err(SpiError::Pin)?;
+        }
+        
+        self.cs.set_low().map_err(SpiError::Pin)?;
+        let result = self.spi.write(bytes).map_err(SpiError::Spi);
+        // release CS even if the transfer failed
+        self.cs.set_high().map_err(SpiError::Pin)?;
+        
+        result
+    }
+}
+
+impl<SPI, DC, CS, RST, PE> Interface for SpiInterface<SPI, DC, CS, RST>
+where
+    SPI: spi::Write<u8>,
+    DC: OutputPin<Error = PE>,
+    CS: OutputPin<Error = PE>,
+    RST: OutputPin<Error = PE>,
+{
+    type Error = SpiError<SPI::Error, PE>;
+    
+    /// Splits the stream just like over I2C, so both buses see the same
+    /// transactions.
+    fn send_commands(&mut self, commands: &[u8]) -> Result<(), Self::Error> {
+        for chunk in commands.chunks(MAX_COMMAND_BYTES) {
+            self.transfer(false, chunk)?;
+        }
+        
+        Ok(())
+    }
+    
+    fn send_data(&mut self, data: &[u8]) -> Result<(), Self::Error> {
+        self.transfer(true, data)
+    }
+}
@@ -0,0 +1,446 @@
+//! Hierarchical settings menus, driven by four buttons.
+//! 
+//! A menu tree is a set of `const` [`Menu`]s. Items read and change the
+//! settings through plain functions, so the whole tree can be built at
+//! compile time without knowing where the settings live:
+//! 
+//! ```ignore
+//! const ROOT: Menu<Settings> = Menu {
+//!     title: pm_str!("SETTINGS"),
+//!     items: &[Item {
+//!         label: pm_str!("Rev power"),
+//!         kind: ItemKind::Number(NumberEditor {
+//!             min: 10, max: 100, step: 5, unit: pm_str!("%"),
+//!             get: |settings| settings.rev_power as i32,
+//!             set: |settings, value| settings.rev_power = value as u8,
+//!         }),
+//!     }],
+//! };
+//! 
+//! let mut menu = MenuState::new(&ROOT);
+//! match menu.handle(Input::Up, &mut settings) { ... }
+//! menu.draw(&mut display, &settings)?;
+//! ```
+//! 
+//! The title goes on the top page, and every page below it shows one item.
+//! Lists longer than that scroll.
+
+use crate::progmem::PmStr;
+
+use super::command::Page;
+use super::display::Display;
+use super::error::InvalidParameter;
+use super::font::FONT_5X7;
+use super::size::DisplaySize;
+use super::strip::MAX_WIDTH;
+use super::text::render_line;
+use super::ui::{char_count, format_number, render_pm_str, text_width, Align, NUMBER_LEN};
+
+/// How deep menus can be nested, counting the root menu.
+pub const MAX_DEPTH: usize = 4;
+
+/// The buttons that drive the menu.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Input {
+    Up,
+    Down,
+    Select,
+    Back,
+}
+
+/// What an input did.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Event {
+    /// Nothing, e.g. `Select` on an empty menu.
+    Ignored,
+    
+    /// The selection or an editor moved. Only the menu has to be redrawn.
+    Moved,
+    
+    /// An edit was committed or an action run, so the settings may have
+    /// changed and have to be applied.
+    Changed,
+    
+    /// `Back` was pressed in the root menu.
+    Closed,
+}
+
+/// A list of items under a title. `C` is whatever holds the settings.
+pub struct Menu<C: 'static> {
+    pub title: PmStr,
+    pub items: &'static [Item<C>],
+}
+
+pub struct Item<C: 'static> {
+    pub label: PmStr,
+    pub kind: ItemKind<C>,
+}
+
+/// What happens when an item is selected.
+pub enum ItemKind<C: 'static> {
+    /// Open another menu.
+    Submenu(&'static Menu<C>),
+    
+    /// Edit a number.
+    Number(NumberEditor<C>),
+    
+    /// Pick one of a list of options.
+    Choice(ChoicePicker<C>),
+    
+    /// Run a function, optionally after asking first.
+    Action(Action<C>),
+}
+
+/// A number from `min` to `max`, changed `step` at a time with Up/Down.
+/// Select stores it, Back leaves it as it was.
+pub struct NumberEditor<C> {
+    pub min: i32,
+    pub max: i32,
+    pub step: i32,
+    
+    /// Shown right after the number, e.g. `%`.
+    pub unit: PmStr,
+    
+    pub get: fn(&C) -> i32,
+    pub set: fn(&mut C, i32),
+}
+
+/// One of `options`, by index. Up/Down go through the options, Select
+/// stores the one shown, Back leaves it as it was.
+pub struct ChoicePicker<C> {
+    pub options: &'static [PmStr],
+    pub get: fn(&C) -> usize,
+    pub set: fn(&mut C, usize),
+}
+
+/// A function to run, e.g. resetting the settings to their defaults.
+pub struct Action<C> {
+    /// If set, a dialog shows this question and the action only runs if the
+    /// answer is yes.
+    pub confirm: Option<PmStr>,
+    
+    pub run: fn(&mut C),
+}
+
+/// A menu on the stack, and where in it the user is.
+struct Level<C: 'static> {
+    menu: &'static Menu<C>,
+    selected: u8,
+    
+    /// First item on screen.
+    top: u8,
+}
+
+// derive would need `C: Copy`
+impl<C> Clone for Level<C> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<C> Copy for Level<C> {}
+
+/// An editor that's open on the selected item.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Editing {
+    Number(i32),
+    Choice(usize),
+    
+    /// The confirm dialog, `true` if "yes" is selected.
+    Confirm(bool),
+}
+
+/// Where the user is in a menu tree, and what's being edited.
+pub struct MenuState<C: 'static> {
+    stack: [Level<C>; MAX_DEPTH],
+    depth: usize,
+    editing: Option<Editing>,
+    dirty: bool,
+}
+
+impl<C> MenuState<C> {
+    pub const fn new(root: &'static Menu<C>) -> Self {
+        Self {
+            stack: [Level { menu: root, selected: 0, top: 0 }; MAX_DEPTH],
+            depth: 1,
+            editing: None,
+            dirty: true,
+        }
+    }
+    
+    /// Go back to the top of the root menu, dropping any open editor.
+    pub fn reset(&mut self) {
+        self.stack[0].selected = 0;
+        self.stack[0].top = 0;
+        self.depth = 1;
+        self.editing = None;
+        self.dirty = true;
+    }
+    
+    /// Redraw everything on the next [`draw`](MenuState::draw), e.g. after
+    /// something else was shown or the settings changed behind the menu's
+    /// back.
+    pub fn invalidate(&mut self) {
+        self.dirty = true;
+    }
+    
+    fn level(&self) -> &Level<C> {
+        &self.stack[self.depth - 1]
+    }
+    
+    fn level_mut(&mut self) -> &mut Level<C> {
+        &mut self.stack[self.depth - 1]
+    }
+    
+    /// The item the cursor is on, if the menu isn't empty.
+    fn selected_item(&self) -> Option<&'static Item<C>> {
+        let level = self.level();
+        level.menu.items.get(level.selected as usize)
+    }
+    
+    /// React to a button press. `context` is only changed when an edit is
+    /// committed or an action runs, which returns [`Event::Changed`].
+    pub fn handle(&mut self, input: Input, context: &mut C) -> Event {
+        let event = match self.editing {
+            Some(editing) => self.handle_editor(editing, input, context),
+            None => self.handle_list(input, context),
+        };
+        
+        if event != Event::Ignored {
+            self.dirty = true;
+        }
+        event
+    }
+    
+    fn handle_list(&mut self, input: Input, context: &mut C) -> Event {
+        let count = self.level().menu.items.len() as u8;
+        
+        match input {
+            Input::Up | Input::Down if count == 0 => Event::Ignored,
+            Input::Up => {
+                let level = self.level_mut();
+                level.selected = level.selected.checked_sub(1).unwrap_or(count - 1);
+                Event::Moved
+            },
+            Input::Down => {
+                let level = self.level_mut();
+                level.selected = (level.selected + 1) % count;
+                Event::Moved
+            },
+            Input::Select => {
+                let Some(item) = self.selected_item() else { return Event::Ignored };
+                match &item.kind {
+                    ItemKind::Submenu(menu) => {
+                        if self.depth == MAX_DEPTH {
+                            return Event::Ignored;
+                        }
+                        self.stack[self.depth] = Level { menu: *menu, selected: 0, top: 0 };
+                        self.depth += 1;
+                        Event::Moved
+                    },
+                    ItemKind::Number(editor) => {
+                        self.editing = Some(Editing::Number((editor.get)(context)));
+                        Event::Moved
+                    },
+                    ItemKind::Choice(picker) => {
+                        self.editing = Some(Editing::Choice((picker.get)(context)));
+                        Event::Moved
+                    },
+                    ItemKind::Action(action) => {
+                        if action.confirm.is_some() {
+                            self.editing = Some(Editing::Confirm(false));
+                            return Event::Moved;
+                        }
+                        (action.run)(context);
+                        Event::Changed
+                    },
+                }
+            },
+            Input::Back => {
+                if self.depth == 1 {
+                    return Event::Closed;
+                }
+                self.depth -= 1;
+                Event::Moved
+            },
+        }
+    }
+    
+    fn handle_editor(&mut self, editing: Editing, input: Input, context: &mut C) -> Event {
+        let Some(item) = self.selected_item() else {
+            self.editing = None;
+            return Event::Moved;
+        };
+        
+        if input == Input::Back {
+            self.editing = None;
+            return Event::Moved;
+        }
+        
+        match (editing, &item.kind) {
+            (Editing::Number(value), ItemKind::Number(editor)) => match input {
+                Input::Up => self.editing = Some(Editing::Number(value.saturating_add(editor.step).min(editor.max))),
+                Input::Down => self.editing = Some(Editing::Number(value.saturating_sub(editor.step).max(editor.min))),
+                _ => {
+                    (editor.set)(context, value);
+                    self.editing = None;
+                    return Event::Changed;
+                },
+            },
+            (Editing::Choice(index), ItemKind::Choice(picker)) => {
+                let count = picker.options.len();
+                match input {
+                    Input::Up => self.editing = Some(Editing::Choice((index + count - 1) % count)),
+                    Input::Down => self.editing = Some(Editing::Choice((index + 1) % count)),
+                    _ => {
+                        (picker.set)(context, index);
+                        self.editing = None;
+                        return Event::Changed;
+                    },
+                }
+            },
+            (Editing::Confirm(yes), ItemKind::Action(action)) => match input {
+                Input::Up | Input::Down => self.editing = Some(Editing::Confirm(!yes)),
+                _ => {
+                    self.editing = None;
+                    if !yes {
+                        return Event::Moved;
+                    }
+                    (action.run)(context);
+                    return Event::Changed;
+                },
+            },
+            // the editor doesn't belong to the item, which can't happen
+            _ => self.editing = None,
+        }
+        
+        Event::Moved
+    }
+    
+    /// Redraw the whole screen if anything changed. Returns whether anything
+    /// was written.
+    pub fn draw<D>(&mut self, display: &mut D, context: &C) -> Result<bool, D::Error>
+    where D: Display, D::Error: From<InvalidParameter> {
+        if !self.dirty {
+            return Ok(false);
+        }
+        
+        let pages = D::Size::PAGES;
+        let rows = pages.saturating_sub(1).max(1);
+        
+        // keep the selection on screen
+        let level = self.level_mut();
+        if level.selected < level.top {
+            level.top = level.selected;
+        } else if level.selected >= level.top + rows {
+            level.top = level.selected + 1 - rows;
+        }
+        
+        let mut line = [0; MAX_WIDTH];
+        let line = &mut line[..D::Size::WIDTH as usize];
+        for page in 0..pages {
+            line.fill(0);
+            match self.editing {
+                Some(Editing::Confirm(yes)) => self.render_dialog(page, pages, yes, line),
+                _ => self.render_list(page, context, line),
+            }
+            display.write_page(Page::try_from(page)?, 0, line)?;
+        }
+        
+        self.dirty = false;
+        Ok(true)
+    }
+    
+    fn render_list(&self, page: u8, context: &C, line: &mut [u8]) {
+        let level = self.level();
+        if page == 0 {
+            render_title(line, level.menu.title);
+            return;
+        }
+        
+        let index = level.top + page - 1;
+        let Some(item) = level.menu.items.get(index as usize) else { return };
+        let selected = index == level.selected;
+        let editing = selected && self.editing.is_some();
+        
+        render_pm_str(line, 2, item.label);
+        
+        let end = line.len() - 2;
+        let (start, end) = match &item.kind {
+            ItemKind::Submenu(_) => render_right(line, end, ">", None),
+            ItemKind::Number(editor) => {
+                let value = match self.editing {
+                    Some(Editing::Number(value)) if editing => value,
+                    _ => (editor.get)(context),
+                };
+                let mut buffer = [0; NUMBER_LEN];
+                render_right(line, end, format_number(value, 0, &mut buffer), Some(editor.unit))
+            },
+            ItemKind::Choice(picker) => {
+                let index = match self.editing {
+                    Some(Editing::Choice(index)) if editing => index,
+                    _ => (picker.get)(context),
+                };
+                match picker.options.get(index) {
+                    Some(&option) => render_right(line, end, "", Some(option)),
+                    None => render_right(line, end, "?", None),
+                }
+            },
+            ItemKind::Action(_) => (end, end),
+        };
+        
+        if editing {
+            // the value being edited is highlighted, with a column to spare
+            let end = (end + 1).min(line.len());
+            invert(&mut line[start.saturating_sub(1)..end]);
+        } else if selected {
+            invert(line);
+        }
+    }
+    
+    fn render_dialog(&self, page: u8, pages: u8, yes: bool, line: &mut [u8]) {
+        let Some(item) = self.selected_item() else { return };
+        let ItemKind::Action(Action { confirm: Some(question), .. }) = item.kind else { return };
+        
+        let width = line.len();
+        if page == 0 {
+            render_title(line, item.label);
+        } else if page == pages / 2 - 1 {
+            let col = Align::Center.start(width as u8, text_width(char_count(question)));
+            render_pm_str(line, col, question);
+        } else if page == pages / 2 + 1 {
+            for (text, center, highlighted) in [("NO", width / 4, !yes), ("YES", width * 3 / 4, yes)] {
+                let start = center - text_width(text.len()) / 2;
+                let end = render_line(&FONT_5X7, line, start as u8, text) as usize;
+                if highlighted {
+                    invert(&mut line[start - 1..end]);
+                }
+            }
+        }
+    }
+}
+
+/// Render a title, centered on a highlighted line.
+fn render_title(line: &mut [u8], title: PmStr) {
+    let col = Align::Center.start(line.len() as u8, text_width(char_count(title)));
+    render_pm_str(line, col, title);
+    invert(line);
+}
+
+/// Render `text` and then `unit` so they end at column `end`, and return the
+/// columns they took.
+fn render_right(line: &mut [u8], end: usize, text: &str, unit: Option<PmStr>) -> (usize, usize) {
+    let chars = text.len() + unit.map_or(0, char_count);
+    let start = end.saturating_sub(text_width(chars));
+    let col = render_line(&FONT_5X7, line, start as u8, text);
+    if let Some(unit) = unit {
+        render_pm_str(line, col, unit);
+    }
+    (start, end)
+}
+
+fn invert(bytes: &mut [u8]) {
+    for byte in bytes {
+        *byte = !*byte;
+    }
+}
@@ -1,7 +1,49 @@
 // https://cdn-shop.adafruit.com/datasheets/SSD1306.pdf
 
+//! Drivers for the SSD1306 family of monochrome OLED controllers.
+//! 
+//! `display` is the controller-agnostic layer; `command`/`ssd1306` and
+//! `sh1106` are the per-controller command sets and drivers.
+
+pub mod animation;
+pub mod bitmap;
+pub mod canvas;
 pub mod command;
+pub mod decode;
+pub mod digits;
+pub mod display;
+pub mod error;
+pub mod font;
+pub mod framebuffer;
+#[cfg(feature = "graphics")]
+pub mod graphics;
+pub mod interface;
+pub mod menu;
+pub mod mode;
+pub mod orientation;
+pub mod scroll;
+pub mod sh1106;
+pub mod size;
+#[allow(clippy::module_inception)]
 pub mod ssd1306;
+pub mod strip;
+pub mod text;
+pub mod transfer;
+pub mod transition;
+pub mod ui;
 
+pub use bitmap::Bitmap;
+pub use canvas::{Canvas, Drawable};
+pub use display::Display;
+pub use error::Ssd1306Error;
+pub use framebuffer::FrameBuffer;
+pub use interface::{I2cInterface, SpiInterface};
+pub use sh1106::SH1106;
+pub use text::TextDisplay;
 pub use ssd1306::SSD1306;
 
+
+#[cfg(test)]
+mod emulator;
+#[cfg(test)]
+mod tests;
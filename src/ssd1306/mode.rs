@@ -0,0 +1,83 @@
+//! Type-level addressing modes for the [`SSD1306`](super::SSD1306) driver.
+//! 
+//! The column/page start commands only do anything in page addressing mode,
+//! and the column/page window commands only do anything in horizontal or
+//! vertical addressing mode. Tracking the mode in the driver's type means
+//! sending the wrong kind of addressing command is a compile error instead of
+//! a silently ignored write.
+
+use super::command::{AddressMode, Command, EncodedCommand, PageCommand, WindowCommand};
+
+/// An addressing mode the driver can be in.
+pub trait AddressingMode {
+    const MODE: AddressMode;
+}
+
+/// Marker for modes that use a column/page window (horizontal and vertical).
+pub trait WindowMode: AddressingMode {}
+
+/// Page addressing mode (the controller's reset state).
+pub struct PageAddressing;
+
+/// Horizontal addressing mode.
+pub struct HorizontalAddressing;
+
+/// Vertical addressing mode.
+pub struct VerticalAddressing;
+
+impl AddressingMode for PageAddressing {
+    const MODE: AddressMode = AddressMode::Page;
+}
+
+impl AddressingMode for HorizontalAddressing {
+    const MODE: AddressMode = AddressMode::Horizontal;
+}
+
+impl AddressingMode for VerticalAddressing {
+    const MODE: AddressMode = AddressMode::Vertical;
+}
+
+impl WindowMode for HorizontalAddressing {}
+impl WindowMode for VerticalAddressing {}
+
+/// A command that can be sent while the driver is in addressing mode `M`.
+pub trait ModeCommand<M> {
+    fn encode(&self) -> EncodedCommand;
+    
+    /// The command, if it's one of the mode-independent ones.
+    fn general(&self) -> Option<Command> {
+        None
+    }
+}
+
+impl<M: AddressingMode> ModeCommand<M> for Command {
+    fn encode(&self) -> EncodedCommand {
+        Command::encode(*self)
+    }
+    
+    fn general(&self) -> Option<Command> {
+        Some(*self)
+    }
+}
+
+impl ModeCommand<PageAddressing> for PageCommand {
+    fn encode(&self) -> EncodedCommand {
+        PageCommand::encode(*self)
+    }
+}
+
+impl<M: WindowMode> ModeCommand<M> for WindowCommand {
+    fn encode(&self) -> EncodedCommand {
+        WindowCommand::encode(*self)
+    }
+}
+
+impl<M, C: ModeCommand<M>> ModeCommand<M> for &C {
+    fn encode(&self) -> EncodedCommand {
+        (**self).encode()
+    }
+    
+    fn general(&self) -> Option<Command> {
+        (**self).general()
+    }
+}
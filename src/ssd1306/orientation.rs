@@ -0,0 +1,49 @@
+//! Which way up the panel is mounted.
+//! 
+//! The controllers can't rotate by 90 degrees, only flip the segment (column)
+//! and COM (row) scan directions. A 180 degree rotation is both flips at once.
+
+/// Rotation of the picture on the panel.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Rotation {
+    /// Column 0 on the left, page 0 at the top, with the pin header on top
+    /// of the common modules.
+    #[default]
+    Deg0,
+    
+    /// Upside down, for panels mounted with the pin header at the bottom.
+    Deg180,
+}
+
+/// Rotation plus independent mirroring of either axis.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Orientation {
+    pub rotation: Rotation,
+    
+    /// Swap left and right.
+    pub mirror_horizontal: bool,
+    
+    /// Swap top and bottom.
+    pub mirror_vertical: bool,
+}
+
+impl Orientation {
+    pub const fn new(rotation: Rotation) -> Self {
+        Self { rotation, mirror_horizontal: false, mirror_vertical: false }
+    }
+    
+    pub const fn mirrored(self, horizontal: bool, vertical: bool) -> Self {
+        Self { mirror_horizontal: horizontal, mirror_vertical: vertical, ..self }
+    }
+    
+    /// Argument for `SegmentRemap`: whether RAM column 127 is mapped to SEG0.
+    pub const fn segment_remap(self) -> bool {
+        matches!(self.rotation, Rotation::Deg0) != self.mirror_horizontal
+    }
+    
+    /// Argument for `SetComScanDir`: whether rows are scanned from COM[N-1]
+    /// down to COM0.
+    pub const fn com_scan_reversed(self) -> bool {
+        matches!(self.rotation, Rotation::Deg0) != self.mirror_vertical
+    }
+}
@@ -0,0 +1,188 @@
+use super::command::{Command, HorizontalScroll, Page, ScrollInterval, VerticalHorizontalScroll};
+use super::error::{InvalidParameter, Ssd1306Error};
+use super::interface::Interface;
+use super::mode::AddressingMode;
+use super::size::DisplaySize;
+use super::ssd1306::SSD1306;
+
+#[derive(Debug, Clone, Copy)]
+enum Direction {
+    Horizontal(HorizontalScroll),
+    Diagonal(VerticalHorizontalScroll, u8),
+}
+
+/// Builder for a continuous hardware scroll, created by [`SSD1306::scroll`].
+/// 
+/// Nothing is sent until [`ScrollBuilder::start`], which checks the
+/// configuration against the datasheet's constraints first.
+pub struct ScrollBuilder<'a, DI, S, M> where DI: Interface {
+    display: &'a mut SSD1306<DI, S, M>,
+    direction: Direction,
+    interval: ScrollInterval,
+    start_page: Page,
+    end_page: Option<Page>,
+    area: Option<(u8, u8)>,
+}
+
+impl<'a, DI, S: DisplaySize, M: AddressingMode> ScrollBuilder<'a, DI, S, M> where DI: Interface {
+    /// Scroll horizontally. (default: `HorizontalScroll::Right`)
+    pub fn horizontal(mut self, direction: HorizontalScroll) -> Self {
+        self.direction = Direction::Horizontal(direction);
+        self
+    }
+    
+    /// Scroll vertically by `offset` rows per step, while also scrolling
+    /// the page band horizontally by one column.
+    pub fn diagonal(mut self, direction: VerticalHorizontalScroll, offset: u8) -> Self {
+        self.direction = Direction::Diagonal(direction, offset);
+        self
+    }
+    
+    /// Only scroll pages `start_page..=end_page` horizontally. (default: all pages)
+    pub fn pages(mut self, start_page: Page, end_page: Page) -> Self {
+        self.start_page = start_page;
+        self.end_page = Some(end_page);
+        self
+    }
+    
+    /// Time between each scroll step. (default: `ScrollInterval::Frames5`)
+    pub fn interval(mut self, interval: ScrollInterval) -> Self {
+        self.interval = interval;
+        self
+    }
+    
+    /// Keep the first `top` rows fixed and vertically scroll the `bottom`
+    /// rows below them. Only diagonal scrolling scrolls vertically, so this
+    /// is rejected for a horizontal one. The display's start line has to be
+    /// within the first `bottom` rows.
+    /// (default: the whole display scrolls)
+    pub fn area(mut self, top: u8, bottom: u8) -> Self {
+        self.area = Some((top, bottom));
+        self
+    }
+    
+    /// The last page of the band, defaulting to the panel's last page.
+    fn end_page(&self) -> Page {
+        self.end_page
+            .or_else(|| Page::try_from(S::PAGES - 1).ok())
+            .unwrap_or(Page::Page7)
+    }
+    
+    fn validate(&self) -> Result<(), Ssd1306Error<DI::Error>> {
+        if self.end_page() as u8 >= S::PAGES {
+            return Err(InvalidParameter::Page.into());
+        }
+        if self.start_page as u8 > self.end_page() as u8 {
+            return Err(InvalidParameter::PageRange.into());
+        }
+        
+        match self.direction {
+            Direction::Horizontal(_) => {
+                // there's no vertical scroll for an area to apply to
+                if self.area.is_some() {
+                    return Err(InvalidParameter::ScrollAreaNotVertical.into());
+                }
+            }
+            Direction::Diagonal(_, offset) => {
+                let (top, bottom) = self.area.unwrap_or((0, S::HEIGHT));
+                
+                if top as u16 + bottom as u16 > S::HEIGHT as u16 {
+                    return Err(InvalidParameter::ScrollArea.into());
+                }
+                if self.display.start_line >= bottom {
+                    return Err(InvalidParameter::StartLine.into());
+                }
+                if offset >= bottom {
+                    return Err(InvalidParameter::ScrollOffset.into());
+                }
+            }
+        }
+        
+        Ok(())
+    }
+    
+    /// Validate the configuration, then send the setup and activation as a
+    /// single command stream.
+    /// 
+    /// If the display is already scrolling, it's stopped first, since the
+    /// setup can't be changed while scrolling is active.
+    pub fn start(self) -> Result<(), Ssd1306Error<DI::Error>> {
+        self.validate()?;
+        
+        let mut commands = [Command::NoOp; 4];
+        let mut len = 0;
+        
+        if self.display.is_scrolling() {
+            commands[len] = Command::EnableScrolling(false);
+            len += 1;
+        }
+        
+        match self.direction {
+            Direction::Horizontal(direction) => {
+                commands[len] = Command::SetupHorizontalScroll {
+                    direction,
+                    interval: self.interval,
+                    start_page: self.start_page,
+                    end_page: self.end_page(),
+                };
+                len += 1;
+            }
+            Direction::Diagonal(direction, offset) => {
+                let (top, bottom) = self.area.unwrap_or((0, S::HEIGHT));
+                commands[len] = Command::SetupVerticalScrollArea { top, bottom };
+                commands[len + 1] = Command::SetupVerticalAndHorizontalScroll {
+                    direction,
+                    interval: self.interval,
+                    start_page: self.start_page,
+                    end_page: self.end_page(),
+                    offset,
+                };
+                len += 2;
+            }
+        }
+        
+        // activation has to come directly after the setup
+        commands[len] = Command::EnableScrolling(true);
+        len += 1;
+        
+        self.display.send_commands(commands[..len].iter())
+    }
+}
+
+impl<DI, S: DisplaySize, M: AddressingMode> SSD1306<DI, S, M> where DI: Interface {
+    /// Configure a continuous hardware scroll.
+    pub fn scroll(&mut self) -> ScrollBuilder<'_, DI, S, M> {
+        ScrollBuilder {
+            display: self,
+            direction: Direction::Horizontal(HorizontalScroll::Right),
+            interval: ScrollInterval::Frames5,
+            start_page: Page::Page0,
+            end_page: None,
+            area: None,
+        }
+    }
+    
+    /// Stop any active scrolling.
+    /// 
+    /// The RAM content is left in an undefined state afterwards, see
+    /// [`SSD1306::ram_needs_rewrite`].
+    pub fn stop_scrolling(&mut self) -> Result<(), Ssd1306Error<DI::Error>> {
+        self.send_command(Command::EnableScrolling(false))
+    }
+    
+    pub fn is_scrolling(&self) -> bool {
+        self.scrolling
+    }
+    
+    /// Whether the RAM has to be rewritten because scrolling was deactivated,
+    /// or the horizontal orientation changed, since the last call to
+    /// [`SSD1306::mark_ram_rewritten`].
+    pub fn ram_needs_rewrite(&self) -> bool {
+        self.ram_stale
+    }
+    
+    /// Tell the driver that the whole RAM has been rewritten.
+    pub fn mark_ram_rewritten(&mut self) {
+        self.ram_stale = false;
+    }
+}
@@ -0,0 +1,332 @@
+//! Driver for the SH1106, the controller on most cheap 1.3" OLEDs.
+//! 
+//! It looks a lot like an SSD1306, but has 132 columns of RAM (the panel is
+//! centered in it) and only supports page addressing, so frames have to be
+//! written one page at a time.
+
+#![allow(dead_code)]
+
+use core::marker::PhantomData;
+
+use crate::progmem::PmBytes;
+
+use super::command::{self, EncodedCommand, Page};
+use super::display::Display;
+use super::error::{InvalidParameter, Ssd1306Error};
+use super::interface::{Interface, MAX_COMMAND_BYTES};
+use super::orientation::{Orientation, Rotation};
+use super::size::{DisplaySize, DisplaySize128x64};
+
+/// SH1106 command set.
+/// 
+/// https://www.velleman.eu/downloads/29/infosheets/sh1106_datasheet.pdf
+#[derive(Debug, Copy, Clone)]
+pub enum Command {
+    /// ### Set Column Address.
+    /// 
+    /// Set the column address register for the next data write, from 0-131.
+    /// (RESET=0)
+    ColumnStart(u8), // 0x00-0x0F + 0x10-0x1F
+    
+    /// ### Set Pump Voltage Value.
+    /// 
+    /// (RESET = `PumpVoltage::V8_0`)
+    PumpVoltage(PumpVoltage), // 0x30-0x33
+    
+    /// ### Set Display Start Line.
+    /// 
+    /// Set display RAM display start line register from 0-63. (RESET=0)
+    SetStartLine(u8), // 0x40-0x7F
+    
+    /// ### Set Contrast Control.
+    /// 
+    /// Contrast increases as the value increases. (RESET=0x80)
+    SetContrast{contrast: u8}, // 0x81
+    
+    /// ### Set Segment Remap.
+    /// 
+    /// `false`: column address 0 is mapped to SEG0 (RESET)
+    /// 
+    /// `true`: column address 131 is mapped to SEG0
+    SegmentRemap(bool), // 0xA0 | 0xA1
+    
+    /// ### Set Entire Display ON/OFF.
+    /// 
+    /// `true`: Entire display ON. Output ignores RAM content. (RESET=false)
+    AllPixelsOn(bool), // 0xA4 | 0xA5
+    
+    /// ### Set Normal/Reverse Display.
+    /// 
+    /// `true`: pixels are ON if the RAM content is `0`. (RESET=false)
+    InvertDisplay(bool), // 0xA6 | 0xA7
+    
+    /// ### Set Multiplex Ratio.
+    /// 
+    /// Set MUX ratio to `ratio+1` MUX. (RESET=63)
+    SetMultiplexRatio{ratio: u8}, // 0xA8
+    
+    /// ### Set DC-DC ON/OFF.
+    /// 
+    /// The SH1106's equivalent of the SSD1306 charge pump. (RESET=true)
+    DcDc(bool), // 0xAD
+    
+    /// ### Set Display ON/OFF.
+    /// 
+    /// `false`: Display OFF (sleep mode). (RESET)
+    DisplayEnable(bool), // 0xAE | 0xAF
+    
+    /// ### Set Page Address.
+    PageStart(Page), // 0xB0-0xB7
+    
+    /// ### Set Common Output Scan Direction.
+    /// 
+    /// `true`: scan from COM[N-1] to COM0. (RESET=false)
+    SetComScanDir(bool), // 0xC0 | 0xC8
+    
+    /// ### Set Display Offset.
+    /// 
+    /// Set the vertical shift by COM, from 0-63. (RESET=0)
+    DisplayOffset(u8), // 0xD3
+    
+    /// ### Set Display Clock Divide Ratio/Oscillator Frequency.
+    /// 
+    /// both values are 0-15, real divide ratio is `divide_ratio+1`
+    DisplayClockDiv{oscillator_freq: u8, divide_ratio: u8}, // 0xD5
+    
+    /// ### Set Dis-charge/Pre-charge Period.
+    /// 
+    /// Both phases are in units of DCLK, from 1-15. (RESET=2 for both)
+    PreChargePeriod{phase1: u8, phase2: u8}, // 0xD9
+    
+    /// ### Set Common Pads Hardware Configuration.
+    /// 
+    /// `alternative`: `false` for sequential, `true` for alternative (RESET).
+    ComPinConfig{alternative: bool}, // 0xDA
+    
+    /// ### Set VCOM Deselect Level.
+    /// 
+    /// VCOM = 0.430 + `level` x 0.006415 x VREF. (RESET=0x35)
+    VcomDeselect(u8), // 0xDB
+    
+    /// ### Read-Modify-Write.
+    /// 
+    /// Stop the column counter from advancing on reads until `End`.
+    ReadModifyWrite, // 0xE0
+    
+    /// ### End.
+    /// 
+    /// Release read-modify-write mode, restoring the column address.
+    End, // 0xEE
+    
+    /// ### NOP.
+    NoOp, // 0xE3
+}
+
+/// Charge pump output voltage.
+#[derive(Debug, Clone, Copy)]
+pub enum PumpVoltage {
+    V6_4 = 0b00,
+    V7_4 = 0b01,
+    /// default
+    V8_0 = 0b10,
+    V9_0 = 0b11,
+}
+
+impl Command {
+    /// Encode the command into the bytes the controller expects.
+    pub const fn encode(self) -> EncodedCommand {
+        macro_rules! cmd {
+            [$($x:expr),+ $(,)?] => { EncodedCommand::new(&[$($x),+]) }
+        }
+        
+        match self {
+            Command::ColumnStart(addr) => cmd![addr & 0x0F, 0x10 | ((addr >> 4) & 0x0F)],
+            Command::PumpVoltage(voltage) => cmd![0x30 | voltage as u8],
+            Command::SetStartLine(line) => cmd![0x40 | (line & 0x3F)],
+            Command::SetContrast{contrast} => cmd![0x81, contrast],
+            Command::SegmentRemap(remap) => cmd![0xA0 | (remap as u8)],
+            Command::AllPixelsOn(enable) => cmd![0xA4 | (enable as u8)],
+            Command::InvertDisplay(enable) => cmd![0xA6 | (enable as u8)],
+            Command::SetMultiplexRatio{ratio} => cmd![0xA8, ratio & 0x3F],
+            Command::DcDc(enable) => cmd![0xAD, 0x8A | (enable as u8)],
+            Command::DisplayEnable(enable) => cmd![0xAE | (enable as u8)],
+            Command::PageStart(page) => cmd![0xB0 | (page as u8)],
+            Command::SetComScanDir(remap) => cmd![0xC0 | ((remap as u8) << 3)],
+            Command::DisplayOffset(offset) => cmd![0xD3, offset & 0x3F],
+            Command::DisplayClockDiv {
+                oscillator_freq: osc,
+                divide_ratio: div
+            } => cmd![0xD5, ((osc & 0x0F) << 4) | (div & 0x0F)],
+            Command::PreChargePeriod { phase1, phase2 } => {
+                cmd![0xD9, ((phase2 & 0x0F) << 4) | (phase1 & 0x0F)]
+            }
+            Command::ComPinConfig { alternative } => cmd![0xDA, 0x02 | ((alternative as u8) << 4)],
+            Command::VcomDeselect(level) => cmd![0xDB, level],
+            Command::ReadModifyWrite => cmd![0xE0],
+            Command::End => cmd![0xEE],
+            Command::NoOp => cmd![0xE3],
+        }
+    }
+}
+
+/// Columns of RAM in the SH1106.
+const RAM_COLUMNS: u8 = 132;
+
+/// Where the SSD1306's 128 columns sit in the SH1106's 132: column 0 of a
+/// 128 column panel. [`DisplaySize::COLUMN_OFFSET`] counts from here, since
+/// it's a property of the panel, the same whichever controller drives it.
+const RAM_COLUMN_OFFSET: u8 = 2;
+
+/// The init sequence for a panel of size `S` mounted in `orientation`, one
+/// command per entry.
+const fn init_commands<S: DisplaySize>(orientation: Orientation) -> [EncodedCommand; 15] {
+    [
+        Command::DisplayEnable(false).encode(),
+        Command::DisplayClockDiv{oscillator_freq: 0x8, divide_ratio: 0x0}.encode(),
+        Command::SetMultiplexRatio{ratio: S::MUX_RATIO}.encode(),
+        Command::DisplayOffset(0).encode(),
+        Command::SetStartLine(0).encode(),
+        Command::DcDc(true).encode(),
+        Command::PumpVoltage(PumpVoltage::V8_0).encode(),
+        
+        Command::ComPinConfig{alternative: S::COM_PINS.0}.encode(),
+        
+        Command::SegmentRemap(orientation.segment_remap()).encode(),
+        Command::SetComScanDir(orientation.com_scan_reversed()).encode(),
+        
+        // set default brightness
+        Command::PreChargePeriod{phase1: 2, phase2: 2}.encode(),
+        Command::SetContrast{contrast: 0x5F}.encode(),
+        
+        Command::VcomDeselect(0x35).encode(),
+        Command::InvertDisplay(false).encode(),
+        
+        Command::DisplayEnable(true).encode(),
+    ]
+}
+
+const INIT_LEN: usize = command::stream_len(&init_commands::<DisplaySize128x64>(Orientation::new(Rotation::Deg0)));
+const _: () = assert!(INIT_LEN <= MAX_COMMAND_BYTES, "init sequence doesn't fit in one transaction");
+
+/// SH1106 driver for a panel of size `S`.
+pub struct SH1106<DI, S = DisplaySize128x64> where DI: Interface {
+    interface: DI,
+    orientation: Orientation,
+    size: PhantomData<S>,
+}
+
+impl<DI, S: DisplaySize> SH1106<DI, S> where DI: Interface {
+    /// RAM column wired to the panel's column 0: the panel's own offset, on
+    /// top of the controller's. Using this for a panel that doesn't fit in
+    /// the RAM fails to compile.
+    const FIRST_COLUMN: u8 = {
+        assert!(
+            RAM_COLUMN_OFFSET as u16 + S::COLUMN_OFFSET as u16 + S::WIDTH as u16 <= RAM_COLUMNS as u16,
+            "the panel doesn't fit in the SH1106's RAM",
+        );
+        RAM_COLUMN_OFFSET + S::COLUMN_OFFSET
+    };
+    
+    pub fn new(interface: DI, _size: S) -> Self {
+        Self {
+            interface,
+            orientation: Orientation::new(Rotation::Deg0),
+            size: PhantomData,
+        }
+    }
+    
+    /// Set the orientation `initialize` sets up, without touching the bus.
+    pub fn with_orientation(self, orientation: Orientation) -> Self {
+        Self { orientation, ..self }
+    }
+    
+    /// Consume the driver and hand back the underlying interface.
+    pub fn release(self) -> DI {
+        self.interface
+    }
+    
+    /// Bring the display out of reset and turn it on.
+    pub fn initialize(&mut self) -> Result<(), Ssd1306Error<DI::Error>> {
+        let sequence: [u8; INIT_LEN] = command::command_stream(&init_commands::<S>(self.orientation));
+        self.interface.send_commands(&sequence).map_err(Ssd1306Error::Bus)
+    }
+    
+    pub fn send_command(&mut self, command: Command) -> Result<(), Ssd1306Error<DI::Error>> {
+        self.send_commands(core::iter::once(command))
+    }
+    
+    /// Send a batch of commands, packed like the SSD1306's, see
+    /// [`Interface::send_batched`].
+    pub fn send_commands(&mut self, commands: impl IntoIterator<Item = Command>) -> Result<(), Ssd1306Error<DI::Error>> {
+        self.interface.send_batched(commands.into_iter().map(Command::encode)).map_err(Ssd1306Error::Bus)
+    }
+    
+    /// Send raw GDDRAM data at the current position.
+    pub fn send_data(&mut self, data: &[u8]) -> Result<(), Ssd1306Error<DI::Error>> {
+        self.interface.send_data(data).map_err(Ssd1306Error::Bus)
+    }
+    
+    /// Send GDDRAM data at the current position, straight from flash.
+    pub fn send_flash_data(&mut self, data: PmBytes) -> Result<(), Ssd1306Error<DI::Error>> {
+        self.interface.send_flash_data(data).map_err(Ssd1306Error::Bus)
+    }
+    
+    /// Move the RAM pointer to column `col` of `page`.
+    pub fn set_position(&mut self, page: Page, col: u8) -> Result<(), Ssd1306Error<DI::Error>> {
+        if col >= S::WIDTH {
+            return Err(InvalidParameter::Column.into());
+        }
+        if page as u8 >= S::PAGES {
+            return Err(InvalidParameter::Page.into());
+        }
+        
+        self.send_commands([
+            Command::PageStart(page),
+            Command::ColumnStart(Self::FIRST_COLUMN + col),
+        ])
+    }
+}
+
+impl<DI, S: DisplaySize> Display for SH1106<DI, S> where DI: Interface {
+    type Error = Ssd1306Error<DI::Error>;
+    type Size = S;
+    
+    fn write_page(&mut self, page: Page, col: u8, data: &[u8]) -> Result<(), Self::Error> {
+        if data.is_empty() || col as usize + data.len() > S::WIDTH as usize {
+            return Err(InvalidParameter::Column.into());
+        }
+        
+        self.set_position(page, col)?;
+        self.send_data(data)
+    }
+    
+    fn write_page_flash(&mut self, page: Page, col: u8, data: PmBytes) -> Result<(), Self::Error> {
+        if data.is_empty() || col as usize + data.len() > S::WIDTH as usize {
+            return Err(InvalidParameter::Column.into());
+        }
+        
+        self.set_position(page, col)?;
+        self.send_flash_data(data)
+    }
+    
+    fn set_display_on(&mut self, on: bool) -> Result<(), Self::Error> {
+        self.send_command(Command::DisplayEnable(on))
+    }
+    
+    fn set_contrast(&mut self, contrast: u8) -> Result<(), Self::Error> {
+        self.send_command(Command::SetContrast { contrast })
+    }
+    
+    fn set_inverted(&mut self, inverted: bool) -> Result<(), Self::Error> {
+        self.send_command(Command::InvertDisplay(inverted))
+    }
+    
+    fn set_orientation(&mut self, orientation: Orientation) -> Result<(), Self::Error> {
+        self.send_commands([
+            Command::SegmentRemap(orientation.segment_remap()),
+            Command::SetComScanDir(orientation.com_scan_reversed()),
+        ])?;
+        self.orientation = orientation;
+        Ok(())
+    }
+}
@@ -0,0 +1,74 @@
+//! Panel geometries the driver supports.
+//! 
+//! The SSD1306 always has 128x64 pixels of RAM, but smaller panels only wire
+//! up part of it. Everything that depends on which part lives here, so the
+//! rest of the driver can stay generic over the panel.
+
+/// Geometry of a panel driven by an SSD1306.
+pub trait DisplaySize {
+    /// Visible width in pixels.
+    const WIDTH: u8;
+    
+    /// Visible height in pixels. Always a multiple of 8.
+    const HEIGHT: u8;
+    
+    /// First RAM column that's wired to the panel.
+    const COLUMN_OFFSET: u8;
+    
+    /// COM pin hardware configuration `(alternative, remap)`,
+    /// see `Command::ComPinConfig`.
+    const COM_PINS: (bool, bool);
+    
+    /// Value for `Command::SetMultiplexRatio`.
+    const MUX_RATIO: u8 = Self::HEIGHT - 1;
+    
+    /// Number of visible pages.
+    const PAGES: u8 = Self::HEIGHT / 8;
+    
+    /// Bytes needed to hold a whole frame.
+    const BUFFER_SIZE: usize = Self::WIDTH as usize * Self::PAGES as usize;
+}
+
+/// 128x64 panel, e.g. the common 0.96" modules.
+#[derive(Debug, Clone, Copy)]
+pub struct DisplaySize128x64;
+
+/// 128x32 panel, e.g. the 0.91" modules.
+#[derive(Debug, Clone, Copy)]
+pub struct DisplaySize128x32;
+
+/// 72x40 panel, e.g. the 0.42" modules.
+#[derive(Debug, Clone, Copy)]
+pub struct DisplaySize72x40;
+
+/// 64x48 panel, e.g. the 0.66" modules.
+#[derive(Debug, Clone, Copy)]
+pub struct DisplaySize64x48;
+
+impl DisplaySize for DisplaySize128x64 {
+    const WIDTH: u8 = 128;
+    const HEIGHT: u8 = 64;
+    const COLUMN_OFFSET: u8 = 0;
+    const COM_PINS: (bool, bool) = (true, false);
+}
+
+impl DisplaySize for DisplaySize128x32 {
+    const WIDTH: u8 = 128;
+    const HEIGHT: u8 = 32;
+    const COLUMN_OFFSET: u8 = 0;
+    const COM_PINS: (bool, bool) = (false, false);
+}
+
+impl DisplaySize for DisplaySize72x40 {
+    const WIDTH: u8 = 72;
+    const HEIGHT: u8 = 40;
+    const COLUMN_OFFSET: u8 = 28;
+    const COM_PINS: (bool, bool) = (true, false);
+}
+
+impl DisplaySize for DisplaySize64x48 {
+    const WIDTH: u8 = 64;
+    const HEIGHT: u8 = 48;
+    const COLUMN_OFFSET: u8 = 32;
+    const COM_PINS: (bool, bool) = (true, false);
+}
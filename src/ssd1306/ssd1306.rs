@@ -1,128 +1,461 @@
 #![allow(dead_code)]
 
-use embedded_hal::blocking::i2c::Write;
+use core::marker::PhantomData;
 
-pub const SSD_1306_WIDTH: u8 = 128;
-pub const SSD_1306_HEIGHT: u8 = 64;
+use crate::progmem::PmBytes;
 
-use super::command::{Command, AddressMode};
+use super::command::{self, Command, AddressMode, EncodedCommand, Page, PageCommand, VcomhLevel, WindowCommand};
+use super::display::Display;
+use super::error::{InvalidParameter, Ssd1306Error};
+use super::framebuffer::FrameBuffer;
+use super::interface::{Interface, MAX_COMMAND_BYTES};
+use super::orientation::{Orientation, Rotation};
+use super::mode::{AddressingMode, HorizontalAddressing, ModeCommand, PageAddressing, WindowMode};
+use super::size::{DisplaySize, DisplaySize128x64};
+use super::strip::MAX_WIDTH;
 
-pub struct SSD1306<I> where I: Write {
-    i2c: I,
-    address: u8,
+/// Maximum number of command bytes sent in one transaction by `send_commands`.
+pub const BATCH_SIZE: usize = MAX_COMMAND_BYTES;
+
+/// The init sequence for a panel of size `S` mounted in `orientation`, one
+/// command per entry.
+const fn init_commands<S: DisplaySize>(orientation: Orientation) -> [EncodedCommand; 17] {
+    [
+        Command::DisplayEnable(false).encode(),
+        Command::DisplayClockDiv{oscillator_freq: 0x8, divide_ratio: 0x0}.encode(),
+        Command::SetMultiplexRatio{ratio: S::MUX_RATIO}.encode(),
+        Command::DisplayOffset(0).encode(),
+        Command::SetStartLine(0).encode(),
+        Command::ChargePump(true).encode(),
+        AddressMode::Page.encode(),
+        
+        Command::ComPinConfig{alternative: S::COM_PINS.0, remap: S::COM_PINS.1}.encode(),
+        
+        Command::SegmentRemap(orientation.segment_remap()).encode(),
+        Command::SetComScanDir(orientation.com_scan_reversed()).encode(),
+        
+        // set default brightness
+        Command::PreChargePeriod{phase1: 1, phase2: 2}.encode(),
+        Command::SetContrast{contrast: 0x5F}.encode(),
+        
+        Command::VcomhDeselect(VcomhLevel::Auto).encode(),
+        Command::AllPixelsOn(false).encode(),
+        Command::InvertDisplay(false).encode(),
+        Command::EnableScrolling(false).encode(),
+        
+        Command::DisplayEnable(true).encode(),
+    ]
 }
 
-impl<I> SSD1306<I> where I: Write {
-    const DATA_BYTE: u8 = 0x40;
+/// Length of the encoded init sequence. Only the parameters change between
+/// panels and orientations, so this is the same for all of them.
+const INIT_LEN: usize = command::stream_len(&init_commands::<DisplaySize128x64>(Orientation::new(Rotation::Deg0)));
+const _: () = assert!(INIT_LEN <= MAX_COMMAND_BYTES, "init sequence doesn't fit in one transaction");
+
+/// SSD1306 driver for a panel of size `S`, currently in addressing mode `M`.
+/// 
+/// A freshly created driver is in `PageAddressing` mode, which is what the
+/// controller resets into. Use [`SSD1306::set_address_mode`] to switch.
+/// 
+/// Columns and pages taken by the driver are relative to the visible area of
+/// the panel, the offset into the controller's RAM is added on the way out.
+pub struct SSD1306<DI, S = DisplaySize128x64, M = PageAddressing> where DI: Interface {
+    interface: DI,
     
-    pub fn new(address: u8, i2c: I) -> Self {
+    /// Whether scrolling has been activated (and not deactivated since).
+    pub(super) scrolling: bool,
+    
+    /// Set when scrolling is deactivated, since the RAM has to be rewritten.
+    pub(super) ram_stale: bool,
+    
+    /// The display start line last set. A vertical scroll area has to
+    /// include it.
+    pub(super) start_line: u8,
+    
+    orientation: Orientation,
+    
+    size: PhantomData<S>,
+    mode: PhantomData<M>,
+}
+
+impl<DI, S: DisplaySize> SSD1306<DI, S, PageAddressing> where DI: Interface {
+    pub fn new(interface: DI, _size: S) -> Self {
         Self {
-            i2c,
-            address,
+            interface,
+            scrolling: false,
+            ram_stale: false,
+            start_line: 0,
+            orientation: Orientation::new(Rotation::Deg0),
+            size: PhantomData,
+            mode: PhantomData,
         }
     }
     
-    pub fn initialize(&mut self) -> Result<(), I::Error> {
-        self.send_command(Command::DisplayEnable(false))?;
-        self.send_command(Command::DisplayClockDiv{oscillator_freq: 0x8, divide_ratio: 0x0})?;
-        self.send_command(Command::SetMultiplexRatio{ratio: 63})?;
-        self.i2c.write(self.address, &[0x00, 0xD3, 0x00])?; // Set Display Offset 0
-        self.send_command(Command::SetStartLine(0))?; // Set Display Start Line 0
-        self.send_command(Command::ChargePump(true))?;
-        self.send_command(Command::SetAddressMode(AddressMode::Page))?;
+    /// Set the orientation `initialize` sets up, without touching the bus.
+    pub fn with_orientation(self, orientation: Orientation) -> Self {
+        Self { orientation, ..self }
+    }
+    
+    /// Bring the display out of reset and turn it on.
+    /// 
+    /// The whole init sequence goes out as a single I2C transaction. This
+    /// leaves the controller in page addressing mode.
+    pub fn initialize(&mut self) -> Result<(), Ssd1306Error<DI::Error>> {
+        // this folds down to a constant when the orientation is known at compile time
+        let sequence: [u8; INIT_LEN] = command::command_stream(&init_commands::<S>(self.orientation));
+        self.interface.send_commands(&sequence).map_err(Ssd1306Error::Bus)?;
+        self.scrolling = false;
+        self.start_line = 0;
+        Ok(())
+    }
+    
+    /// Move the RAM pointer to column `col` of `page`.
+    pub fn set_position(&mut self, page: Page, col: u8) -> Result<(), Ssd1306Error<DI::Error>> {
+        if col >= S::WIDTH {
+            return Err(InvalidParameter::Column.into());
+        }
+        if page as u8 >= S::PAGES {
+            return Err(InvalidParameter::Page.into());
+        }
         
-        self.i2c.write(self.address, &[0x00, 0xDA, 0x12])?; // SetComPinConfig(true, false) (wut?)
+        self.send_commands([
+            PageCommand::PageStart(page),
+            PageCommand::ColumnStart(col + S::COLUMN_OFFSET),
+        ])
+    }
+}
+
+impl<DI, S: DisplaySize, M: WindowMode> SSD1306<DI, S, M> where DI: Interface {
+    /// Restrict writes to columns `start_col..=end_col` of pages
+    /// `start_page..=end_page`, and move the RAM pointer to its top left.
+    pub fn set_window(&mut self, start_col: u8, end_col: u8, start_page: Page, end_page: Page) -> Result<(), Ssd1306Error<DI::Error>> {
+        let commands = window_commands::<S>(start_col, end_col, start_page, end_page)?;
+        self.send_commands(commands)
+    }
+}
+
+/// The commands selecting a window of a panel of size `S`, with the panel's
+/// column offset already applied.
+pub(super) fn window_commands<S: DisplaySize>(start_col: u8, end_col: u8, start_page: Page, end_page: Page) -> Result<[WindowCommand; 2], InvalidParameter> {
+    if end_col >= S::WIDTH {
+        return Err(InvalidParameter::Column);
+    }
+    if start_col > end_col {
+        return Err(InvalidParameter::ColumnRange);
+    }
+    if end_page as u8 >= S::PAGES {
+        return Err(InvalidParameter::Page);
+    }
+    if start_page as u8 > end_page as u8 {
+        return Err(InvalidParameter::PageRange);
+    }
+    
+    Ok([
+        WindowCommand::SetColumnAddress {
+            start_col: start_col + S::COLUMN_OFFSET,
+            end_col: end_col + S::COLUMN_OFFSET,
+        },
+        WindowCommand::SetPageAddress { start_page, end_page },
+    ])
+}
+
+impl<DI, S: DisplaySize> SSD1306<DI, S, HorizontalAddressing> where DI: Interface {
+    /// Write a rectangle `width` columns wide and `page_count` pages tall,
+    /// with its top left corner at column `x` of `page_start`.
+    /// 
+    /// `data` is laid out like a [`Bitmap`](super::bitmap::Bitmap): `width`
+    /// bytes for the top page, then the next page down and so on. The window
+    /// is set to just the rectangle, so it all goes out as one data stream
+    /// and nothing around it is touched.
+    pub fn blit(&mut self, x: u8, page_start: Page, width: u8, page_count: u8, data: &[u8]) -> Result<(), Ssd1306Error<DI::Error>> {
+        let (end_col, end_page) = blit_area::<S>(x, page_start as u8, width, page_count)?;
+        if data.len() != width as usize * page_count as usize {
+            return Err(InvalidParameter::BufferLength.into());
+        }
         
-        // Set the rotation to zero
-        self.i2c.write(self.address, &[0x00, 0xA1])?; // SetSegmentRemap(true)
-        self.i2c.write(self.address, &[0x00, 0xC8])?; // ReverseComDirection(true)
+        self.set_window(x, end_col, page_start, end_page)?;
+        self.send_data(data)
+    }
+    
+    /// Like [`blit`](SSD1306::blit), but with the top of the rectangle at any
+    /// pixel row `y`, not just a page boundary.
+    /// 
+    /// Unless `y` is a multiple of 8 the image straddles `page_count + 1`
+    /// pages, and the pages at the top and bottom are shared with whatever is
+    /// above and below it. The controller can't be read back, so those rows
+    /// are cleared, unless a `shadow` copy of the display is given: then
+    /// they're read from it, and the shadow is updated to match what was
+    /// sent. The shadow isn't marked dirty, since the display already shows
+    /// the result.
+    pub fn blit_at(&mut self, x: u8, y: u8, width: u8, page_count: u8, data: &[u8], mut shadow: Option<&mut FrameBuffer<S>>) -> Result<(), Ssd1306Error<DI::Error>> {
+        let shift = y % 8;
+        let pages = page_count.checked_add((shift != 0) as u8).ok_or(InvalidParameter::PageRange)?;
+        let (end_col, end_page) = blit_area::<S>(x, y / 8, width, pages)?;
+        if data.len() != width as usize * page_count as usize {
+            return Err(InvalidParameter::BufferLength.into());
+        }
         
-        // set default brightness
-        self.i2c.write(self.address, &[0x00, 0xD9, 0x21])?; // SetPreChargePeriod(1, 2)
-        self.send_command(Command::SetContrast{contrast: 0x5F})?;
+        let first = Page::try_from(y / 8)?;
+        self.set_window(x, end_col, first, end_page)?;
         
-        self.i2c.write(self.address, &[0x00, 0xDB, 0x40])?; // SetVcomhDeselect(VcomhLevel::Auto) (what???)
-        self.send_command(Command::AllPixelsOn(false))?; // (should be false)
-        self.i2c.write(self.address, &[0x00, 0xA6])?; // InvertDisplay(false)
-        self.send_command(Command::EnableScroll(false))?; // EnableScroll(false)
+        let image_page = |index: u8| {
+            let start = index as usize * width as usize;
+            data.get(start..start + width as usize)
+        };
         
-        self.send_command(Command::DisplayEnable(true))?;
+        let mut row = [0; MAX_WIDTH];
+        let row = &mut row[..width as usize];
+        for offset in 0..pages {
+            // the image page starting on this page, and the one spilling
+            // over from the page above
+            let upper = image_page(offset);
+            let lower = if shift > 0 && offset > 0 { image_page(offset - 1) } else { None };
+            
+            let mut mask = 0;
+            if upper.is_some() { mask |= 0xFF << shift }
+            if lower.is_some() { mask |= 0xFF >> (8 - shift) }
+            
+            for (col, byte) in row.iter_mut().enumerate() {
+                *byte = upper.map_or(0, |upper| upper[col] << shift)
+                    | lower.map_or(0, |lower| lower[col] >> (8 - shift));
+            }
+            
+            if let Some(shadow) = shadow.as_deref_mut() {
+                let page = Page::try_from(first as u8 + offset)?;
+                let kept = &mut shadow.page_mut_untracked(page)[x as usize..=end_col as usize];
+                for (byte, old) in row.iter_mut().zip(kept.iter_mut()) {
+                    *byte |= *old & !mask;
+                    *old = *byte;
+                }
+            }
+            
+            self.send_data(row)?;
+        }
         
         Ok(())
     }
+}
+
+/// Check a blit of `page_count` pages of `width` columns at column `x` of
+/// page `page_start` on a panel of size `S`, and return its last column and
+/// page.
+fn blit_area<S: DisplaySize>(x: u8, page_start: u8, width: u8, page_count: u8) -> Result<(u8, Page), InvalidParameter> {
+    if width == 0 {
+        return Err(InvalidParameter::ColumnRange);
+    }
+    if page_count == 0 {
+        return Err(InvalidParameter::PageRange);
+    }
     
-    #[inline(always)]
-    pub fn send_data(&mut self, data: &[u8]) -> Result<(), I::Error> {
-        // only write to the display in small chunks of bytes
-        const CHUNK_SIZE: usize = 16;
-        
-        let mut buffer: [u8; CHUNK_SIZE + 1] = [Self::DATA_BYTE; CHUNK_SIZE + 1];
+    let end_col = x.checked_add(width - 1).ok_or(InvalidParameter::Column)?;
+    match page_start.checked_add(page_count) {
+        Some(end) if end <= S::PAGES => Ok((end_col, Page::try_from(end - 1)?)),
+        _ => Err(InvalidParameter::PageRange),
+    }
+}
+
+impl<DI, S: DisplaySize, M: AddressingMode> SSD1306<DI, S, M> where DI: Interface {
+    /// Consume the driver and hand back the underlying interface.
+    pub fn release(self) -> DI {
+        self.interface
+    }
+    
+    /// Switch the controller to addressing mode `N`.
+    /// 
+    /// ```ignore
+    /// let mut display = display.set_address_mode::<HorizontalAddressing>()?;
+    /// ```
+    pub fn set_address_mode<N: AddressingMode>(mut self) -> Result<SSD1306<DI, S, N>, Ssd1306Error<DI::Error>> {
+        self.interface.send_batched([N::MODE.encode()]).map_err(Ssd1306Error::Bus)?;
         
-        for chunk in data.chunks(CHUNK_SIZE) {
-            buffer[1..=chunk.len()].copy_from_slice(chunk);
-            self.i2c.write(self.address, &buffer[..=chunk.len()])?
-        }
+        Ok(SSD1306 {
+            interface: self.interface,
+            scrolling: self.scrolling,
+            ram_stale: self.ram_stale,
+            start_line: self.start_line,
+            orientation: self.orientation,
+            size: PhantomData,
+            mode: PhantomData,
+        })
+    }
+    
+    /// Send raw GDDRAM data at the current position.
+    #[inline(always)]
+    pub fn send_data(&mut self, data: &[u8]) -> Result<(), Ssd1306Error<DI::Error>> {
+        self.interface.send_data(data).map_err(Ssd1306Error::Bus)
+    }
+    
+    /// Send GDDRAM data at the current position, straight from flash.
+    pub fn send_flash_data(&mut self, data: PmBytes) -> Result<(), Ssd1306Error<DI::Error>> {
+        self.interface.send_flash_data(data).map_err(Ssd1306Error::Bus)
+    }
+    
+    /// Send a single command.
+    /// 
+    /// Addressing commands only compile if they apply to the current mode:
+    /// `PageCommand`s need `PageAddressing`, `WindowCommand`s need
+    /// `HorizontalAddressing` or `VerticalAddressing`.
+    #[inline(always)]
+    pub fn send_command(&mut self, command: impl ModeCommand<M>) -> Result<(), Ssd1306Error<DI::Error>> {
+        self.send_commands(core::iter::once(command))
+    }
+    
+    /// Send a batch of commands as one continuous command stream.
+    /// 
+    /// Commands are packed into transactions of up to `BATCH_SIZE` bytes,
+    /// so long batches still only cost a handful of bus start/stop cycles.
+    /// A single command is never split across transactions.
+    pub fn send_commands<C>(&mut self, commands: impl IntoIterator<Item = C>) -> Result<(), Ssd1306Error<DI::Error>>
+    where C: ModeCommand<M> {
+        let (mut scrolling, mut ram_stale, mut start_line) = (self.scrolling, self.ram_stale, self.start_line);
+        let commands = commands.into_iter().map(|command| {
+            match command.general() {
+                // stopping a scroll leaves GDDRAM the way it scrolled to
+                Some(Command::EnableScrolling(false)) => {
+                    ram_stale |= scrolling;
+                    scrolling = false;
+                },
+                Some(Command::EnableScrolling(true)) => scrolling = true,
+                Some(Command::SetStartLine(line)) => start_line = line & 0x3F,
+                _ => {},
+            }
+            command.encode()
+        });
+        self.interface.send_batched(commands).map_err(Ssd1306Error::Bus)?;
         
+        // only once the controller has actually got the commands
+        self.scrolling = scrolling;
+        self.ram_stale = ram_stale;
+        self.start_line = start_line;
         Ok(())
     }
+}
+
+impl<DI, S: DisplaySize, M: AddressingMode> SSD1306<DI, S, M> where DI: Interface {
+    pub fn set_display_on(&mut self, on: bool) -> Result<(), Ssd1306Error<DI::Error>> {
+        self.send_command(Command::DisplayEnable(on))
+    }
     
-    #[inline(always)]
-    pub fn send_command(&mut self, command: Command) -> Result<(), I::Error> {
-        macro_rules! cmd {
-            [$($x:expr),+ $(,)?] => { self.i2c.write(self.address, &[0x00, $($x),+]) }
-        }
+    pub fn set_contrast(&mut self, contrast: u8) -> Result<(), Ssd1306Error<DI::Error>> {
+        self.send_command(Command::SetContrast { contrast })
+    }
+    
+    pub fn set_inverted(&mut self, inverted: bool) -> Result<(), Ssd1306Error<DI::Error>> {
+        self.send_command(Command::InvertDisplay(inverted))
+    }
+    
+    pub fn orientation(&self) -> Orientation {
+        self.orientation
+    }
+    
+    /// Change which way up the picture is shown.
+    /// 
+    /// The segment remap only applies to data written after it, so flipping
+    /// the horizontal direction marks the RAM as needing a rewrite, see
+    /// [`SSD1306::ram_needs_rewrite`]. The vertical direction applies
+    /// straight away.
+    pub fn set_orientation(&mut self, orientation: Orientation) -> Result<(), Ssd1306Error<DI::Error>> {
+        self.send_commands([
+            Command::SegmentRemap(orientation.segment_remap()),
+            Command::SetComScanDir(orientation.com_scan_reversed()),
+        ])?;
         
-        match command {
-            // Fundamental commands
-            Command::SetContrast{contrast} => cmd![0x81, contrast],
-            Command::AllPixelsOn(enable) => cmd![0xA4 | (enable as u8)],
-            Command::InvertDisplay(enable) => cmd![0xA6 | (enable as u8)],
-            Command::DisplayEnable(enable) => cmd![0xAE | (enable as u8)],
-            
-            // Scrolling commands
-            Command::SetupHorizontalScroll {
-                direction: dir,
-                start_page: start,
-                interval,
-                end_page: end,
-            } => [0x26 | dir as u8, 0x00, start as u8, interval as u8, end as u8, 0x00, 0xFF],
-            Command::SetupVerticalAndHorizontalScroll {
-                direction: vdir,
-                start_page: start,
-                interval,
-                end_page: end,
-                offset,
-            } => [0x28 | vdir as u8, 0x00, start as u8, interval as u8, end as u8, offset as u8],
-            Command::EnableScroll(enable) => cmd![0x2E | (enable as u8)],
-            Command::SetupVerticalScrollArea { top, bottom } => cmd![0xA3, top, bottom],
-            
-            // Addressing Setting Commands
-            Command::LowerColumnStart(addr) => cmd![0x00 | (addr & 0x0F)],
-            Command::UpperColumnStart(addr) => cmd![0x10 | (addr & 0x0F)],
-            Command::ColumnStart(addr) => cmd![0x0F & addr, 0x10 | ((addr >> 4) & 0x0F)],
-            Command::SetAddressMode(mode) => cmd![0x20, mode as u8],
-            Command::SetColumnAddress{start_col: u8, end_col: u8} => cmd![0x21, start_col, end_col],
-            Command::SetPageAddress{start_page, end_page} => [0x22, start_page as u8, end_page as u8],
-            Command::PageStart(page) => cmd![0xB0 | (page as u8)],
-            
-            // Hardware Configuration Commands
-            Command::SetStartLine(line) => cmd![0x40 | (line as u8)],
-            
-            // Brightness-related commands
-            Command::ChargePump(enable) => cmd![0x8D, 0x10 | ((enable as u8) << 2)],
-            
-            // Display-related commands
-            Command::SetMultiplexRatio { ratio } => cmd![0xA8, ratio],
-            Command::DisplayClockDiv {
-                oscillator_freq: osc,
-                divide_ratio: div
-            } => cmd![0xD5, ((osc & 0x0F) << 4) | (div & 0x0F)],
-            
-            // No operation
-            Command::NoOp => cmd![0xE3],
-            
-            _ => todo!()
+        if orientation.segment_remap() != self.orientation.segment_remap() {
+            self.ram_stale = true;
         }
+        self.orientation = orientation;
+        Ok(())
+    }
+    
+    /// Change the rotation, keeping the mirror flags.
+    pub fn set_rotation(&mut self, rotation: Rotation) -> Result<(), Ssd1306Error<DI::Error>> {
+        self.set_orientation(Orientation { rotation, ..self.orientation })
+    }
+    
+    /// Change the mirror flags, keeping the rotation.
+    pub fn set_mirror(&mut self, horizontal: bool, vertical: bool) -> Result<(), Ssd1306Error<DI::Error>> {
+        self.set_orientation(self.orientation.mirrored(horizontal, vertical))
+    }
+}
+
+/// Check that `len` bytes starting at `col` fit on a page.
+pub(super) fn check_page_run<S: DisplaySize>(col: u8, len: usize) -> Result<(), InvalidParameter> {
+    if len == 0 || col as usize + len > S::WIDTH as usize {
+        return Err(InvalidParameter::Column);
+    }
+    Ok(())
+}
+
+impl<DI, S: DisplaySize> Display for SSD1306<DI, S, PageAddressing> where DI: Interface {
+    type Error = Ssd1306Error<DI::Error>;
+    type Size = S;
+    
+    fn write_page(&mut self, page: Page, col: u8, data: &[u8]) -> Result<(), Self::Error> {
+        check_page_run::<S>(col, data.len())?;
+        self.set_position(page, col)?;
+        self.send_data(data)
+    }
+    
+    fn write_page_flash(&mut self, page: Page, col: u8, data: PmBytes) -> Result<(), Self::Error> {
+        check_page_run::<S>(col, data.len())?;
+        self.set_position(page, col)?;
+        self.send_flash_data(data)
+    }
+    
+    fn set_display_on(&mut self, on: bool) -> Result<(), Self::Error> {
+        SSD1306::set_display_on(self, on)
+    }
+    
+    fn set_contrast(&mut self, contrast: u8) -> Result<(), Self::Error> {
+        SSD1306::set_contrast(self, contrast)
+    }
+    
+    fn set_inverted(&mut self, inverted: bool) -> Result<(), Self::Error> {
+        SSD1306::set_inverted(self, inverted)
+    }
+    
+    fn set_orientation(&mut self, orientation: Orientation) -> Result<(), Self::Error> {
+        SSD1306::set_orientation(self, orientation)
+    }
+}
+
+impl<DI, S: DisplaySize> Display for SSD1306<DI, S, HorizontalAddressing> where DI: Interface {
+    type Error = Ssd1306Error<DI::Error>;
+    type Size = S;
+    
+    fn write_page(&mut self, page: Page, col: u8, data: &[u8]) -> Result<(), Self::Error> {
+        check_page_run::<S>(col, data.len())?;
+        self.set_window(col, col + (data.len() - 1) as u8, page, page)?;
+        self.send_data(data)
+    }
+    
+    fn write_page_flash(&mut self, page: Page, col: u8, data: PmBytes) -> Result<(), Self::Error> {
+        check_page_run::<S>(col, data.len())?;
+        self.set_window(col, col + (data.len() - 1) as u8, page, page)?;
+        self.send_flash_data(data)
+    }
+    
+    /// Horizontal mode wraps from one page to the next by itself, so the
+    /// whole frame goes out as one data stream.
+    fn write_frame(&mut self, frame: &[u8]) -> Result<(), Self::Error> {
+        let last_page = Page::try_from(S::PAGES - 1)?;
+        self.set_window(0, S::WIDTH - 1, Page::Page0, last_page)?;
+        self.send_data(&frame[..frame.len().min(S::BUFFER_SIZE)])
     }
     
+    fn set_display_on(&mut self, on: bool) -> Result<(), Self::Error> {
+        SSD1306::set_display_on(self, on)
+    }
+    
+    fn set_contrast(&mut self, contrast: u8) -> Result<(), Self::Error> {
+        SSD1306::set_contrast(self, contrast)
+    }
+    
+    fn set_inverted(&mut self, inverted: bool) -> Result<(), Self::Error> {
+        SSD1306::set_inverted(self, inverted)
+    }
+    
+    fn set_orientation(&mut self, orientation: Orientation) -> Result<(), Self::Error> {
+        SSD1306::set_orientation(self, orientation)
+    }
 }
@@ -0,0 +1,89 @@
+//! Page-at-a-time rendering for builds without room for a framebuffer.
+//! 
+//! Instead of keeping the whole frame in RAM, [`render`] draws every
+//! drawable once per page into a single page sized [`PageStrip`] on the
+//! stack, and sends each page as soon as it's done. That trades CPU time
+//! (everything is drawn `PAGES` times) for 128 bytes of RAM instead of 1 KiB.
+
+use core::marker::PhantomData;
+
+use super::canvas::{Canvas, Drawable};
+use super::command::Page;
+use super::display::Display;
+use super::size::{DisplaySize, DisplaySize128x64};
+
+/// Widest panel any supported controller drives.
+pub const MAX_WIDTH: usize = 128;
+
+/// One page of a panel of size `S`, standing in for the whole canvas.
+/// 
+/// Pixels outside the current page are dropped.
+pub struct PageStrip<S = DisplaySize128x64> {
+    page: Page,
+    buffer: [u8; MAX_WIDTH],
+    size: PhantomData<S>,
+}
+
+impl<S: DisplaySize> PageStrip<S> {
+    pub const fn new(page: Page) -> Self {
+        Self { page, buffer: [0; MAX_WIDTH], size: PhantomData }
+    }
+    
+    pub fn page(&self) -> Page {
+        self.page
+    }
+    
+    /// Move on to `page` and clear the strip.
+    pub fn set_page(&mut self, page: Page) {
+        self.page = page;
+        self.buffer = [0; MAX_WIDTH];
+    }
+    
+    /// The bytes of the current page, one per column.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.buffer[..S::WIDTH as usize]
+    }
+}
+
+impl<S: DisplaySize> Canvas for PageStrip<S> {
+    fn size(&self) -> (u8, u8) {
+        (S::WIDTH, S::HEIGHT)
+    }
+    
+    fn set_pixel(&mut self, x: u8, y: u8, on: bool) {
+        if x >= S::WIDTH || y / 8 != self.page as u8 {
+            return;
+        }
+        
+        let mask = 1 << (y % 8);
+        if on {
+            self.buffer[x as usize] |= mask;
+        } else {
+            self.buffer[x as usize] &= !mask;
+        }
+    }
+    
+    fn fill(&mut self, on: bool) {
+        self.buffer = [if on { 0xFF } else { 0x00 }; MAX_WIDTH];
+    }
+}
+
+/// Draw `drawables` (in order, later ones on top) and send the result to
+/// `display`, one page at a time.
+pub fn render<D>(display: &mut D, drawables: &[&dyn Drawable]) -> Result<(), D::Error>
+where D: Display {
+    let mut strip = PageStrip::<D::Size>::new(Page::Page0);
+    
+    for index in 0..D::Size::PAGES {
+        let Ok(page) = Page::try_from(index) else { break };
+        strip.set_page(page);
+        
+        for drawable in drawables {
+            drawable.draw(&mut strip);
+        }
+        
+        display.write_page(page, 0, strip.as_bytes())?;
+    }
+    
+    Ok(())
+}
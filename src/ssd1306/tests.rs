@@ -0,0 +1,2265 @@
+//! Host-side tests for the display driver.
+//! 
+//! These run against a mock bus that records every transaction, so they
+//! don't need a panel (or an AVR) attached.
+
+extern crate std;
+
+use std::string::String;
+use std::vec;
+use std::vec::Vec;
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use embedded_hal::blocking::delay::DelayUs;
+use embedded_hal::blocking::{i2c, spi};
+use embedded_hal::digital::v2::OutputPin;
+
+use crate::progmem::{PmBytes, PmStr};
+
+use super::command::*;
+use super::mode::*;
+use super::size::*;
+use super::decode;
+use super::error::{DecodeError, InvalidParameter, Ssd1306Error};
+use super::bitmap::Bitmap;
+use super::canvas::{Canvas, Drawable};
+use super::emulator::{Addressing, Emulator};
+use super::framebuffer::DirtyColumns;
+use super::strip::{self, PageStrip};
+use super::interface::SpiError;
+use super::orientation::{Orientation, Rotation};
+use super::transfer::{Action, BackgroundFrame, Status, Transfer, TransferError};
+use super::{sh1106, Display, FrameBuffer, I2cInterface, SpiInterface, TextDisplay, SH1106, SSD1306};
+
+/// An I2C bus that records every write instead of sending it anywhere.
+#[derive(Default)]
+pub struct RecordingI2c {
+    pub writes: Vec<(u8, Vec<u8>)>,
+    
+    /// Addresses that NACK instead of recording the write.
+    pub absent: Vec<u8>,
+}
+
+impl i2c::Write for RecordingI2c {
+    type Error = ();
+    
+    fn write(&mut self, address: u8, bytes: &[u8]) -> Result<(), ()> {
+        if self.absent.contains(&address) {
+            return Err(());
+        }
+        
+        self.writes.push((address, bytes.to_vec()));
+        Ok(())
+    }
+}
+
+/// Send a single command and return the bytes that hit the bus.
+fn encode(command: Command) -> Vec<u8> {
+    encode_in::<PageAddressing>(command)
+}
+
+/// Send a single command from a driver in addressing mode `M`, and return
+/// the bytes that hit the bus.
+fn encode_in<M: AddressingMode>(command: impl ModeCommand<M> + core::fmt::Debug) -> Vec<u8> {
+    let mut display = SSD1306::new(I2cInterface::new(RecordingI2c::default(), 0x3C), DisplaySize128x64)
+        .set_address_mode::<M>()
+        .unwrap();
+    display.send_command(&command).unwrap();
+    
+    // skip the addressing mode change
+    let writes = display.release().release().writes;
+    assert_eq!(writes.len(), 2, "{:?} should be a single transaction", command);
+    assert_eq!(writes[1].0, 0x3C);
+    writes[1].1.clone()
+}
+
+/// A flash string, read back into a normal one.
+fn text(string: PmStr) -> String {
+    let mut text = String::new();
+    string.try_for_each_chunk(|chunk| {
+        text.push_str(chunk);
+        Ok::<_, ()>(())
+    }).unwrap();
+    text
+}
+
+#[test]
+fn fundamental_commands() {
+    assert_eq!(encode(Command::SetContrast { contrast: 0x5F }), [0x00, 0x81, 0x5F]);
+    assert_eq!(encode(Command::AllPixelsOn(false)), [0x00, 0xA4]);
+    assert_eq!(encode(Command::AllPixelsOn(true)), [0x00, 0xA5]);
+    assert_eq!(encode(Command::InvertDisplay(false)), [0x00, 0xA6]);
+    assert_eq!(encode(Command::InvertDisplay(true)), [0x00, 0xA7]);
+    assert_eq!(encode(Command::DisplayEnable(false)), [0x00, 0xAE]);
+    assert_eq!(encode(Command::DisplayEnable(true)), [0x00, 0xAF]);
+}
+
+#[test]
+fn scrolling_commands() {
+    assert_eq!(
+        encode(Command::SetupHorizontalScroll {
+            direction: HorizontalScroll::Right,
+            interval: ScrollInterval::Frames2,
+            start_page: Page::Page1,
+            end_page: Page::Page6,
+        }),
+        [0x00, 0x26, 0x00, 0x01, 0x07, 0x06, 0x00, 0xFF],
+    );
+    assert_eq!(
+        encode(Command::SetupHorizontalScroll {
+            direction: HorizontalScroll::Left,
+            interval: ScrollInterval::Frames5,
+            start_page: Page::Page0,
+            end_page: Page::Page7,
+        }),
+        [0x00, 0x27, 0x00, 0x00, 0x00, 0x07, 0x00, 0xFF],
+    );
+    assert_eq!(
+        encode(Command::SetupVerticalAndHorizontalScroll {
+            direction: VerticalHorizontalScroll::VerticalRight,
+            interval: ScrollInterval::Frames64,
+            start_page: Page::Page2,
+            end_page: Page::Page3,
+            offset: 1,
+        }),
+        [0x00, 0x29, 0x00, 0x02, 0x01, 0x03, 0x01],
+    );
+    assert_eq!(
+        encode(Command::SetupVerticalAndHorizontalScroll {
+            direction: VerticalHorizontalScroll::VerticalLeft,
+            interval: ScrollInterval::Frames256,
+            start_page: Page::Page0,
+            end_page: Page::Page7,
+            offset: 63,
+        }),
+        [0x00, 0x2A, 0x00, 0x00, 0x03, 0x07, 0x3F],
+    );
+    assert_eq!(encode(Command::EnableScrolling(false)), [0x00, 0x2E]);
+    assert_eq!(encode(Command::EnableScrolling(true)), [0x00, 0x2F]);
+    assert_eq!(encode(Command::SetupVerticalScrollArea { top: 8, bottom: 56 }), [0x00, 0xA3, 0x08, 0x38]);
+}
+
+#[test]
+fn addressing_commands() {
+    assert_eq!(encode_in::<PageAddressing>(PageCommand::LowerColumnStart(0x0A)), [0x00, 0x0A]);
+    assert_eq!(encode_in::<PageAddressing>(PageCommand::UpperColumnStart(0x07)), [0x00, 0x17]);
+    assert_eq!(encode_in::<PageAddressing>(PageCommand::ColumnStart(0x5C)), [0x00, 0x0C, 0x15]);
+    assert_eq!(encode_in::<PageAddressing>(PageCommand::PageStart(Page::Page0)), [0x00, 0xB0]);
+    assert_eq!(encode_in::<PageAddressing>(PageCommand::PageStart(Page::Page7)), [0x00, 0xB7]);
+    assert_eq!(
+        encode_in::<HorizontalAddressing>(WindowCommand::SetColumnAddress { start_col: 0, end_col: 127 }),
+        [0x00, 0x21, 0x00, 0x7F],
+    );
+    assert_eq!(
+        encode_in::<VerticalAddressing>(WindowCommand::SetPageAddress { start_page: Page::Page2, end_page: Page::Page5 }),
+        [0x00, 0x22, 0x02, 0x05],
+    );
+}
+
+#[test]
+fn address_mode_changes() {
+    fn switch_to<M: AddressingMode>() -> Vec<u8> {
+        let display = SSD1306::new(I2cInterface::new(RecordingI2c::default(), 0x3C), DisplaySize128x64);
+        let writes = display.set_address_mode::<M>().unwrap().release().release().writes;
+        assert_eq!(writes.len(), 1);
+        writes[0].1.clone()
+    }
+    
+    assert_eq!(switch_to::<HorizontalAddressing>(), [0x00, 0x20, 0x00]);
+    assert_eq!(switch_to::<VerticalAddressing>(), [0x00, 0x20, 0x01]);
+    assert_eq!(switch_to::<PageAddressing>(), [0x00, 0x20, 0x02]);
+}
+
+#[test]
+fn typed_positioning() {
+    let mut display = SSD1306::new(I2cInterface::new(RecordingI2c::default(), 0x3C), DisplaySize128x64);
+    display.set_position(Page::Page3, 32).unwrap();
+    
+    let mut display = display.set_address_mode::<HorizontalAddressing>().unwrap();
+    display.set_window(32, 95, Page::Page1, Page::Page6).unwrap();
+    
+    let writes = display.release().release().writes;
+    assert_eq!(writes.len(), 3);
+    assert_eq!(writes[0].1, [0x00, 0xB3, 0x00, 0x12]);
+    assert_eq!(writes[2].1, [0x00, 0x21, 0x20, 0x5F, 0x22, 0x01, 0x06]);
+}
+
+#[test]
+fn hardware_configuration_commands() {
+    assert_eq!(encode(Command::SetStartLine(0)), [0x00, 0x40]);
+    assert_eq!(encode(Command::SetStartLine(32)), [0x00, 0x60]);
+    assert_eq!(encode(Command::SetStartLine(63)), [0x00, 0x7F]);
+    assert_eq!(encode(Command::SegmentRemap(false)), [0x00, 0xA0]);
+    assert_eq!(encode(Command::SegmentRemap(true)), [0x00, 0xA1]);
+    assert_eq!(encode(Command::SetMultiplexRatio { ratio: 63 }), [0x00, 0xA8, 0x3F]);
+    assert_eq!(encode(Command::SetComScanDir(false)), [0x00, 0xC0]);
+    assert_eq!(encode(Command::SetComScanDir(true)), [0x00, 0xC8]);
+    assert_eq!(encode(Command::DisplayOffset(0)), [0x00, 0xD3, 0x00]);
+    assert_eq!(encode(Command::DisplayOffset(17)), [0x00, 0xD3, 0x11]);
+    assert_eq!(encode(Command::ComPinConfig { alternative: false, remap: false }), [0x00, 0xDA, 0x02]);
+    assert_eq!(encode(Command::ComPinConfig { alternative: true, remap: false }), [0x00, 0xDA, 0x12]);
+    assert_eq!(encode(Command::ComPinConfig { alternative: true, remap: true }), [0x00, 0xDA, 0x32]);
+    assert_eq!(encode(Command::InternalIref(IrefSelection::External)), [0x00, 0xAD, 0x00]);
+    assert_eq!(encode(Command::InternalIref(IrefSelection::Internal19uA)), [0x00, 0xAD, 0x20]);
+    assert_eq!(encode(Command::InternalIref(IrefSelection::Internal30uA)), [0x00, 0xAD, 0x30]);
+}
+
+#[test]
+fn timing_and_charge_pump_commands() {
+    assert_eq!(encode(Command::DisplayClockDiv { oscillator_freq: 0x8, divide_ratio: 0x0 }), [0x00, 0xD5, 0x80]);
+    assert_eq!(encode(Command::PreChargePeriod { phase1: 1, phase2: 2 }), [0x00, 0xD9, 0x21]);
+    assert_eq!(encode(Command::PreChargePeriod { phase1: 0xF, phase2: 0x1 }), [0x00, 0xD9, 0x1F]);
+    assert_eq!(encode(Command::VcomhDeselect(VcomhLevel::V065)), [0x00, 0xDB, 0x00]);
+    assert_eq!(encode(Command::VcomhDeselect(VcomhLevel::V077)), [0x00, 0xDB, 0x20]);
+    assert_eq!(encode(Command::VcomhDeselect(VcomhLevel::V083)), [0x00, 0xDB, 0x30]);
+    assert_eq!(encode(Command::VcomhDeselect(VcomhLevel::Auto)), [0x00, 0xDB, 0x40]);
+    assert_eq!(encode(Command::NoOp), [0x00, 0xE3]);
+    assert_eq!(encode(Command::ChargePump(false)), [0x00, 0x8D, 0x10]);
+    assert_eq!(encode(Command::ChargePump(true)), [0x00, 0x8D, 0x14]);
+}
+
+#[test]
+fn initialize_is_a_single_transaction() {
+    let mut display = SSD1306::new(I2cInterface::new(RecordingI2c::default(), 0x3C), DisplaySize128x64);
+    display.initialize().unwrap();
+    
+    let writes = display.release().release().writes;
+    assert_eq!(writes.len(), 1);
+    assert_eq!(writes[0].0, 0x3C);
+    assert_eq!(writes[0].1, [
+        0x00,
+        0xAE,
+        0xD5, 0x80,
+        0xA8, 0x3F,
+        0xD3, 0x00,
+        0x40,
+        0x8D, 0x14,
+        0x20, 0x02,
+        0xDA, 0x12,
+        0xA1,
+        0xC8,
+        0xD9, 0x21,
+        0x81, 0x5F,
+        0xDB, 0x40,
+        0xA4,
+        0xA6,
+        0x2E,
+        0xAF,
+    ]);
+}
+
+#[test]
+fn batched_commands_share_a_transaction() {
+    let mut display = SSD1306::new(I2cInterface::new(RecordingI2c::default(), 0x3C), DisplaySize128x64);
+    let batch = [
+        Command::SetContrast { contrast: 0xFF },
+        Command::InvertDisplay(true),
+        Command::SetStartLine(8),
+    ];
+    display.send_commands(batch.iter()).unwrap();
+    
+    let writes = display.release().release().writes;
+    assert_eq!(writes.len(), 1);
+    assert_eq!(writes[0].1, [0x00, 0x81, 0xFF, 0xA7, 0x48]);
+}
+
+#[test]
+fn long_batches_never_split_a_command() {
+    let scroll = Command::SetupHorizontalScroll {
+        direction: HorizontalScroll::Left,
+        interval: ScrollInterval::Frames2,
+        start_page: Page::Page0,
+        end_page: Page::Page7,
+    };
+    
+    let mut display = SSD1306::new(I2cInterface::new(RecordingI2c::default(), 0x3C), DisplaySize128x64);
+    display.send_commands(core::iter::repeat_n(scroll, 10)).unwrap();
+    
+    let writes = display.release().release().writes;
+    assert!(writes.len() > 1);
+    
+    let mut bytes = Vec::new();
+    for (_, write) in writes {
+        assert_eq!(write[0], 0x00);
+        assert!(write.len() <= super::ssd1306::BATCH_SIZE + 1);
+        assert_eq!((write.len() - 1) % 7, 0, "a scroll setup was split");
+        bytes.extend_from_slice(&write[1..]);
+    }
+    assert_eq!(bytes.len(), 70);
+}
+
+#[test]
+fn empty_batch_sends_nothing() {
+    let mut display = SSD1306::new(I2cInterface::new(RecordingI2c::default(), 0x3C), DisplaySize128x64);
+    display.send_commands(core::iter::empty::<Command>()).unwrap();
+    assert!(display.release().release().writes.is_empty());
+}
+
+#[test]
+fn scroll_setup_and_activation_are_atomic() {
+    let mut display = SSD1306::new(I2cInterface::new(RecordingI2c::default(), 0x3C), DisplaySize128x64);
+    display.scroll()
+        .horizontal(HorizontalScroll::Left)
+        .pages(Page::Page2, Page::Page3)
+        .interval(ScrollInterval::Frames2)
+        .start()
+        .unwrap();
+    assert!(display.is_scrolling());
+    
+    let writes = display.release().release().writes;
+    assert_eq!(writes.len(), 1);
+    assert_eq!(writes[0].1, [0x00, 0x27, 0x00, 0x02, 0x07, 0x03, 0x00, 0xFF, 0x2F]);
+}
+
+#[test]
+fn diagonal_scroll_sets_up_the_scroll_area() {
+    let mut display = SSD1306::new(I2cInterface::new(RecordingI2c::default(), 0x3C), DisplaySize128x64);
+    display.scroll()
+        .diagonal(VerticalHorizontalScroll::VerticalRight, 1)
+        .area(16, 48)
+        .start()
+        .unwrap();
+    
+    let writes = display.release().release().writes;
+    assert_eq!(writes.len(), 1);
+    assert_eq!(writes[0].1, [
+        0x00,
+        0xA3, 0x10, 0x30,
+        0x29, 0x00, 0x00, 0x00, 0x07, 0x01,
+        0x2F,
+    ]);
+}
+
+#[test]
+fn invalid_scrolls_are_rejected_before_touching_the_bus() {
+    let mut display = SSD1306::new(I2cInterface::new(RecordingI2c::default(), 0x3C), DisplaySize128x64);
+    
+    assert_eq!(
+        display.scroll().pages(Page::Page5, Page::Page4).start(),
+        Err(Ssd1306Error::InvalidParameter(InvalidParameter::PageRange)),
+    );
+    assert_eq!(
+        display.scroll().diagonal(VerticalHorizontalScroll::VerticalLeft, 1).area(32, 40).start(),
+        Err(Ssd1306Error::InvalidParameter(InvalidParameter::ScrollArea)),
+    );
+    assert_eq!(
+        display.scroll().diagonal(VerticalHorizontalScroll::VerticalLeft, 8).area(0, 8).start(),
+        Err(Ssd1306Error::InvalidParameter(InvalidParameter::ScrollOffset)),
+    );
+    assert_eq!(
+        display.scroll().diagonal(VerticalHorizontalScroll::VerticalLeft, 64).start(),
+        Err(Ssd1306Error::InvalidParameter(InvalidParameter::ScrollOffset)),
+    );
+    assert_eq!(
+        display.scroll().horizontal(HorizontalScroll::Left).area(0, 32).start(),
+        Err(Ssd1306Error::InvalidParameter(InvalidParameter::ScrollAreaNotVertical)),
+    );
+    
+    assert!(!display.is_scrolling());
+    assert!(display.release().release().writes.is_empty());
+}
+
+#[test]
+fn scroll_areas_have_to_include_the_start_line() {
+    let mut display = SSD1306::new(I2cInterface::new(RecordingI2c::default(), 0x3C), DisplaySize128x64);
+    display.send_command(Command::SetStartLine(40)).unwrap();
+    
+    assert_eq!(
+        display.scroll().diagonal(VerticalHorizontalScroll::VerticalLeft, 1).area(0, 40).start(),
+        Err(Ssd1306Error::InvalidParameter(InvalidParameter::StartLine)),
+    );
+    display.scroll().diagonal(VerticalHorizontalScroll::VerticalLeft, 1).area(0, 41).start().unwrap();
+    
+    // initializing puts the start line back at 0
+    display.initialize().unwrap();
+    display.scroll().diagonal(VerticalHorizontalScroll::VerticalLeft, 1).area(0, 8).start().unwrap();
+}
+
+#[test]
+fn failed_commands_leave_the_tracked_state_alone() {
+    let bus = RecordingI2c { absent: vec![0x3C], ..Default::default() };
+    let mut display = SSD1306::new(I2cInterface::new(bus, 0x3C), DisplaySize128x64);
+    
+    assert_eq!(display.scroll().start(), Err(Ssd1306Error::Bus(())));
+    assert!(!display.is_scrolling());
+    
+    // the start line is still 0 as far as the driver knows, so it's inside
+    // the area and the scroll gets as far as the bus
+    assert_eq!(display.send_command(Command::SetStartLine(40)), Err(Ssd1306Error::Bus(())));
+    assert_eq!(
+        display.scroll().diagonal(VerticalHorizontalScroll::VerticalLeft, 1).area(0, 8).start(),
+        Err(Ssd1306Error::Bus(())),
+    );
+}
+
+#[test]
+fn restarting_a_scroll_deactivates_it_first() {
+    let mut display = SSD1306::new(I2cInterface::new(RecordingI2c::default(), 0x3C), DisplaySize128x64);
+    display.scroll().start().unwrap();
+    display.scroll().horizontal(HorizontalScroll::Left).start().unwrap();
+    
+    let writes = display.release().release().writes;
+    assert_eq!(writes.len(), 2);
+    assert_eq!(writes[1].1, [0x00, 0x2E, 0x27, 0x00, 0x00, 0x00, 0x07, 0x00, 0xFF, 0x2F]);
+}
+
+#[test]
+fn deactivating_a_scroll_invalidates_ram() {
+    let mut display = SSD1306::new(I2cInterface::new(RecordingI2c::default(), 0x3C), DisplaySize128x64);
+    display.stop_scrolling().unwrap();
+    assert!(!display.ram_needs_rewrite());
+    
+    display.scroll().start().unwrap();
+    assert!(!display.ram_needs_rewrite());
+    
+    display.stop_scrolling().unwrap();
+    assert!(!display.is_scrolling());
+    assert!(display.ram_needs_rewrite());
+    
+    display.mark_ram_rewritten();
+    assert!(!display.ram_needs_rewrite());
+}
+
+#[test]
+fn probe_finds_either_address() {
+    let mut i2c = RecordingI2c { absent: vec![0x3D], ..Default::default() };
+    assert_eq!(I2cInterface::probe(&mut i2c), Ok(0x3C));
+    assert_eq!(i2c.writes, [(0x3C, vec![0x00, 0xE3])]);
+    
+    let mut i2c = RecordingI2c { absent: vec![0x3C], ..Default::default() };
+    assert_eq!(I2cInterface::probe(&mut i2c), Ok(0x3D));
+    
+    let mut i2c = RecordingI2c { absent: vec![0x3C, 0x3D], ..Default::default() };
+    assert_eq!(I2cInterface::probe(&mut i2c), Err(Ssd1306Error::NotPresent));
+}
+
+#[test]
+fn bus_errors_are_reported() {
+    let i2c = RecordingI2c { absent: vec![0x3C], ..Default::default() };
+    let mut display = SSD1306::new(I2cInterface::new(i2c, 0x3C), DisplaySize128x64);
+    
+    assert_eq!(display.initialize(), Err(Ssd1306Error::Bus(())));
+    assert_eq!(display.send_data(&[0xFF]), Err(Ssd1306Error::Bus(())));
+}
+
+#[test]
+fn error_descriptions_come_from_flash() {
+    assert_eq!(text(Ssd1306Error::<()>::NotPresent.as_str()), "display not present");
+    assert_eq!(text(Ssd1306Error::<()>::from(InvalidParameter::PageRange).as_str()), "invalid page range");
+    assert_eq!(text(InvalidParameter::StartLine.as_str()), "start line outside scroll area");
+    assert_eq!(text(DecodeError::Truncated.as_str()), "command cut short");
+    assert_eq!(text(TransferError::Busy.as_str()), "bus taken by another transfer");
+}
+
+#[test]
+fn out_of_range_positions_are_rejected() {
+    let mut display = SSD1306::new(I2cInterface::new(RecordingI2c::default(), 0x3C), DisplaySize128x64);
+    assert_eq!(
+        display.set_position(Page::Page0, 128),
+        Err(Ssd1306Error::InvalidParameter(InvalidParameter::Column)),
+    );
+    
+    let mut display = display.set_address_mode::<HorizontalAddressing>().unwrap();
+    assert_eq!(
+        display.set_window(0, 128, Page::Page0, Page::Page7),
+        Err(Ssd1306Error::InvalidParameter(InvalidParameter::Column)),
+    );
+    assert_eq!(
+        display.set_window(64, 63, Page::Page0, Page::Page7),
+        Err(Ssd1306Error::InvalidParameter(InvalidParameter::ColumnRange)),
+    );
+    assert_eq!(
+        display.set_window(0, 127, Page::Page4, Page::Page3),
+        Err(Ssd1306Error::InvalidParameter(InvalidParameter::PageRange)),
+    );
+    
+    // only the addressing mode change made it to the bus
+    assert_eq!(display.release().release().writes.len(), 1);
+}
+
+#[test]
+fn page_from_u8() {
+    assert_eq!(Page::try_from(0), Ok(Page::Page0));
+    assert_eq!(Page::try_from(7), Ok(Page::Page7));
+    assert_eq!(Page::try_from(8), Err(InvalidParameter::Page));
+}
+
+#[test]
+fn commands_round_trip_through_decode() {
+    let commands = [
+        decode::Command::General(Command::SetContrast { contrast: 0xCF }),
+        decode::Command::General(Command::AllPixelsOn(true)),
+        decode::Command::General(Command::InvertDisplay(false)),
+        decode::Command::General(Command::DisplayEnable(true)),
+        decode::Command::General(Command::SetupHorizontalScroll {
+            direction: HorizontalScroll::Left,
+            interval: ScrollInterval::Frames25,
+            start_page: Page::Page2,
+            end_page: Page::Page6,
+        }),
+        decode::Command::General(Command::SetupVerticalAndHorizontalScroll {
+            direction: VerticalHorizontalScroll::VerticalRight,
+            interval: ScrollInterval::Frames2,
+            start_page: Page::Page0,
+            end_page: Page::Page7,
+            offset: 0x21,
+        }),
+        decode::Command::General(Command::EnableScrolling(false)),
+        decode::Command::General(Command::SetupVerticalScrollArea { top: 8, bottom: 48 }),
+        decode::Command::General(Command::SetStartLine(33)),
+        decode::Command::General(Command::SegmentRemap(true)),
+        decode::Command::General(Command::SetMultiplexRatio { ratio: 31 }),
+        decode::Command::General(Command::SetComScanDir(true)),
+        decode::Command::General(Command::DisplayOffset(5)),
+        decode::Command::General(Command::ComPinConfig { alternative: false, remap: true }),
+        decode::Command::General(Command::InternalIref(IrefSelection::Internal30uA)),
+        decode::Command::General(Command::DisplayClockDiv { oscillator_freq: 8, divide_ratio: 0 }),
+        decode::Command::General(Command::PreChargePeriod { phase1: 1, phase2: 15 }),
+        decode::Command::General(Command::VcomhDeselect(VcomhLevel::Auto)),
+        decode::Command::General(Command::NoOp),
+        decode::Command::General(Command::ChargePump(true)),
+        decode::Command::Page(PageCommand::LowerColumnStart(0x0A)),
+        decode::Command::Page(PageCommand::UpperColumnStart(0x05)),
+        decode::Command::Page(PageCommand::PageStart(Page::Page3)),
+        decode::Command::Window(WindowCommand::SetColumnAddress { start_col: 4, end_col: 123 }),
+        decode::Command::Window(WindowCommand::SetPageAddress { start_page: Page::Page1, end_page: Page::Page5 }),
+        decode::Command::AddressMode(AddressMode::Vertical),
+    ];
+    
+    for command in commands {
+        let encoded = command.encode();
+        let (decoded, len) = decode::Command::decode(encoded.as_bytes()).unwrap();
+        let name = text(command.name());
+        assert_eq!(len, encoded.len(), "{}", name);
+        assert_eq!(decoded.encode().as_bytes(), encoded.as_bytes(), "{}", name);
+        assert_eq!(text(decoded.name()), name);
+    }
+    
+    // ColumnStart is two commands on the wire
+    let encoded = PageCommand::ColumnStart(0x5A).encode();
+    let names: Vec<_> = decode::commands(encoded.as_bytes()).map(|command| text(command.unwrap().name())).collect();
+    assert_eq!(names, ["LowerColumnStart", "UpperColumnStart"]);
+}
+
+#[test]
+fn command_decode_reports_the_length_used() {
+    // addressing commands included
+    let stream = [0x81, 0x7F, 0xAF, 0x21, 0x00, 0x7F, 0xB3];
+    let mut offset = 0;
+    for expected in [&stream[..2], &stream[2..3], &stream[3..6], &stream[6..]] {
+        let (command, len) = decode::Command::decode(&stream[offset..]).unwrap();
+        assert_eq!(len, expected.len());
+        assert_eq!(command.encode().as_bytes(), expected);
+        offset += len;
+    }
+}
+
+#[test]
+fn malformed_commands_are_rejected() {
+    assert_eq!(decode::Command::decode(&[]).unwrap_err(), DecodeError::Empty);
+    assert_eq!(decode::Command::decode(&[0x81]).unwrap_err(), DecodeError::Truncated);
+    assert_eq!(decode::Command::decode(&[0x26, 0x00, 0x00, 0x07, 0x07, 0x00]).unwrap_err(), DecodeError::Truncated);
+    assert_eq!(decode::Command::decode(&[0xDB, 0x10]).unwrap_err(), DecodeError::InvalidParameter);
+    assert_eq!(decode::Command::decode(&[0xAD, 0x10]).unwrap_err(), DecodeError::InvalidParameter);
+    assert_eq!(decode::Command::decode(&[0x20, 0x03]).unwrap_err(), DecodeError::InvalidParameter);
+    assert_eq!(decode::Command::decode(&[0xFF]).unwrap_err(), DecodeError::UnknownOpcode(0xFF));
+    
+    assert_eq!(decode::Command::decode(&[0x21, 0]).unwrap_err(), DecodeError::Truncated);
+    
+    // decoding a stream stops at the first error
+    let results: Vec<_> = decode::commands(&[0xAF, 0xFF, 0xAE]).collect();
+    assert_eq!(results.len(), 2);
+    assert!(results[0].is_ok());
+    assert_eq!(results[1].unwrap_err(), DecodeError::UnknownOpcode(0xFF));
+}
+
+#[test]
+fn init_sequence_decodes() {
+    let mut display = SSD1306::new(I2cInterface::new(RecordingI2c::default(), 0x3C), DisplaySize128x64);
+    display.initialize().unwrap();
+    
+    let writes = display.release().release().writes;
+    let init = &writes[0].1[1..];
+    
+    let mut reencoded = Vec::new();
+    for command in decode::commands(init) {
+        reencoded.extend_from_slice(command.unwrap().encode().as_bytes());
+    }
+    assert_eq!(reencoded, init);
+}
+
+#[test]
+fn init_sequence_follows_the_panel() {
+    let mut display = SSD1306::new(I2cInterface::new(RecordingI2c::default(), 0x3C), DisplaySize128x32);
+    display.initialize().unwrap();
+    
+    let writes = display.release().release().writes;
+    assert_eq!(writes.len(), 1);
+    let init = &writes[0].1;
+    
+    // multiplex ratio and COM pins are the only differences from 128x64
+    assert_eq!(init[4..6], [0xA8, 0x1F]);
+    assert_eq!(init[13..15], [0xDA, 0x02]);
+}
+
+#[test]
+fn positions_are_offset_into_ram() {
+    let mut display = SSD1306::new(I2cInterface::new(RecordingI2c::default(), 0x3C), DisplaySize72x40);
+    display.set_position(Page::Page1, 0).unwrap();
+    assert_eq!(
+        display.set_position(Page::Page5, 0),
+        Err(Ssd1306Error::InvalidParameter(InvalidParameter::Page)),
+    );
+    assert_eq!(
+        display.set_position(Page::Page0, 72),
+        Err(Ssd1306Error::InvalidParameter(InvalidParameter::Column)),
+    );
+    
+    let mut display = display.set_address_mode::<HorizontalAddressing>().unwrap();
+    display.set_window(0, 71, Page::Page0, Page::Page4).unwrap();
+    
+    let writes = display.release().release().writes;
+    assert_eq!(writes.len(), 3);
+    assert_eq!(writes[0].1, [0x00, 0xB1, 0x0C, 0x11]);
+    assert_eq!(writes[2].1, [0x00, 0x21, 0x1C, 0x63, 0x22, 0x00, 0x04]);
+}
+
+#[test]
+fn scrolling_defaults_to_the_visible_pages() {
+    let mut display = SSD1306::new(I2cInterface::new(RecordingI2c::default(), 0x3C), DisplaySize128x32);
+    display.scroll().start().unwrap();
+    assert_eq!(
+        display.scroll().pages(Page::Page0, Page::Page4).start(),
+        Err(Ssd1306Error::InvalidParameter(InvalidParameter::Page)),
+    );
+    
+    let writes = display.release().release().writes;
+    assert_eq!(writes.len(), 1);
+    assert_eq!(writes[0].1, [0x00, 0x26, 0x00, 0x00, 0x00, 0x03, 0x00, 0xFF, 0x2F]);
+}
+
+#[test]
+fn ssd1306_page_writes() {
+    let mut display = SSD1306::new(I2cInterface::new(RecordingI2c::default(), 0x3C), DisplaySize128x64);
+    display.write_page(Page::Page2, 120, &[0xAA; 8]).unwrap();
+    assert_eq!(
+        display.write_page(Page::Page2, 121, &[0xAA; 8]),
+        Err(Ssd1306Error::InvalidParameter(InvalidParameter::Column)),
+    );
+    
+    let mut display = display.set_address_mode::<HorizontalAddressing>().unwrap();
+    display.write_page(Page::Page7, 0, &[0x55; 4]).unwrap();
+    display.write_frame(&[0xFF; 1024]).unwrap();
+    
+    let writes = display.release().release().writes;
+    assert_eq!(writes[0].1, [0x00, 0xB2, 0x08, 0x17]);
+    assert_eq!(writes[1].1, [0x40, 0xAA, 0xAA, 0xAA, 0xAA, 0xAA, 0xAA, 0xAA, 0xAA]);
+    assert_eq!(writes[3].1, [0x00, 0x21, 0x00, 0x03, 0x22, 0x07, 0x07]);
+    assert_eq!(writes[4].1, [0x40, 0x55, 0x55, 0x55, 0x55]);
+    assert_eq!(writes[5].1, [0x00, 0x21, 0x00, 0x7F, 0x22, 0x00, 0x07]);
+    
+    // the rest of the frame is one data stream
+    let data: usize = writes[6..].iter().map(|(_, bytes)| {
+        assert_eq!(bytes[0], 0x40);
+        bytes.len() - 1
+    }).sum();
+    assert_eq!(data, 1024);
+}
+
+#[test]
+fn sh1106_init_is_a_single_transaction() {
+    let mut display = SH1106::new(I2cInterface::new(RecordingI2c::default(), 0x3C), DisplaySize128x64);
+    display.initialize().unwrap();
+    
+    let writes = display.release().release().writes;
+    assert_eq!(writes.len(), 1);
+    assert_eq!(writes[0].1, [
+        0x00,
+        0xAE,
+        0xD5, 0x80,
+        0xA8, 0x3F,
+        0xD3, 0x00,
+        0x40,
+        0xAD, 0x8B,
+        0x32,
+        0xDA, 0x12,
+        0xA1,
+        0xC8,
+        0xD9, 0x22,
+        0x81, 0x5F,
+        0xDB, 0x35,
+        0xA6,
+        0xAF,
+    ]);
+}
+
+#[test]
+fn sh1106_frames_are_written_page_by_page() {
+    let mut frame = [0u8; 1024];
+    for (index, byte) in frame.iter_mut().enumerate() {
+        *byte = (index / 128) as u8;
+    }
+    
+    let mut display = SH1106::new(I2cInterface::new(RecordingI2c::default(), 0x3C), DisplaySize128x64);
+    display.write_frame(&frame).unwrap();
+    
+    let writes = display.release().release().writes;
+    let mut page = 0;
+    let mut data = Vec::new();
+    for (_, bytes) in writes {
+        match bytes[0] {
+            0x00 => {
+                // every page starts at column 2, where the panel's column 0 is
+                assert_eq!(bytes, [0x00, 0xB0 | page, 0x02, 0x10]);
+                page += 1;
+            }
+            _ => {
+                assert!(bytes[1..].iter().all(|&byte| byte == page - 1));
+                data.extend_from_slice(&bytes[1..]);
+            }
+        }
+    }
+    assert_eq!(page, 8);
+    assert_eq!(data, frame);
+}
+
+#[test]
+fn sh1106_commands() {
+    fn encode(command: sh1106::Command) -> Vec<u8> {
+        command.encode().as_bytes().to_vec()
+    }
+    
+    assert_eq!(encode(sh1106::Command::ColumnStart(130)), [0x02, 0x18]);
+    assert_eq!(encode(sh1106::Command::PumpVoltage(sh1106::PumpVoltage::V9_0)), [0x33]);
+    assert_eq!(encode(sh1106::Command::DcDc(false)), [0xAD, 0x8A]);
+    assert_eq!(encode(sh1106::Command::PageStart(Page::Page5)), [0xB5]);
+    assert_eq!(encode(sh1106::Command::ComPinConfig { alternative: false }), [0xDA, 0x02]);
+    assert_eq!(encode(sh1106::Command::ReadModifyWrite), [0xE0]);
+    assert_eq!(encode(sh1106::Command::End), [0xEE]);
+}
+
+/// Everything that happens on a mock SPI bus, in order.
+#[derive(Debug, Clone, PartialEq)]
+enum SpiEvent {
+    Write(Vec<u8>),
+    Pin(&'static str, bool),
+    Delay(u16),
+}
+
+type SpiLog = Rc<RefCell<Vec<SpiEvent>>>;
+
+struct RecordingSpi(SpiLog);
+struct RecordingPin(&'static str, SpiLog);
+struct RecordingDelay(SpiLog);
+
+impl spi::Write<u8> for RecordingSpi {
+    type Error = ();
+    
+    fn write(&mut self, words: &[u8]) -> Result<(), ()> {
+        self.0.borrow_mut().push(SpiEvent::Write(words.to_vec()));
+        Ok(())
+    }
+}
+
+impl OutputPin for RecordingPin {
+    type Error = ();
+    
+    fn set_low(&mut self) -> Result<(), ()> {
+        self.1.borrow_mut().push(SpiEvent::Pin(self.0, false));
+        Ok(())
+    }
+    
+    fn set_high(&mut self) -> Result<(), ()> {
+        self.1.borrow_mut().push(SpiEvent::Pin(self.0, true));
+        Ok(())
+    }
+}
+
+impl DelayUs<u16> for RecordingDelay {
+    fn delay_us(&mut self, us: u16) {
+        self.0.borrow_mut().push(SpiEvent::Delay(us));
+    }
+}
+
+fn spi_interface(log: &SpiLog) -> SpiInterface<RecordingSpi, RecordingPin, RecordingPin, RecordingPin> {
+    SpiInterface::new(
+        RecordingSpi(log.clone()),
+        RecordingPin("dc", log.clone()),
+        RecordingPin("cs", log.clone()),
+        RecordingPin("rst", log.clone()),
+    )
+}
+
+#[test]
+fn spi_uses_dc_instead_of_control_bytes() {
+    let log = SpiLog::default();
+    let mut display = SSD1306::new(spi_interface(&log), DisplaySize128x64);
+    display.send_command(Command::SetContrast { contrast: 0x10 }).unwrap();
+    display.send_data(&[0x12, 0x34]).unwrap();
+    
+    use SpiEvent::*;
+    assert_eq!(*log.borrow(), [
+        Pin("dc", false), Pin("cs", false), Write(vec![0x81, 0x10]), Pin("cs", true),
+        Pin("dc", true), Pin("cs", false), Write(vec![0x12, 0x34]), Pin("cs", true),
+    ]);
+}
+
+#[test]
+fn spi_and_i2c_share_the_init_sequence() {
+    let log = SpiLog::default();
+    let mut display = SSD1306::new(spi_interface(&log), DisplaySize128x64);
+    display.initialize().unwrap();
+    
+    let mut i2c_display = SSD1306::new(I2cInterface::new(RecordingI2c::default(), 0x3C), DisplaySize128x64);
+    i2c_display.initialize().unwrap();
+    let i2c_writes = i2c_display.release().release().writes;
+    
+    assert_eq!(log.borrow()[2], SpiEvent::Write(i2c_writes[0].1[1..].to_vec()));
+}
+
+#[test]
+fn long_command_streams_are_split_into_transactions() {
+    use super::interface::{Interface, MAX_COMMAND_BYTES};
+    
+    let commands: Vec<u8> = (0..100).collect();
+    
+    let mut i2c = I2cInterface::new(RecordingI2c::default(), 0x3C);
+    i2c.send_commands(&commands).unwrap();
+    let writes = i2c.release().writes;
+    assert_eq!(writes.iter().map(|(_, bytes)| bytes.len()).collect::<Vec<_>>(), [
+        MAX_COMMAND_BYTES + 1,
+        MAX_COMMAND_BYTES + 1,
+        100 - 2 * MAX_COMMAND_BYTES + 1,
+    ]);
+    assert!(writes.iter().all(|(_, bytes)| bytes[0] == 0x00));
+    assert_eq!(writes.iter().flat_map(|(_, bytes)| &bytes[1..]).copied().collect::<Vec<_>>(), commands);
+    
+    let log = SpiLog::default();
+    spi_interface(&log).send_commands(&commands).unwrap();
+    let sent: Vec<_> = log.borrow().iter().filter_map(|event| match event {
+        SpiEvent::Write(bytes) => Some(bytes.clone()),
+        _ => None,
+    }).collect();
+    assert_eq!(sent, writes.iter().map(|(_, bytes)| bytes[1..].to_vec()).collect::<Vec<_>>());
+}
+
+#[test]
+fn spi_reset_pulses_the_reset_pin() {
+    let log = SpiLog::default();
+    let mut interface = spi_interface(&log);
+    interface.reset(&mut RecordingDelay(log.clone())).unwrap();
+    
+    let pins: Vec<_> = log.borrow().iter().filter_map(|event| match event {
+        SpiEvent::Pin(pin, level) => Some((*pin, *level)),
+        _ => None,
+    }).collect();
+    assert_eq!(pins, [("rst", true), ("rst", false), ("rst", true)]);
+}
+
+#[test]
+fn spi_errors_are_reported() {
+    struct FailingSpi;
+    
+    impl spi::Write<u8> for FailingSpi {
+        type Error = u8;
+        
+        fn write(&mut self, _: &[u8]) -> Result<(), u8> {
+            Err(7)
+        }
+    }
+    
+    let log = SpiLog::default();
+    let interface = SpiInterface::new(
+        FailingSpi,
+        RecordingPin("dc", log.clone()),
+        RecordingPin("cs", log.clone()),
+        RecordingPin("rst", log.clone()),
+    );
+    let mut display = SSD1306::new(interface, DisplaySize128x64);
+    
+    assert_eq!(display.send_data(&[0x00]), Err(Ssd1306Error::Bus(SpiError::Spi(7))));
+    // chip select still got released
+    assert_eq!(log.borrow().last(), Some(&SpiEvent::Pin("cs", true)));
+}
+
+/// Drive a transfer the way the TWI ISR would, against a bus where the
+/// display NACKs the `nack_at`th byte, and return the transactions that
+/// were sent (address byte included).
+fn run_transfer(transfer: &mut Transfer, nack_at: Option<usize>) -> Vec<Vec<u8>> {
+    let mut transactions: Vec<Vec<u8>> = Vec::new();
+    let mut sent = 0;
+    let mut action = transfer.start();
+    
+    loop {
+        let status = match action {
+            Action::Start if transactions.is_empty() => Status::Start,
+            Action::Start => Status::RepeatedStart,
+            Action::Write(byte) => {
+                transactions.last_mut().unwrap().push(byte);
+                sent += 1;
+                match transactions.last().unwrap().len() {
+                    _ if nack_at == Some(sent) => Status::DataNack,
+                    1 => Status::AddressAck,
+                    _ => Status::DataAck,
+                }
+            },
+            Action::Stop => break,
+        };
+        if matches!(status, Status::Start | Status::RepeatedStart) {
+            transactions.push(Vec::new());
+        }
+        action = transfer.on_status(status);
+    }
+    
+    assert!(transfer.is_finished());
+    transactions
+}
+
+fn leak(data: Vec<u8>) -> &'static mut [u8] {
+    std::boxed::Box::leak(data.into_boxed_slice())
+}
+
+#[test]
+fn transfer_streams_a_frame_behind_its_window() {
+    let data = leak((0..=255).cycle().take(DisplaySize128x32::BUFFER_SIZE).collect());
+    let mut transfer = Transfer::frame::<DisplaySize128x32>(0x3C, data).unwrap();
+    let transactions = run_transfer(&mut transfer, None);
+    
+    assert_eq!(transactions.len(), 2);
+    assert_eq!(transactions[0], [0x3C << 1, 0x00, 0x21, 0, 127, 0x22, 0, 3]);
+    assert_eq!(transactions[1][..2], [0x3C << 1, 0x40]);
+    assert_eq!(transactions[1].len(), 2 + 512);
+    assert_eq!(transactions[1][2..], *transfer.into_buffer().unwrap());
+}
+
+#[test]
+fn transfer_region_applies_the_column_offset() {
+    let mut transfer = Transfer::region::<DisplaySize72x40>(0x3D, 4, 5, Page::Page1, Page::Page2, leak(vec![1, 2, 3, 4])).unwrap();
+    let transactions = run_transfer(&mut transfer, None);
+    
+    assert_eq!(transactions, [
+        vec![0x3D << 1, 0x00, 0x21, 32, 33, 0x22, 1, 2],
+        vec![0x3D << 1, 0x40, 1, 2, 3, 4],
+    ]);
+    assert_eq!(transfer.result(), Some(Ok(())));
+}
+
+#[test]
+fn transfer_checks_the_region() {
+    let result = Transfer::region::<DisplaySize128x64>(0x3C, 0, 9, Page::Page0, Page::Page0, leak(vec![0; 9]));
+    assert_eq!(result.err(), Some(InvalidParameter::BufferLength));
+    
+    let result = Transfer::region::<DisplaySize128x32>(0x3C, 0, 9, Page::Page0, Page::Page4, leak(vec![0; 50]));
+    assert_eq!(result.err(), Some(InvalidParameter::Page));
+    
+    assert!(matches!(Transfer::write(0x3C, &[0; 42]), Err(TransferError::TooLong)));
+}
+
+#[test]
+fn transfer_write_is_one_transaction() {
+    let mut transfer = Transfer::write(0x3C, &[0x00, 0xAF]).unwrap();
+    
+    assert_eq!(run_transfer(&mut transfer, None), [vec![0x3C << 1, 0x00, 0xAF]]);
+    assert!(transfer.into_buffer().is_none());
+}
+
+#[test]
+fn transfer_stops_on_nack() {
+    let mut transfer = Transfer::frame::<DisplaySize128x64>(0x3C, leak(vec![0; 1024])).unwrap();
+    let transactions = run_transfer(&mut transfer, Some(20));
+    
+    assert_eq!(transactions.iter().map(Vec::len).sum::<usize>(), 20);
+    assert_eq!(transfer.result(), Some(Err(TransferError::DataNack)));
+    
+    let mut transfer = Transfer::write(0x3C, &[0x00, 0xAF]).unwrap();
+    transfer.start();
+    transfer.on_status(Status::from_twsr(0x08));
+    assert_eq!(transfer.on_status(Status::from_twsr(0x20)), Action::Stop);
+    assert_eq!(transfer.result(), Some(Err(TransferError::AddressNack)));
+}
+
+#[test]
+fn background_frame_sends_what_was_drawn() {
+    let mut display = horizontal_display(DisplaySize128x32);
+    let mut frame = BackgroundFrame::<DisplaySize128x32>::new(0x3C, leak(vec![0xFF; 512])).unwrap();
+    
+    // blank to begin with, and due to be sent
+    assert!(frame.is_dirty());
+    let mut transfer = frame.transfer().unwrap();
+    assert!(frame.is_sending());
+    assert!(frame.target(&mut display).is_none());
+    assert!(run_transfer(&mut transfer, None)[1][2..].iter().all(|&byte| byte == 0));
+    assert_eq!(frame.finish(transfer), Ok(()));
+    assert!(frame.transfer().is_none());
+    
+    let mut target = frame.target(&mut display).unwrap();
+    target.write_page(Page::Page1, 126, &[0xAA, 0x55]).unwrap();
+    target.write_page_flash(Page::Page3, 0, crate::progmem!(&[1, 2, 3])).unwrap();
+    assert_eq!(target.write_page(Page::Page4, 0, &[1]), Err(InvalidParameter::Page.into()));
+    assert_eq!(target.write_page(Page::Page0, 127, &[1, 2]), Err(InvalidParameter::Column.into()));
+    
+    let mut transfer = frame.transfer().unwrap();
+    let transactions = run_transfer(&mut transfer, None);
+    assert_eq!(transactions[0], [0x3C << 1, 0x00, 0x21, 0, 127, 0x22, 0, 3]);
+    assert_eq!(transactions[1][2 + 254..2 + 256], [0xAA, 0x55]);
+    assert_eq!(transactions[1][2 + 384..2 + 387], [1, 2, 3]);
+    
+    // none of the drawing touched the display itself
+    assert_eq!(display.release().release().writes.len(), 1);
+}
+
+#[test]
+fn background_frame_resends_a_failed_transfer() {
+    let mut display = horizontal_display(DisplaySize128x32);
+    let mut frame = BackgroundFrame::<DisplaySize128x32>::new(0x3C, leak(vec![0; 512])).unwrap();
+    
+    let mut transfer = frame.transfer().unwrap();
+    run_transfer(&mut transfer, Some(20));
+    assert_eq!(frame.finish(transfer), Err(TransferError::DataNack));
+    
+    // the bus didn't take it at all
+    let transfer = frame.transfer().unwrap();
+    assert_eq!(frame.finish(transfer), Ok(()));
+    assert!(frame.is_dirty());
+    
+    // controller settings go straight to the display
+    frame.target(&mut display).unwrap().set_contrast(0x20).unwrap();
+    assert_eq!(display.release().release().writes[1].1, [0x00, 0x81, 0x20]);
+    
+    assert!(matches!(
+        BackgroundFrame::<DisplaySize128x64>::new(0x3C, leak(vec![0; 512])),
+        Err(InvalidParameter::BufferLength)
+    ));
+}
+
+#[test]
+fn orientation_flags() {
+    use super::orientation::{Orientation, Rotation};
+    
+    let upright = Orientation::new(Rotation::Deg0);
+    assert_eq!((upright.segment_remap(), upright.com_scan_reversed()), (true, true));
+    
+    let flipped = Orientation::new(Rotation::Deg180);
+    assert_eq!((flipped.segment_remap(), flipped.com_scan_reversed()), (false, false));
+    
+    // mirroring an upside down panel horizontally only leaves the rows flipped
+    let mirrored = flipped.mirrored(true, false);
+    assert_eq!((mirrored.segment_remap(), mirrored.com_scan_reversed()), (true, false));
+}
+
+#[test]
+fn init_uses_the_configured_orientation() {
+    use super::orientation::{Orientation, Rotation};
+    
+    let interface = I2cInterface::new(RecordingI2c::default(), 0x3C);
+    let mut display = SSD1306::new(interface, DisplaySize128x64)
+        .with_orientation(Orientation::new(Rotation::Deg180));
+    display.initialize().unwrap();
+    let writes = display.release().release().writes;
+    
+    let init = &writes[0].1;
+    assert!(init.windows(2).any(|pair| pair == [0xA0, 0xC0]));
+    assert!(!init.contains(&0xA1) && !init.contains(&0xC8));
+}
+
+#[test]
+fn set_rotation_at_runtime() {
+    use super::orientation::Rotation;
+    
+    let mut display = SSD1306::new(I2cInterface::new(RecordingI2c::default(), 0x3C), DisplaySize128x64);
+    display.set_mirror(false, true).unwrap();
+    // only the COM direction changed, which applies to the RAM straight away
+    assert!(!display.ram_needs_rewrite());
+    
+    display.set_rotation(Rotation::Deg180).unwrap();
+    assert!(display.ram_needs_rewrite());
+    assert_eq!(display.orientation().rotation, Rotation::Deg180);
+    assert!(display.orientation().mirror_vertical);
+    
+    let writes = display.release().release().writes;
+    assert_eq!(writes, [(0x3C, vec![0x00, 0xA1, 0xC0]), (0x3C, vec![0x00, 0xA0, 0xC8])]);
+}
+
+#[test]
+fn sh1106_batches_like_the_ssd1306() {
+    use super::interface::MAX_COMMAND_BYTES;
+    
+    let mut display = SH1106::new(I2cInterface::new(RecordingI2c::default(), 0x3C), DisplaySize128x64);
+    display.send_commands(core::iter::repeat_n(sh1106::Command::ColumnStart(0x12), 30)).unwrap();
+    
+    let writes = display.release().release().writes;
+    assert_eq!(writes.iter().map(|(_, bytes)| bytes.len()).collect::<Vec<_>>(), [
+        MAX_COMMAND_BYTES + 1,
+        60 - MAX_COMMAND_BYTES + 1,
+    ]);
+}
+
+#[test]
+fn sh1106_adds_the_panel_offset_to_its_own() {
+    let mut display = SH1106::new(I2cInterface::new(RecordingI2c::default(), 0x3C), DisplaySize72x40);
+    display.write_page(Page::Page4, 71, &[0xFF]).unwrap();
+    
+    // 2 for the SH1106's RAM, 28 for the 72x40 panel within the 128 columns
+    assert_eq!(display.release().release().writes[0].1, [0x00, 0xB4, 0x05, 0x16]);
+}
+
+#[test]
+fn sh1106_orientation() {
+    use super::orientation::{Orientation, Rotation};
+    
+    let mut display = SH1106::new(I2cInterface::new(RecordingI2c::default(), 0x3C), DisplaySize128x64);
+    Display::set_orientation(&mut display, Orientation::new(Rotation::Deg180).mirrored(true, true)).unwrap();
+    
+    assert_eq!(display.release().release().writes, [(0x3C, vec![0x00, 0xA1, 0xC8])]);
+}
+
+fn horizontal_display<S: DisplaySize>(size: S) -> SSD1306<I2cInterface<RecordingI2c>, S, HorizontalAddressing> {
+    let display = SSD1306::new(I2cInterface::new(RecordingI2c::default(), 0x3C), size);
+    display.set_address_mode::<HorizontalAddressing>().unwrap()
+}
+
+#[test]
+fn framebuffer_pixels() {
+    let mut frame = FrameBuffer::<DisplaySize128x32>::new();
+    frame.set_pixel(3, 9, true);
+    frame.set_pixel(200, 9, true);
+    frame.set_pixel(3, 32, true);
+    
+    assert!(frame.get_pixel(3, 9));
+    assert!(!frame.get_pixel(3, 8));
+    assert_eq!(frame.page(Page::Page1)[3], 0b10);
+    assert_eq!(frame.as_bytes().iter().filter(|&&byte| byte != 0).count(), 1);
+    
+    frame.set_pixel(3, 9, false);
+    assert!(!frame.get_pixel(3, 9));
+}
+
+#[test]
+fn framebuffer_first_flush_is_one_frame() {
+    let mut display = horizontal_display(DisplaySize128x64);
+    let mut frame = FrameBuffer::<DisplaySize128x64>::new();
+    frame.flush(&mut display).unwrap();
+    
+    assert!(!frame.is_dirty());
+    // skip the addressing mode change
+    let writes = display.release().release().writes;
+    assert_eq!(writes[1].1, [0x00, 0x21, 0, 127, 0x22, 0, 7]);
+    assert_eq!(writes[2..].iter().map(|(_, write)| write.len() - 1).sum::<usize>(), 1024);
+}
+
+#[test]
+fn framebuffer_flushes_only_dirty_columns() {
+    let mut display = horizontal_display(DisplaySize128x64);
+    let mut frame = FrameBuffer::<DisplaySize128x64>::new();
+    frame.flush(&mut display).unwrap();
+    
+    frame.set_pixel(10, 0, true);
+    frame.set_pixel(12, 7, true);
+    frame.set_pixel(100, 63, true);
+    // already off, so nothing to send
+    frame.set_pixel(50, 20, false);
+    assert_eq!(frame.dirty_columns(Page::Page0), Some(DirtyColumns { start: 10, end: 12 }));
+    assert_eq!(frame.dirty_columns(Page::Page2), None);
+    frame.flush(&mut display).unwrap();
+    
+    let writes = display.release().release().writes;
+    assert_eq!(writes[writes.len() - 4..], [
+        (0x3C, vec![0x00, 0x21, 10, 12, 0x22, 0, 0]),
+        (0x3C, vec![0x40, 0x01, 0x00, 0x80]),
+        (0x3C, vec![0x00, 0x21, 100, 100, 0x22, 7, 7]),
+        (0x3C, vec![0x40, 0x80]),
+    ]);
+}
+
+#[test]
+fn framebuffer_fill_only_dirties_changed_columns() {
+    let mut display = horizontal_display(DisplaySize72x40);
+    let mut frame = FrameBuffer::<DisplaySize72x40>::new();
+    frame.flush(&mut display).unwrap();
+    
+    frame.set_pixel(5, 0, true);
+    frame.set_pixel(60, 0, true);
+    frame.mark_all_dirty();
+    assert!((0..5).all(|page| frame.dirty_columns(Page::try_from(page).unwrap()).is_some()));
+    frame.flush(&mut display).unwrap();
+    
+    frame.clear();
+    assert_eq!(frame.dirty_columns(Page::Page0), Some(DirtyColumns { start: 5, end: 60 }));
+    assert_eq!(frame.dirty_columns(Page::Page1), None);
+    
+    frame.fill(true);
+    assert_eq!(frame.dirty_columns(Page::Page4), Some(DirtyColumns { start: 0, end: 71 }));
+    assert!(frame.as_bytes().iter().all(|&byte| byte == 0xFF));
+    assert_eq!(frame.as_bytes().len(), 72 * 5);
+}
+
+#[test]
+fn framebuffer_flushes_to_sh1106() {
+    let mut display = SH1106::new(I2cInterface::new(RecordingI2c::default(), 0x3C), DisplaySize128x64);
+    let mut frame = FrameBuffer::<DisplaySize128x64>::new();
+    frame.flush(&mut display).unwrap();
+    
+    frame.set_pixel(0, 8, true);
+    frame.flush(&mut display).unwrap();
+    
+    let writes = display.release().release().writes;
+    assert_eq!(writes[writes.len() - 2..], [
+        (0x3C, vec![0x00, 0xB1, 0x02, 0x10]),
+        (0x3C, vec![0x40, 0x01]),
+    ]);
+}
+
+/// A test screen: a border, plus a diagonal line on the left.
+fn border_screen() -> [&'static dyn Drawable; 2] {
+    fn border(canvas: &mut dyn Canvas) {
+        let (width, height) = canvas.size();
+        for x in 0..width {
+            canvas.set_pixel(x, 0, true);
+            canvas.set_pixel(x, height - 1, true);
+        }
+        for y in 0..height {
+            canvas.set_pixel(0, y, true);
+            canvas.set_pixel(width - 1, y, true);
+        }
+    }
+    
+    fn diagonal(canvas: &mut dyn Canvas) {
+        for i in 0..20 {
+            canvas.set_pixel(i, i, true);
+        }
+    }
+    
+    [&border, &diagonal]
+}
+
+#[test]
+fn strip_and_framebuffer_render_the_same_screen() {
+    let mut frame = FrameBuffer::<DisplaySize128x32>::new();
+    frame.draw(&border_screen());
+    
+    let mut display = SH1106::new(I2cInterface::new(RecordingI2c::default(), 0x3C), DisplaySize128x32);
+    strip::render(&mut display, &border_screen()).unwrap();
+    let writes = display.release().release().writes;
+    
+    let streamed: Vec<u8> = writes
+        .iter()
+        .filter(|(_, write)| write[0] == 0x40)
+        .flat_map(|(_, write)| write[1..].to_vec())
+        .collect();
+    assert_eq!(streamed, frame.as_bytes());
+}
+
+#[test]
+fn strip_only_keeps_its_own_page() {
+    let mut strip = PageStrip::<DisplaySize128x64>::new(Page::Page2);
+    strip.set_pixel(1, 7, true);
+    strip.set_pixel(1, 16, true);
+    strip.set_pixel(1, 23, true);
+    strip.set_pixel(1, 24, true);
+    assert_eq!(strip.as_bytes()[1], 0x81);
+    
+    strip.set_page(Page::Page3);
+    assert!(strip.as_bytes().iter().all(|&byte| byte == 0));
+    strip.fill(true);
+    assert_eq!(strip.as_bytes().len(), 128);
+    assert!(strip.as_bytes().iter().all(|&byte| byte == 0xFF));
+}
+
+#[cfg(feature = "graphics")]
+#[test]
+fn graphics_draw_to_framebuffer_and_strip() {
+    use embedded_graphics_core::draw_target::DrawTarget;
+    use embedded_graphics_core::geometry::{Point, Size};
+    use embedded_graphics_core::pixelcolor::BinaryColor;
+    use embedded_graphics_core::primitives::Rectangle;
+    use embedded_graphics_core::Pixel;
+    
+    use super::graphics::Graphic;
+    
+    let mut frame = FrameBuffer::<DisplaySize128x32>::new();
+    frame.fill_solid(&Rectangle::new(Point::new(120, 4), Size::new(20, 8)), BinaryColor::On).unwrap();
+    assert!(frame.get_pixel(127, 11));
+    assert!(!frame.get_pixel(127, 12));
+    assert_eq!(frame.page(Page::Page0)[120..], [0xF0; 8]);
+    
+    // off-panel pixels are clipped instead of wrapping around
+    frame.draw_iter([Pixel(Point::new(-1, 0), BinaryColor::On), Pixel(Point::new(0, 300), BinaryColor::On)]).unwrap();
+    assert!(!frame.get_pixel(0, 0) && !frame.get_pixel(255, 0));
+    
+    let dots = Graphic(Pixel(Point::new(3, 17), BinaryColor::On));
+    let mut frame = FrameBuffer::<DisplaySize128x32>::new();
+    frame.draw(&[&dots]);
+    
+    let mut display = SH1106::new(I2cInterface::new(RecordingI2c::default(), 0x3C), DisplaySize128x32);
+    strip::render(&mut display, &[&dots]).unwrap();
+    let streamed: Vec<u8> = display.release().release().writes
+        .iter()
+        .filter(|(_, write)| write[0] == 0x40)
+        .flat_map(|(_, write)| write[1..].to_vec())
+        .collect();
+    assert_eq!(streamed, frame.as_bytes());
+    assert_eq!(frame.page(Page::Page2)[3], 0b10);
+}
+
+/// Data bytes written to the display, in order.
+fn data_bytes(writes: &[(u8, Vec<u8>)]) -> Vec<u8> {
+    writes
+        .iter()
+        .filter(|(_, write)| write[0] == 0x40)
+        .flat_map(|(_, write)| write[1..].to_vec())
+        .collect()
+}
+
+#[test]
+fn write_str_at_renders_one_run() {
+    let mut display = SSD1306::new(I2cInterface::new(RecordingI2c::default(), 0x3C), DisplaySize128x64);
+    let end = display.write_str_at(Page::Page2, 10, "Hi!").unwrap();
+    assert_eq!(end, 10 + 3 * 6);
+    
+    let writes = display.release().release().writes;
+    assert_eq!(writes[0].1, [0x00, 0xB2, 0x0A, 0x10]);
+    assert_eq!(data_bytes(&writes), [
+        0x7F, 0x08, 0x08, 0x08, 0x7F, 0x00,
+        0x00, 0x44, 0x7D, 0x40, 0x00, 0x00,
+        0x00, 0x00, 0x5F, 0x00, 0x00, 0x00,
+    ]);
+}
+
+#[test]
+fn write_str_at_clips_and_falls_back() {
+    let mut display = SSD1306::new(I2cInterface::new(RecordingI2c::default(), 0x3C), DisplaySize128x64);
+    // 3 columns left: only part of the first glyph fits
+    assert_eq!(display.write_str_at(Page::Page0, 125, "AB").unwrap(), 128);
+    assert_eq!(display.write_str_at(Page::Page0, 128, "AB").unwrap(), 128);
+    assert_eq!(display.write_str_at(Page::Page0, 0, "\u{e9}").unwrap(), 6);
+    
+    let writes = display.release().release().writes;
+    assert_eq!(data_bytes(&writes), [0x7E, 0x11, 0x11, 0x02, 0x01, 0x51, 0x09, 0x06, 0x00]);
+    
+    let font = &super::font::FONT_5X7;
+    assert_eq!(font.glyphs.len(), ('~' as usize - ' ' as usize + 1) * 5);
+    assert_eq!(font.glyph('~').iter().collect::<Vec<_>>(), [0x08, 0x04, 0x08, 0x10, 0x08]);
+}
+
+/// What the build script made of `assets/`.
+mod assets {
+    #![allow(dead_code)]
+    
+    use crate::ssd1306::animation::Animation;
+    use crate::ssd1306::bitmap::Bitmap;
+    use crate::ssd1306::font::Font;
+    
+    include!(concat!(env!("OUT_DIR"), "/assets.rs"));
+}
+
+#[test]
+fn bdf_fonts_are_converted_to_columns() {
+    let font = &assets::DIGITS_3X5;
+    let glyph = |c| font.glyph(c).iter().collect::<Vec<_>>();
+    
+    // '-' to '9', with the gaps left blank and the degree sign dropped
+    assert_eq!((font.glyph_width, font.first, font.last, font.fallback), (3, '-', '9', '-'));
+    assert_eq!(font.glyphs.len(), 13 * 3);
+    
+    // 6 rows, the bottom one for descenders
+    assert_eq!(glyph('0'), [0x1F, 0x11, 0x1F]);
+    assert_eq!(glyph('2'), [0x1D, 0x15, 0x17]);
+    assert_eq!(glyph('9'), [0x17, 0x15, 0x1F]);
+    // glyphs smaller than the cell are placed by their BBX
+    assert_eq!(glyph('1'), [0x00, 0x1F, 0x00]);
+    assert_eq!(glyph('-'), [0x04, 0x04, 0x04]);
+    assert_eq!(glyph('.'), [0x00, 0x00, 0x00]);
+    assert_eq!(glyph('A'), glyph('-'));
+}
+
+#[test]
+fn png_images_are_packed_into_pages() {
+    let bolt = &assets::BOLT;
+    assert_eq!((bolt.width, bolt.height, bolt.pages()), (5, 10, 2));
+    
+    // only the dark, opaque pixels are lit: the transparent background and
+    // the white bottom row aren't
+    assert_eq!(bolt.page(0).iter().collect::<Vec<_>>(), [0x18, 0x1E, 0xD7, 0x71, 0x30]);
+    assert_eq!(bolt.page(1).iter().collect::<Vec<_>>(), [0x00, 0x01, 0x00, 0x00, 0x00]);
+}
+
+#[test]
+fn cursor_is_a_uwrite_sink() {
+    use ufmt::uWrite;
+    
+    let mut display = SH1106::new(I2cInterface::new(RecordingI2c::default(), 0x3C), DisplaySize128x32);
+    let mut cursor = display.cursor(Page::Page3, 0);
+    cursor.write_str("AMMO: 1").unwrap();
+    cursor.write_str("2\r\n").unwrap();
+    assert_eq!((cursor.page(), cursor.col()), (Page::Page0, 0));
+    cursor.write_char('-').unwrap();
+    assert_eq!(cursor.col(), 6);
+    
+    let writes = display.release().release().writes;
+    let data = data_bytes(&writes);
+    // "AMMO: 12", the rest of page 3 blanked, then "-" on page 0
+    assert_eq!(data.len(), 6 * 7 + 6 + (128 - 6 * 8) + 6);
+    assert!(data[6 * 8..6 * 8 + 80].iter().all(|&byte| byte == 0));
+    assert!(writes.iter().any(|(_, write)| write[..2] == [0x00, 0xB0]));
+}
+
+#[test]
+fn digit_columns() {
+    use super::digits::{digit_column, DigitHeight};
+    
+    // an 8 is a full box with a bar through the middle
+    assert_eq!(digit_column(DigitHeight::Px16, 8, 0), 0xFFFF);
+    assert_eq!(digit_column(DigitHeight::Px16, 8, 5), 0xC183);
+    // a 1 is just the right hand side
+    assert_eq!(digit_column(DigitHeight::Px24, 1, 0), 0);
+    assert_eq!(digit_column(DigitHeight::Px24, 1, 13), 0xFF_FFFF);
+    assert_eq!(digit_column(DigitHeight::Px32, 8, 17), u32::MAX);
+    assert_eq!(digit_column(DigitHeight::Px32, 8, 18), 0);
+    assert_eq!(digit_column(DigitHeight::Px32, 10, 0), 0);
+}
+
+#[test]
+fn big_number_only_redraws_changed_digits() {
+    use super::digits::{BigNumber, DigitHeight};
+    
+    let mut number = BigNumber::new(Page::Page2, 10, DigitHeight::Px24);
+    assert_eq!(number.width(), 17 * 2 + 14);
+    
+    let mut display = SSD1306::new(I2cInterface::new(RecordingI2c::default(), 0x3C), DisplaySize128x64);
+    number.update(&mut display, 120).unwrap();
+    let first = display.release().release().writes;
+    
+    let mut display = SSD1306::new(I2cInterface::new(RecordingI2c::default(), 0x3C), DisplaySize128x64);
+    number.update(&mut display, 120).unwrap();
+    number.update(&mut display, 129).unwrap();
+    let second = display.release().release().writes;
+    
+    // 3 digits of 3 pages, each a position and a data write
+    let positions = |writes: &[(u8, Vec<u8>)]| writes
+        .iter()
+        .filter(|(_, write)| write[0] == 0x00)
+        .map(|(_, write)| write[1..].to_vec())
+        .collect::<Vec<_>>();
+    assert_eq!(positions(&first).len(), 9);
+    // only the ones digit, at column 10 + 2 * 17 = 44
+    assert_eq!(positions(&second), [vec![0xB2, 0x0C, 0x12], vec![0xB3, 0x0C, 0x12], vec![0xB4, 0x0C, 0x12]]);
+}
+
+#[test]
+fn big_number_blanks_leading_zeros_and_checks_bounds() {
+    use super::digits::{BigNumber, DigitHeight};
+    
+    let mut display = SSD1306::new(I2cInterface::new(RecordingI2c::default(), 0x3C), DisplaySize128x32);
+    let mut number = BigNumber::new(Page::Page0, 0, DigitHeight::Px16);
+    number.update(&mut display, 5).unwrap();
+    let data = data_bytes(&display.release().release().writes);
+    // two blank digits with their gaps, then a 5
+    assert!(data[..2 * 12 * 2].iter().all(|&byte| byte == 0));
+    assert!(data[2 * 12 * 2..].iter().any(|&byte| byte != 0));
+    
+    let mut display = SSD1306::new(I2cInterface::new(RecordingI2c::default(), 0x3C), DisplaySize128x32);
+    let mut number = BigNumber::new(Page::Page1, 0, DigitHeight::Px32);
+    assert_eq!(number.update(&mut display, 5), Err(Ssd1306Error::InvalidParameter(InvalidParameter::Page)));
+    let mut number = BigNumber::new(Page::Page0, 100, DigitHeight::Px32);
+    assert_eq!(number.update(&mut display, 5), Err(Ssd1306Error::InvalidParameter(InvalidParameter::Column)));
+    assert!(display.release().release().writes.is_empty());
+}
+
+/// A 3x10 diagonal, as the build script packs it.
+const DIAGONAL: Bitmap = Bitmap { width: 3, height: 10, data: crate::progmem!(&[0x49, 0x92, 0x24, 0x02, 0x00, 0x01]) };
+
+#[test]
+fn bitmaps_are_page_packed() {
+    assert_eq!(DIAGONAL.pages(), 2);
+    assert_eq!(DIAGONAL.page(1).iter().collect::<Vec<_>>(), [0x02, 0x00, 0x01]);
+    
+    for y in 0..10 {
+        for x in 0..3 {
+            assert_eq!(DIAGONAL.get_pixel(x, y), x == y % 3, "({}, {})", x, y);
+        }
+    }
+    assert!(!DIAGONAL.get_pixel(3, 0));
+    assert!(!DIAGONAL.get_pixel(0, 12));
+    
+    // drawing is opaque, and clipped at the edges
+    let mut buffer = FrameBuffer::<DisplaySize128x32>::new();
+    buffer.fill(true);
+    DIAGONAL.draw(&mut buffer, 126, 20);
+    assert!(buffer.get_pixel(126, 20));
+    assert!(!buffer.get_pixel(127, 20));
+    assert!(buffer.get_pixel(127, 21));
+    assert!(buffer.get_pixel(126, 29));
+    assert!(!buffer.get_pixel(126, 28));
+    assert!(buffer.get_pixel(125, 20));
+}
+
+#[test]
+fn bitmaps_are_written_a_page_at_a_time() {
+    let mut display = SSD1306::new(I2cInterface::new(RecordingI2c::default(), 0x3C), DisplaySize128x32);
+    DIAGONAL.write_to(&mut display, Page::Page2, 5).unwrap();
+    let writes = display.release().release().writes;
+    assert_eq!(data_bytes(&writes), [0x49, 0x92, 0x24, 0x02, 0x00, 0x01]);
+    assert!(writes.contains(&(0x3C, vec![0x00, 0xB2, 0x05, 0x10])));
+    assert!(writes.contains(&(0x3C, vec![0x00, 0xB3, 0x05, 0x10])));
+    
+    let mut display = SSD1306::new(I2cInterface::new(RecordingI2c::default(), 0x3C), DisplaySize128x32);
+    assert_eq!(DIAGONAL.write_to(&mut display, Page::Page3, 0), Err(Ssd1306Error::InvalidParameter(InvalidParameter::Page)));
+    assert_eq!(DIAGONAL.write_to(&mut display, Page::Page0, 126), Err(Ssd1306Error::InvalidParameter(InvalidParameter::Column)));
+    assert!(display.release().release().writes.is_empty());
+}
+
+#[test]
+fn flash_data_goes_out_like_ram_data() {
+    const FLASH: PmBytes = crate::progmem!(&[
+        0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0A, 0x0B, 0x0C, 0x0D, 0x0E, 0x0F,
+        0x10, 0x11, 0x12, 0x13, 0x14, 0x15, 0x16, 0x17, 0x18, 0x19, 0x1A, 0x1B, 0x1C, 0x1D, 0x1E, 0x1F,
+        0x20, 0x21, 0x22, 0x23, 0x24,
+    ]);
+    let ram = FLASH.iter().collect::<Vec<_>>();
+    
+    let mut display = SSD1306::new(I2cInterface::new(RecordingI2c::default(), 0x3C), DisplaySize128x64);
+    display.send_data(&ram).unwrap();
+    display.send_flash_data(FLASH).unwrap();
+    let writes = display.release().release().writes;
+    assert_eq!(writes.len(), 6);
+    assert_eq!(writes[..3], writes[3..]);
+    
+    // over SPI flash data is sent a chunk at a time
+    let log = SpiLog::default();
+    let mut display = SSD1306::new(spi_interface(&log), DisplaySize128x64);
+    display.send_flash_data(FLASH).unwrap();
+    let sent = log.borrow().iter().flat_map(|event| match event {
+        SpiEvent::Write(bytes) => bytes.clone(),
+        _ => vec![],
+    }).collect::<Vec<_>>();
+    assert_eq!(sent, ram);
+    
+    let mut display = SH1106::new(I2cInterface::new(RecordingI2c::default(), 0x3C), DisplaySize128x64);
+    assert_eq!(display.write_page_flash(Page::Page0, 100, FLASH), Err(Ssd1306Error::InvalidParameter(InvalidParameter::Column)));
+    display.write_page_flash(Page::Page0, 10, FLASH.slice(0..5)).unwrap();
+    assert_eq!(data_bytes(&display.release().release().writes), [0x00, 0x01, 0x02, 0x03, 0x04]);
+}
+
+#[test]
+fn blit_sends_only_the_rectangle() {
+    let mut display = horizontal_display(DisplaySize128x64);
+    display.blit(10, Page::Page2, 3, 2, &[1, 2, 3, 4, 5, 6]).unwrap();
+    let writes = display.release().release().writes;
+    
+    // after switching to horizontal mode
+    assert_eq!(writes[1..], [
+        (0x3C, vec![0x00, 0x21, 10, 12, 0x22, 2, 3]),
+        (0x3C, vec![0x40, 1, 2, 3, 4, 5, 6]),
+    ]);
+}
+
+#[test]
+fn invalid_blits_are_rejected_before_touching_the_bus() {
+    let mut display = horizontal_display(DisplaySize128x64);
+    let invalid = |parameter| Err(Ssd1306Error::InvalidParameter(parameter));
+    
+    assert_eq!(display.blit(0, Page::Page0, 2, 2, &[0; 3]), invalid(InvalidParameter::BufferLength));
+    assert_eq!(display.blit(127, Page::Page0, 2, 1, &[0; 2]), invalid(InvalidParameter::Column));
+    assert_eq!(display.blit(0, Page::Page7, 1, 2, &[0; 2]), invalid(InvalidParameter::PageRange));
+    assert_eq!(display.blit(0, Page::Page0, 0, 1, &[]), invalid(InvalidParameter::ColumnRange));
+    // 8 rows at y = 60 would run off the bottom
+    assert_eq!(display.blit_at(0, 60, 1, 1, &[0xFF], None), invalid(InvalidParameter::PageRange));
+    // the extra page for the shift can't wrap the page count around
+    assert_eq!(display.blit_at(0, 1, 1, 255, &[0; 255], None), invalid(InvalidParameter::PageRange));
+    assert_eq!(display.blit_at(0, 250, 1, 1, &[0xFF], None), invalid(InvalidParameter::PageRange));
+    assert_eq!(display.release().release().writes.len(), 1);
+    
+    // a 32 row panel only has 4 pages
+    let mut display = horizontal_display(DisplaySize128x32);
+    assert_eq!(display.blit(0, Page::Page3, 1, 2, &[0; 2]), invalid(InvalidParameter::PageRange));
+    assert_eq!(display.blit_at(0, 28, 1, 1, &[0xFF], None), invalid(InvalidParameter::PageRange));
+    assert_eq!(display.release().release().writes.len(), 1);
+}
+
+#[test]
+fn blit_at_shifts_into_the_next_page() {
+    let mut display = horizontal_display(DisplaySize128x64);
+    display.blit_at(4, 11, 2, 1, &[0xFF, 0x81], None).unwrap();
+    let writes = display.release().release().writes;
+    
+    // rows 11-18 span pages 1 and 2
+    assert_eq!(writes[1], (0x3C, vec![0x00, 0x21, 4, 5, 0x22, 1, 2]));
+    assert_eq!(data_bytes(&writes), [0xF8, 0x08, 0x07, 0x04]);
+}
+
+#[test]
+fn blit_at_keeps_the_surroundings_from_the_shadow() {
+    let mut shadow = FrameBuffer::<DisplaySize128x64>::new();
+    shadow.fill(true);
+    shadow.flush(&mut horizontal_display(DisplaySize128x64)).unwrap();
+    
+    let mut display = horizontal_display(DisplaySize128x64);
+    display.blit_at(4, 11, 2, 1, &[0x00, 0x81], Some(&mut shadow)).unwrap();
+    let writes = display.release().release().writes;
+    
+    // rows 8-10 and 19-23 are kept from the shadow
+    assert_eq!(data_bytes(&writes), [0x07, 0x0F, 0xF8, 0xFC]);
+    assert_eq!(shadow.page(Page::Page1)[3..7], [0xFF, 0x07, 0x0F, 0xFF]);
+    assert_eq!(shadow.page(Page::Page2)[3..7], [0xFF, 0xF8, 0xFC, 0xFF]);
+    assert!(!shadow.is_dirty());
+}
+
+fn emulated_display() -> SSD1306<I2cInterface<Emulator>, DisplaySize128x64> {
+    let mut display = SSD1306::new(I2cInterface::new(Emulator::new(), 0x3C), DisplaySize128x64);
+    display.initialize().unwrap();
+    display
+}
+
+#[test]
+fn emulator_follows_the_addressing_modes() {
+    let mut display = emulated_display().set_address_mode::<HorizontalAddressing>().unwrap();
+    // a 2x2 window wraps to the next page after 2 bytes, and back to the start after 4
+    display.set_window(10, 11, Page::Page3, Page::Page4).unwrap();
+    display.send_data(&[1, 2, 3, 4, 5]).unwrap();
+    let mut display = display.set_address_mode::<VerticalAddressing>().unwrap();
+    display.set_window(20, 21, Page::Page0, Page::Page1).unwrap();
+    display.send_data(&[6, 7, 8]).unwrap();
+    
+    let emulator = display.release().release();
+    assert_eq!(emulator.addressing(), Addressing::Vertical);
+    assert_eq!([emulator.ram_byte(3, 10), emulator.ram_byte(3, 11)], [5, 2]);
+    assert_eq!([emulator.ram_byte(4, 10), emulator.ram_byte(4, 11)], [3, 4]);
+    assert_eq!([emulator.ram_byte(0, 20), emulator.ram_byte(1, 20), emulator.ram_byte(0, 21)], [6, 7, 8]);
+}
+
+#[test]
+fn emulator_models_the_display_registers() {
+    let mut display = emulated_display();
+    display.write_page(Page::Page0, 0, &[0x01]).unwrap();
+    
+    let emulator = display.release().release();
+    assert!(emulator.display_on());
+    assert!(!emulator.scrolling());
+    assert_eq!(emulator.contrast(), 0x5F);
+    // the init sequence shows the picture upright
+    assert!(emulator.pixel(0, 0));
+    assert!(!emulator.pixel(127, 63));
+    
+    let mut display = SSD1306::new(I2cInterface::new(emulator, 0x3C), DisplaySize128x64);
+    display.send_commands([
+        Command::SetStartLine(63),
+        Command::InvertDisplay(true),
+        Command::SetContrast { contrast: 0xFF },
+    ]).unwrap();
+    let emulator = display.release().release();
+    assert!(!emulator.pixel(0, 1));
+    assert!(emulator.pixel(0, 0));
+    assert!(emulator.inverted());
+    assert_eq!(emulator.start_line(), 63);
+    
+    // the segment remap only applies to data written after it
+    let mut display = SSD1306::new(I2cInterface::new(emulator, 0x3C), DisplaySize128x64);
+    display.send_commands([Command::SegmentRemap(false), Command::SetStartLine(0), Command::InvertDisplay(false)]).unwrap();
+    display.write_page(Page::Page7, 0, &[0x80]).unwrap();
+    let emulator = display.release().release();
+    assert!(emulator.pixel(0, 0));
+    assert!(emulator.pixel(127, 63));
+    
+    let png = emulator.render_png();
+    let mut reader = png::Decoder::new(png.as_slice()).read_info().unwrap();
+    let mut pixels = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut pixels).unwrap();
+    assert_eq!((info.width, info.height), (128, 64));
+    assert_eq!(info.color_type, png::ColorType::Grayscale);
+    assert_ne!(pixels[0], 0);
+    assert_eq!(pixels[1], 0);
+    assert_ne!(pixels[64 * 128 - 1], 0);
+}
+
+#[test]
+fn golden_text_and_digits() {
+    use super::digits::{BigNumber, DigitHeight};
+    use ufmt::uWrite;
+    
+    let mut display = emulated_display();
+    display.write_str_at(Page::Page0, 0, "MODE: BURST").unwrap();
+    display.cursor(Page::Page1, 0).write_str("AMMO 12/18\nREADY").unwrap();
+    BigNumber::new(Page::Page3, 30, DigitHeight::Px32).update(&mut display, 147).unwrap();
+    DIAGONAL.write_to(&mut display, Page::Page6, 120).unwrap();
+    
+    display.release().release().assert_matches_golden("text_and_digits");
+}
+
+#[test]
+fn golden_rotated_with_shifted_blit() {
+    let mut display = SSD1306::new(I2cInterface::new(Emulator::new(), 0x3C), DisplaySize128x64)
+        .with_orientation(Orientation::new(Rotation::Deg180));
+    display.initialize().unwrap();
+    display.write_str_at(Page::Page0, 0, "UPSIDE DOWN").unwrap();
+    
+    let mut display = display.set_address_mode::<HorizontalAddressing>().unwrap();
+    display.blit_at(60, 29, 8, 1, &[0x18, 0x3C, 0x7E, 0xFF, 0xFF, 0x7E, 0x3C, 0x18], None).unwrap();
+    
+    display.release().release().assert_matches_golden("rotated");
+}
+
+fn render_widget(widget: &impl super::ui::Widget, page: u8) -> Vec<u8> {
+    let mut line = vec![0; widget.area().width as usize];
+    widget.render(page, &mut line);
+    line
+}
+
+#[test]
+fn widgets_only_redraw_when_their_value_changes() {
+    use super::ui::{Align, Area, Number, Widget};
+    
+    let mut display = SSD1306::new(I2cInterface::new(RecordingI2c::default(), 0x3C), DisplaySize128x64);
+    let mut ammo = Number::new(Area::new(Page::Page1, 100, 28, 2), Align::Right);
+    ammo.set(18);
+    
+    assert!(ammo.draw(&mut display).unwrap());
+    let writes = display.release().release().writes;
+    // both pages of the area, and nothing else
+    assert_eq!(data_bytes(&writes).len(), 2 * 28);
+    
+    let mut display = SSD1306::new(I2cInterface::new(RecordingI2c::default(), 0x3C), DisplaySize128x64);
+    ammo.set(18);
+    assert!(!ammo.draw(&mut display).unwrap());
+    ammo.set(17);
+    assert!(ammo.draw(&mut display).unwrap());
+    assert!(!ammo.draw(&mut display).unwrap());
+    ammo.invalidate();
+    assert!(ammo.draw(&mut display).unwrap());
+}
+
+#[test]
+fn text_widgets_align_in_their_area() {
+    use super::font::FONT_5X7;
+    use super::ui::{Align, Area, Label, Number};
+    
+    let glyph = |c| FONT_5X7.glyph(c).iter().collect::<Vec<_>>();
+    
+    let mut number = Number::new(Area::new(Page::Page0, 0, 20, 1), Align::Right);
+    number.set(-7);
+    let line = render_widget(&number, 0);
+    assert_eq!(line[..9], [0; 9]);
+    assert_eq!(line[9..14], glyph('-'));
+    assert_eq!(line[15..20], glyph('7'));
+    
+    let label = Label::new(Area::new(Page::Page0, 0, 21, 2), Align::Center, crate::pm_str!("OK"));
+    let line = render_widget(&label, 0);
+    assert_eq!(line[..5], [0; 5]);
+    assert_eq!(line[5..10], glyph('O'));
+    assert_eq!(line[11..16], glyph('K'));
+    assert_eq!(line[16..], [0; 5]);
+    assert_eq!(render_widget(&label, 1), [0; 21]);
+    
+    // fixed point numbers render like the same text would
+    let area = Area::new(Page::Page0, 0, 40, 1);
+    for (value, text) in [(741, crate::pm_str!("7.41V")), (-5, crate::pm_str!("-0.05V")), (0, crate::pm_str!("0.00V"))] {
+        let mut number = Number::new(area, Align::Right).with_decimals(2).with_unit(crate::pm_str!("V"));
+        number.set(value);
+        assert_eq!(render_widget(&number, 0), render_widget(&Label::new(area, Align::Right, text), 0));
+    }
+}
+
+#[test]
+fn bars_redraw_only_when_a_column_changes() {
+    use super::ui::{Area, BatteryGauge, ProgressBar, Widget};
+    
+    // 10 columns inside the outline, so every 10% is a column
+    let mut bar = ProgressBar::new(Area::new(Page::Page0, 0, 14, 1), 100);
+    bar.set(30);
+    assert_eq!(render_widget(&bar, 0), [0xFF, 0x81, 0xBD, 0xBD, 0xBD, 0x81, 0x81, 0x81, 0x81, 0x81, 0x81, 0x81, 0x81, 0xFF]);
+    bar.mark_drawn();
+    bar.set(35);
+    assert!(!bar.is_dirty());
+    bar.set(250);
+    assert_eq!(bar.value(), 100);
+    assert!(bar.is_dirty());
+    
+    // 3 segments of 3 columns, and the terminal
+    let mut battery = BatteryGauge::new(Area::new(Page::Page0, 0, 19, 1));
+    assert_eq!(battery.segments(), 3);
+    battery.set_percent(1);
+    assert_eq!(
+        render_widget(&battery, 0),
+        [0xFF, 0x81, 0xBD, 0xBD, 0xBD, 0x81, 0x81, 0x81, 0x81, 0x81, 0x81, 0x81, 0x81, 0x81, 0x81, 0x81, 0xFF, 0x3C, 0x3C],
+    );
+    battery.mark_drawn();
+    battery.set_percent(33);
+    assert!(!battery.is_dirty());
+    battery.set_percent(34);
+    assert!(battery.is_dirty());
+}
+
+#[test]
+fn sparkline_keeps_the_newest_samples() {
+    use super::ui::{Area, Sparkline};
+    
+    let mut spark = Sparkline::<4>::new(Area::new(Page::Page0, 0, 4, 1), 0, 7);
+    spark.push(0);
+    assert_eq!(render_widget(&spark, 0), [0, 0, 0, 0x80]);
+    
+    for sample in [7, 3, 3, 100] {
+        spark.push(sample);
+    }
+    assert_eq!(spark.samples().collect::<Vec<_>>(), [7, 3, 3, 100]);
+    // the jump from 3 up to the top is joined up
+    assert_eq!(render_widget(&spark, 0), [0x01, 0x1F, 0x10, 0x1F]);
+}
+
+#[test]
+fn widgets_outside_the_display_are_rejected() {
+    use super::ui::{Area, Icon, Widget};
+    
+    let mut display = SSD1306::new(I2cInterface::new(RecordingI2c::default(), 0x3C), DisplaySize128x32);
+    let mut icon = Icon::new(Area::new(Page::Page3, 0, 3, 2), Some(DIAGONAL));
+    assert_eq!(icon.draw(&mut display), Err(Ssd1306Error::InvalidParameter(InvalidParameter::Page)));
+    let mut icon = Icon::new(Area::new(Page::Page0, 126, 3, 2), Some(DIAGONAL));
+    assert_eq!(icon.draw(&mut display), Err(Ssd1306Error::InvalidParameter(InvalidParameter::Column)));
+    assert!(display.release().release().writes.is_empty());
+}
+
+#[test]
+fn golden_widgets() {
+    use super::ui::{self, Align, Area, BatteryGauge, Icon, Label, Number, ProgressBar, Sparkline, Widget};
+    
+    let mut display = emulated_display();
+    ui::clear(&mut display).unwrap();
+    
+    let mut mode = Label::new(Area::new(Page::Page0, 0, 60, 1), Align::Left, crate::pm_str!("BURST"));
+    let mut ammo = Number::new(Area::new(Page::Page0, 64, 30, 1), Align::Right);
+    let mut battery = BatteryGauge::new(Area::new(Page::Page0, 104, 24, 1));
+    let mut icon = Icon::new(Area::new(Page::Page2, 0, 3, 2), Some(DIAGONAL));
+    let mut rev = ProgressBar::new(Area::new(Page::Page2, 8, 120, 2), 255);
+    let mut history = Sparkline::<64>::new(Area::new(Page::Page5, 32, 64, 3), -32, 32);
+    
+    ammo.set(12);
+    battery.set_percent(60);
+    rev.set(170);
+    for x in 0..64 {
+        history.push(((x % 16) as i16 - 8) * 4);
+    }
+    
+    assert!(mode.draw(&mut display).unwrap());
+    assert!(ammo.draw(&mut display).unwrap());
+    assert!(battery.draw(&mut display).unwrap());
+    assert!(icon.draw(&mut display).unwrap());
+    assert!(rev.draw(&mut display).unwrap());
+    assert!(history.draw(&mut display).unwrap());
+    
+    display.release().release().assert_matches_golden("widgets");
+}
+
+mod menu_tree {
+    use crate::ssd1306::menu::{Action, ChoicePicker, Item, ItemKind, Menu, NumberEditor};
+    
+    pub struct Settings {
+        pub power: i32,
+        pub mode: usize,
+        pub contrast: i32,
+        pub resets: u8,
+    }
+    
+    pub const DEFAULT: Settings = Settings { power: 50, mode: 0, contrast: 0x5F, resets: 0 };
+    
+    const DISPLAY: Menu<Settings> = Menu {
+        title: crate::pm_str!("DISPLAY"),
+        items: &[Item {
+            label: crate::pm_str!("Contrast"),
+            kind: ItemKind::Number(NumberEditor {
+                min: 0,
+                max: 255,
+                step: 16,
+                unit: crate::pm_str!(""),
+                get: |settings| settings.contrast,
+                set: |settings, value| settings.contrast = value,
+            }),
+        }],
+    };
+    
+    pub const ROOT: Menu<Settings> = Menu {
+        title: crate::pm_str!("SETTINGS"),
+        items: &[
+            Item {
+                label: crate::pm_str!("Power"),
+                kind: ItemKind::Number(NumberEditor {
+                    min: 10,
+                    max: 100,
+                    step: 5,
+                    unit: crate::pm_str!("%"),
+                    get: |settings| settings.power,
+                    set: |settings, value| settings.power = value,
+                }),
+            },
+            Item {
+                label: crate::pm_str!("Mode"),
+                kind: ItemKind::Choice(ChoicePicker {
+                    options: &[crate::pm_str!("SINGLE"), crate::pm_str!("BURST"), crate::pm_str!("AUTO")],
+                    get: |settings| settings.mode,
+                    set: |settings, index| settings.mode = index,
+                }),
+            },
+            Item { label: crate::pm_str!("Display"), kind: ItemKind::Submenu(&DISPLAY) },
+            Item {
+                label: crate::pm_str!("Reset"),
+                kind: ItemKind::Action(Action {
+                    confirm: Some(crate::pm_str!("Reset settings?")),
+                    run: |settings| *settings = Settings { resets: settings.resets + 1, ..DEFAULT },
+                }),
+            },
+        ],
+    };
+}
+
+#[test]
+fn menu_edits_numbers_and_choices() {
+    use super::menu::{Event, Input, MenuState};
+    use menu_tree::{DEFAULT, ROOT};
+    
+    let mut settings = DEFAULT;
+    let mut menu = MenuState::new(&ROOT);
+    
+    // the editor clamps, and nothing changes until it's committed
+    assert_eq!(menu.handle(Input::Select, &mut settings), Event::Moved);
+    for _ in 0..20 {
+        menu.handle(Input::Up, &mut settings);
+    }
+    assert_eq!(settings.power, 50);
+    assert_eq!(menu.handle(Input::Select, &mut settings), Event::Changed);
+    assert_eq!(settings.power, 100);
+    
+    // Back throws the edit away
+    menu.handle(Input::Select, &mut settings);
+    menu.handle(Input::Down, &mut settings);
+    assert_eq!(menu.handle(Input::Back, &mut settings), Event::Moved);
+    assert_eq!(settings.power, 100);
+    
+    // choices wrap around
+    menu.handle(Input::Down, &mut settings);
+    menu.handle(Input::Select, &mut settings);
+    menu.handle(Input::Up, &mut settings);
+    assert_eq!(menu.handle(Input::Select, &mut settings), Event::Changed);
+    assert_eq!(settings.mode, 2);
+}
+
+#[test]
+fn menu_navigates_submenus_and_confirms_actions() {
+    use super::menu::{Event, Input, MenuState};
+    use menu_tree::{DEFAULT, ROOT};
+    
+    let mut settings = DEFAULT;
+    let mut menu = MenuState::new(&ROOT);
+    
+    // the selection wraps from the top to the last item
+    menu.handle(Input::Up, &mut settings);
+    settings.power = 80;
+    menu.handle(Input::Select, &mut settings);
+    assert_eq!(menu.handle(Input::Select, &mut settings), Event::Moved);
+    assert_eq!(settings.resets, 0, "no is the default answer");
+    
+    menu.handle(Input::Select, &mut settings);
+    menu.handle(Input::Down, &mut settings);
+    assert_eq!(menu.handle(Input::Select, &mut settings), Event::Changed);
+    assert_eq!((settings.resets, settings.power), (1, 50));
+    
+    // into the display menu and back out
+    menu.handle(Input::Up, &mut settings);
+    menu.handle(Input::Select, &mut settings);
+    menu.handle(Input::Select, &mut settings);
+    menu.handle(Input::Down, &mut settings);
+    menu.handle(Input::Select, &mut settings);
+    assert_eq!(settings.contrast, 0x5F - 16);
+    assert_eq!(menu.handle(Input::Back, &mut settings), Event::Moved);
+    assert_eq!(menu.handle(Input::Back, &mut settings), Event::Closed);
+}
+
+#[test]
+fn menu_only_redraws_after_input() {
+    use super::menu::{Input, MenuState};
+    use menu_tree::{DEFAULT, ROOT};
+    
+    let mut settings = DEFAULT;
+    let mut menu = MenuState::new(&ROOT);
+    let mut display = SSD1306::new(I2cInterface::new(RecordingI2c::default(), 0x3C), DisplaySize128x32);
+    
+    assert!(menu.draw(&mut display, &settings).unwrap());
+    assert!(!menu.draw(&mut display, &settings).unwrap());
+    assert_eq!(data_bytes(&display.release().release().writes).len(), 4 * 128);
+    
+    // 3 rows fit under the title, so the last item scrolls the list
+    let mut display = SSD1306::new(I2cInterface::new(RecordingI2c::default(), 0x3C), DisplaySize128x32);
+    menu.handle(Input::Up, &mut settings);
+    assert!(menu.draw(&mut display, &settings).unwrap());
+    let data = data_bytes(&display.release().release().writes);
+    // the selected item is on the bottom row, highlighted
+    assert_eq!(data[3 * 128], 0xFF);
+    assert_eq!(data[128], 0x00);
+}
+
+#[test]
+fn golden_menu() {
+    use super::menu::{Input, MenuState};
+    use menu_tree::{DEFAULT, ROOT};
+    
+    let mut settings = DEFAULT;
+    let mut menu = MenuState::new(&ROOT);
+    let mut display = emulated_display();
+    
+    // editing the mode
+    menu.handle(Input::Down, &mut settings);
+    menu.handle(Input::Select, &mut settings);
+    menu.handle(Input::Down, &mut settings);
+    menu.draw(&mut display, &settings).unwrap();
+    display.release().release().assert_matches_golden("menu");
+    
+    let mut display = emulated_display();
+    menu.handle(Input::Back, &mut settings);
+    menu.handle(Input::Up, &mut settings);
+    menu.handle(Input::Up, &mut settings);
+    menu.handle(Input::Select, &mut settings);
+    menu.handle(Input::Down, &mut settings);
+    menu.draw(&mut display, &settings).unwrap();
+    display.release().release().assert_matches_golden("menu_confirm");
+}
+
+/// An 8x16 block, then the same block with a notch cut into its top page.
+const BLOCK: super::animation::Animation = super::animation::Animation {
+    width: 8,
+    height: 16,
+    data: crate::progmem!(&[
+        50, 0, super::animation::KEY_FRAME,
+        0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF,
+        0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF,
+        20, 0, 1,
+        0, 2, 2, 0x00, 0x00,
+    ]),
+};
+
+#[test]
+fn animations_draw_frames_as_they_come_due() {
+    use super::animation::Player;
+    
+    let mut display = SSD1306::new(I2cInterface::new(RecordingI2c::default(), 0x3C), DisplaySize128x64);
+    let mut player = Player::new();
+    assert!(!player.update(&mut display, 10).unwrap());
+    
+    player.play(BLOCK, Page::Page2, 60, false);
+    assert!(player.update(&mut display, 0).unwrap());
+    assert!(!player.update(&mut display, 30).unwrap());
+    assert_eq!(data_bytes(&display.release().release().writes).len(), 16);
+    
+    // only the changed bytes of the delta frame go out
+    let mut display = SSD1306::new(I2cInterface::new(RecordingI2c::default(), 0x3C), DisplaySize128x64);
+    assert!(player.update(&mut display, 20).unwrap());
+    assert_eq!(data_bytes(&display.release().release().writes), vec![0x00, 0x00]);
+    
+    // the last frame stays up for its duration too
+    let mut display = SSD1306::new(I2cInterface::new(RecordingI2c::default(), 0x3C), DisplaySize128x64);
+    assert!(!player.update(&mut display, 10).unwrap());
+    assert!(player.is_playing());
+    assert!(!player.update(&mut display, 10).unwrap());
+    assert!(!player.is_playing());
+    assert!(display.release().release().writes.is_empty());
+}
+
+#[test]
+fn looping_animations_start_over_from_the_key_frame() {
+    use super::animation::Player;
+    
+    let mut display = emulated_display();
+    let mut player = Player::new();
+    player.play(BLOCK, Page::Page2, 60, true);
+    for _ in 0..2 {
+        player.update(&mut display, 50).unwrap();
+    }
+    
+    let emulator = display.release().release();
+    assert_eq!(emulator.ram_byte(2, 61), 0xFF);
+    assert_eq!(emulator.ram_byte(2, 62), 0x00);
+    assert_eq!(emulator.ram_byte(3, 62), 0xFF);
+    
+    let mut display = emulated_display();
+    assert!(player.update(&mut display, 20).unwrap());
+    assert!(player.is_playing());
+    let emulator = display.release().release();
+    assert_eq!(emulator.ram_byte(2, 62), 0xFF);
+    
+    player.stop();
+    assert!(!player.is_playing());
+}
+
+#[test]
+fn animations_outside_the_display_are_rejected() {
+    use super::animation::{Animation, Player};
+    
+    let mut display = SSD1306::new(I2cInterface::new(RecordingI2c::default(), 0x3C), DisplaySize128x32);
+    let mut player = Player::new();
+    player.play(BLOCK, Page::Page3, 0, false);
+    assert_eq!(player.update(&mut display, 0), Err(Ssd1306Error::InvalidParameter(InvalidParameter::Page)));
+    player.play(BLOCK, Page::Page0, 124, false);
+    assert_eq!(player.update(&mut display, 0), Err(Ssd1306Error::InvalidParameter(InvalidParameter::Column)));
+    
+    // so is data that runs out in the middle of a frame
+    let truncated = Animation { data: BLOCK.data.slice(0..10), ..BLOCK };
+    player.play(truncated, Page::Page0, 0, false);
+    assert_eq!(player.update(&mut display, 0), Err(Ssd1306Error::InvalidParameter(InvalidParameter::BufferLength)));
+    assert!(display.release().release().writes.is_empty());
+}
+
+/// Play recorded bus writes into a fresh emulator.
+fn replay(writes: &[(u8, Vec<u8>)]) -> Emulator {
+    let mut emulator = Emulator::new();
+    for (_, bytes) in writes {
+        emulator.transaction(bytes);
+    }
+    emulator
+}
+
+/// A recording display in `rotation`, showing nothing but row 32 lit.
+fn transition_display(rotation: Rotation) -> SSD1306<I2cInterface<RecordingI2c>, DisplaySize128x64> {
+    let mut display = SSD1306::new(I2cInterface::new(RecordingI2c::default(), 0x3C), DisplaySize128x64)
+        .with_orientation(Orientation::new(rotation));
+    display.initialize().unwrap();
+    display.write_page(Page::Page4, 0, &[0x01; 128]).unwrap();
+    display
+}
+
+/// The rows lit in column 0.
+fn lit_rows(emulator: &Emulator) -> Vec<usize> {
+    (0..64).filter(|&y| emulator.pixel(0, y)).collect()
+}
+
+#[test]
+fn transitions_hide_the_screen_then_reveal_the_new_one() {
+    use super::transition::{Effect, Transition};
+    
+    for effect in [Effect::Fade, Effect::Roll, Effect::Slide, Effect::Wipe] {
+        let mut display = transition_display(Rotation::Deg0);
+        let mut transition = Transition::new();
+        transition.start(effect, 0x5F);
+        
+        let mut steps = 1;
+        while !transition.update(&mut display, 25).unwrap() {
+            assert!(transition.is_hiding());
+            steps += 1;
+        }
+        assert_eq!(steps, 9, "{:?}", effect);
+        
+        let writes = display.release().release().writes;
+        let emulator = replay(&writes);
+        assert!(!emulator.display_on());
+        
+        // draw the new screen in the dark, then let it come back
+        let mut display = SSD1306::new(I2cInterface::new(RecordingI2c { writes, ..Default::default() }, 0x3C), DisplaySize128x64);
+        display.write_page(Page::Page4, 0, &[0x00; 128]).unwrap();
+        display.write_page(Page::Page1, 0, &[0x01; 128]).unwrap();
+        assert!(!transition.update(&mut display, 10).unwrap());
+        
+        let mut steps = 0;
+        while transition.is_running() {
+            assert!(!transition.is_hiding());
+            assert!(!transition.update(&mut display, 25).unwrap());
+            steps += 1;
+        }
+        assert_eq!(steps, 8, "{:?}", effect);
+        
+        let emulator = replay(&display.release().release().writes);
+        assert!(emulator.display_on());
+        assert_eq!(emulator.contrast(), 0x5F);
+        assert_eq!(emulator.start_line(), 0);
+        assert_eq!(lit_rows(&emulator), [8], "{:?}", effect);
+    }
+}
+
+#[test]
+fn transitions_only_send_a_few_commands() {
+    use super::transition::{Effect, Transition};
+    
+    for effect in [Effect::Fade, Effect::Roll, Effect::Slide, Effect::Wipe] {
+        let before = transition_display(Rotation::Deg0).release().release().writes.len();
+        let mut display = transition_display(Rotation::Deg0);
+        
+        let mut transition = Transition::new();
+        transition.start(effect, 0x5F);
+        while transition.is_running() {
+            transition.update(&mut display, 25).unwrap();
+        }
+        
+        let writes = display.release().release().writes;
+        assert!(data_bytes(&writes[before..]).is_empty());
+        let sent: usize = writes[before..].iter().map(|(_, bytes)| bytes.len()).sum();
+        assert!(sent < 100, "{:?} sent {} bytes", effect, sent);
+    }
+}
+
+#[test]
+fn transitions_reset_the_scroll_area_to_the_panel() {
+    use super::transition::{Effect, Transition};
+    
+    let mut display = SSD1306::new(I2cInterface::new(RecordingI2c::default(), 0x3C), DisplaySize128x32);
+    display.initialize().unwrap();
+    
+    let mut transition = Transition::new();
+    transition.start(Effect::Fade, 0x5F);
+    transition.update(&mut display, 25).unwrap();
+    
+    // only 32 rows are driven, so the area can't be the 64 rows of RAM
+    let writes = display.release().release().writes;
+    assert_eq!(writes[1].1, [0x00, 0x2E, 0xA3, 0, 32]);
+}
+
+#[test]
+fn wipes_keep_the_picture_still_and_slides_move_it() {
+    use super::transition::{Effect, Transition};
+    
+    // halfway out, 24 rows are hidden
+    let halfway = |effect, rotation| {
+        let mut display = transition_display(rotation);
+        let mut transition = Transition::new();
+        transition.start(effect, 0x5F);
+        for _ in 0..4 {
+            transition.update(&mut display, 25).unwrap();
+        }
+        replay(&display.release().release().writes)
+    };
+    
+    let emulator = halfway(Effect::Wipe, Rotation::Deg0);
+    assert_eq!(lit_rows(&emulator), [32]);
+    assert!(!emulator.pixel(0, 23) && !emulator.pixel(0, 0));
+    let emulator = halfway(Effect::Slide, Rotation::Deg0);
+    assert_eq!(lit_rows(&emulator), [56]);
+    
+    // upside down, row 32 of the RAM is row 31 of the panel
+    assert_eq!(lit_rows(&halfway(Effect::Wipe, Rotation::Deg180)), [31]);
+    assert_eq!(lit_rows(&halfway(Effect::Slide, Rotation::Deg180)), [55]);
+    
+    let emulator = halfway(Effect::Roll, Rotation::Deg0);
+    assert_eq!(emulator.start_line(), 32);
+    assert_eq!(lit_rows(&emulator), [0]);
+    assert_eq!(halfway(Effect::Fade, Rotation::Deg0).contrast(), 0x5F / 2);
+}
+
+#[test]
+fn restarted_transitions_turn_around() {
+    use super::transition::{Effect, Transition};
+    
+    let mut display = transition_display(Rotation::Deg0);
+    let mut transition = Transition::new();
+    transition.start(Effect::Fade, 0x80);
+    while !transition.update(&mut display, 25).unwrap() {}
+    for _ in 0..3 {
+        transition.update(&mut display, 25).unwrap();
+    }
+    
+    // back out from 3 steps in, with the same effect
+    transition.start(Effect::Slide, 0x80);
+    assert!(transition.is_hiding());
+    let mut steps = 1;
+    while !transition.update(&mut display, 25).unwrap() {
+        steps += 1;
+    }
+    assert_eq!(steps, 4);
+    
+    let emulator = replay(&display.release().release().writes);
+    assert_eq!(emulator.contrast(), 0);
+    assert_eq!(lit_rows(&emulator), []);
+}
@@ -0,0 +1,113 @@
+//! Text output straight to the display, a page per line.
+//! 
+//! ```ignore
+//! display.write_str_at(Page::Page0, 0, "MODE: BURST")?;
+//! ufmt::uwriteln!(display.cursor(Page::Page1, 0), "AMMO: {}", ammo)?;
+//! ```
+
+use super::command::Page;
+use super::display::Display;
+use super::font::{Font, FONT_5X7};
+use super::size::DisplaySize;
+use super::strip::MAX_WIDTH;
+
+/// Render `s` into `line` starting at `col`, clipped at the end of the line.
+/// Returns the column after the last character drawn.
+pub(super) fn render_line(font: &Font, line: &mut [u8], col: u8, s: &str) -> u8 {
+    let mut col = col as usize;
+    
+    for c in s.chars() {
+        for column in font.glyph(c).iter().chain([0]) {
+            let Some(byte) = line.get_mut(col) else { return col as u8 };
+            *byte = column;
+            col += 1;
+        }
+    }
+    
+    col as u8
+}
+
+/// Text output for any [`Display`], using the built-in [`FONT_5X7`].
+pub trait TextDisplay: Display {
+    /// Write `s` on `page`, starting at column `col`, and return the column
+    /// after the last character. Text past the right edge is cut off.
+    /// 
+    /// The whole run goes out as one write.
+    fn write_str_at(&mut self, page: Page, col: u8, s: &str) -> Result<u8, Self::Error> {
+        let width = Self::Size::WIDTH;
+        if s.is_empty() || col >= width {
+            return Ok(col);
+        }
+        
+        let mut line = [0; MAX_WIDTH];
+        let end = render_line(&FONT_5X7, &mut line[..width as usize], col, s);
+        self.write_page(page, col, &line[col as usize..end as usize])?;
+        Ok(end)
+    }
+    
+    /// Blank `page` from column `col` to the right edge.
+    fn clear_line_from(&mut self, page: Page, col: u8) -> Result<(), Self::Error> {
+        let width = Self::Size::WIDTH;
+        if col >= width {
+            return Ok(());
+        }
+        
+        self.write_page(page, col, &[0; MAX_WIDTH][..(width - col) as usize])
+    }
+    
+    /// A text cursor at column `col` of `page`, for `ufmt::uwrite!`.
+    fn cursor(&mut self, page: Page, col: u8) -> TextCursor<'_, Self> where Self: Sized {
+        TextCursor { display: self, page, col }
+    }
+}
+
+impl<D: Display> TextDisplay for D {}
+
+/// Where the next character goes.
+/// 
+/// Lines are pages. A `'\n'` blanks the rest of the line before moving to the
+/// start of the next page (wrapping back to the top after the last one), so
+/// `uwriteln!` always leaves a clean line behind. `'\r'` is ignored, so
+/// strings written for the serial console look the same here.
+pub struct TextCursor<'a, D> {
+    display: &'a mut D,
+    page: Page,
+    col: u8,
+}
+
+impl<D: Display> TextCursor<'_, D> {
+    pub fn page(&self) -> Page {
+        self.page
+    }
+    
+    pub fn col(&self) -> u8 {
+        self.col
+    }
+    
+    fn newline(&mut self) -> Result<(), D::Error> {
+        self.display.clear_line_from(self.page, self.col)?;
+        
+        let next = (self.page as u8 + 1) % D::Size::PAGES;
+        self.page = Page::try_from(next).unwrap_or(Page::Page0);
+        self.col = 0;
+        Ok(())
+    }
+}
+
+impl<D: Display> ufmt::uWrite for TextCursor<'_, D> {
+    type Error = D::Error;
+    
+    fn write_str(&mut self, s: &str) -> Result<(), D::Error> {
+        for (index, line) in s.split('\n').enumerate() {
+            if index > 0 {
+                self.newline()?;
+            }
+            
+            for run in line.split('\r') {
+                self.col = self.display.write_str_at(self.page, self.col, run)?;
+            }
+        }
+        
+        Ok(())
+    }
+}
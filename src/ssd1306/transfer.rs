@@ -0,0 +1,400 @@
+//! Interrupt-driven I2C transfers.
+//! 
+//! A [`Transfer`] is the part of a TWI master-transmitter driver that doesn't
+//! touch any registers: each time the TWI interrupt fires, the ISR decodes the
+//! status register into a [`Status`], hands it to [`Transfer::on_status`] and
+//! does whatever [`Action`] comes back. That way a whole frame can be streamed
+//! out a byte per interrupt while the main loop keeps running.
+//! 
+//! [`BackgroundFrame`] is a frame to stream that way: the main loop draws
+//! into it, then lends it to a transfer until the ISR is done with it.
+
+use core::marker::PhantomData;
+
+use crate::pm_str;
+use crate::progmem::{PmBytes, PmStr};
+
+use super::command::Page;
+use super::display::Display;
+use super::error::InvalidParameter;
+use super::interface::{COMMAND_STREAM, DATA_STREAM, MAX_COMMAND_BYTES};
+use super::orientation::Orientation;
+use super::size::DisplaySize;
+use super::ssd1306::{check_page_run, window_commands};
+
+/// Largest transaction [`Transfer::write`] can copy in, control byte included.
+pub const HEAD_CAPACITY: usize = MAX_COMMAND_BYTES + 1;
+
+/// Master-transmitter status codes, from the upper 5 bits of TWSR.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Status {
+    Start,            // 0x08
+    RepeatedStart,    // 0x10
+    AddressAck,       // 0x18
+    AddressNack,      // 0x20
+    DataAck,          // 0x28
+    DataNack,         // 0x30
+    ArbitrationLost,  // 0x38
+    Other(u8),
+}
+
+impl Status {
+    /// Decode a raw TWSR value. The prescaler bits are ignored.
+    pub const fn from_twsr(twsr: u8) -> Self {
+        match twsr & 0xF8 {
+            0x08 => Status::Start,
+            0x10 => Status::RepeatedStart,
+            0x18 => Status::AddressAck,
+            0x20 => Status::AddressNack,
+            0x28 => Status::DataAck,
+            0x30 => Status::DataNack,
+            0x38 => Status::ArbitrationLost,
+            other => Status::Other(other),
+        }
+    }
+    
+    const fn code(self) -> u8 {
+        match self {
+            Status::Start => 0x08,
+            Status::RepeatedStart => 0x10,
+            Status::AddressAck => 0x18,
+            Status::AddressNack => 0x20,
+            Status::DataAck => 0x28,
+            Status::DataNack => 0x30,
+            Status::ArbitrationLost => 0x38,
+            Status::Other(code) => code,
+        }
+    }
+}
+
+/// What the ISR should do next.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Action {
+    /// Send a (repeated) START condition.
+    Start,
+    
+    /// Load the byte into TWDR and send it.
+    Write(u8),
+    
+    /// Send a STOP condition. No more interrupts will follow for this transfer.
+    Stop,
+}
+
+/// Why a transfer was abandoned.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TransferError {
+    /// Nothing acknowledged the address.
+    AddressNack,
+    
+    /// The display stopped acknowledging bytes.
+    DataNack,
+    
+    /// Another master took over the bus.
+    ArbitrationLost,
+    
+    /// The TWI reported a status a master transmitter shouldn't see.
+    UnexpectedStatus(u8),
+    
+    /// [`Transfer::write`] was given more than [`HEAD_CAPACITY`] bytes.
+    TooLong,
+    
+    /// Another transfer took the bus while a blocking write was waiting
+    /// for it.
+    Busy,
+}
+
+impl TransferError {
+    pub fn as_str(&self) -> PmStr {
+        match self {
+            TransferError::AddressNack => pm_str!("address not acknowledged"),
+            TransferError::DataNack => pm_str!("data not acknowledged"),
+            TransferError::ArbitrationLost => pm_str!("arbitration lost"),
+            TransferError::UnexpectedStatus(_) => pm_str!("unexpected TWI status"),
+            TransferError::TooLong => pm_str!("transaction too long"),
+            TransferError::Busy => pm_str!("bus taken by another transfer"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Segment {
+    Head,
+    Body,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Phase {
+    Idle,
+    Addressing(Segment),
+    Sending(Segment, usize),
+    Done,
+    Failed(TransferError),
+}
+
+/// One queued write to a display.
+/// 
+/// This is up to two I2C transactions: a short head that's copied into the
+/// transfer (a command stream, or any small write), then optionally a body
+/// of GDDRAM data that's borrowed for as long as the transfer runs and sent
+/// after a repeated START, behind a [`DATA_STREAM`] control byte.
+pub struct Transfer {
+    address: u8,
+    head: [u8; HEAD_CAPACITY],
+    head_len: usize,
+    body: Option<&'static mut [u8]>,
+    phase: Phase,
+}
+
+impl Transfer {
+    /// A single transaction of `bytes`, control byte included.
+    pub fn write(address: u8, bytes: &[u8]) -> Result<Self, TransferError> {
+        if bytes.len() > HEAD_CAPACITY {
+            return Err(TransferError::TooLong);
+        }
+        
+        let mut head = [0; HEAD_CAPACITY];
+        head[..bytes.len()].copy_from_slice(bytes);
+        
+        Ok(Self { address, head, head_len: bytes.len(), body: None, phase: Phase::Idle })
+    }
+    
+    /// Write `data` to the whole of a panel of size `S`.
+    /// 
+    /// The display has to be in horizontal addressing mode.
+    pub fn frame<S: DisplaySize>(address: u8, data: &'static mut [u8]) -> Result<Self, InvalidParameter> {
+        let last_page = Page::try_from(S::PAGES - 1)?;
+        Self::region::<S>(address, 0, S::WIDTH - 1, Page::Page0, last_page, data)
+    }
+    
+    /// Write `data` to columns `start_col..=end_col` of pages
+    /// `start_page..=end_page` of a panel of size `S`.
+    /// 
+    /// The display has to be in horizontal addressing mode, and `data` has to
+    /// cover the region exactly, page by page.
+    pub fn region<S: DisplaySize>(
+        address: u8,
+        start_col: u8,
+        end_col: u8,
+        start_page: Page,
+        end_page: Page,
+        data: &'static mut [u8],
+    ) -> Result<Self, InvalidParameter> {
+        let window = window_commands::<S>(start_col, end_col, start_page, end_page)?;
+        
+        let width = (end_col - start_col) as usize + 1;
+        let pages = (end_page as u8 - start_page as u8) as usize + 1;
+        if data.len() != width * pages {
+            return Err(InvalidParameter::BufferLength);
+        }
+        
+        let mut head = [COMMAND_STREAM; HEAD_CAPACITY];
+        let mut head_len = 1;
+        for command in window {
+            let bytes = command.encode();
+            head[head_len..head_len + bytes.len()].copy_from_slice(bytes.as_bytes());
+            head_len += bytes.len();
+        }
+        
+        Ok(Self { address, head, head_len, body: Some(data), phase: Phase::Idle })
+    }
+    
+    /// Kick off the transfer. The ISR takes over from the first interrupt.
+    pub fn start(&mut self) -> Action {
+        self.phase = Phase::Addressing(Segment::Head);
+        Action::Start
+    }
+    
+    /// Advance the transfer after a TWI interrupt.
+    pub fn on_status(&mut self, status: Status) -> Action {
+        match (self.phase, status) {
+            (Phase::Addressing(_), Status::Start | Status::RepeatedStart) => {
+                Action::Write(self.address << 1)
+            },
+            (Phase::Addressing(segment), Status::AddressAck) => self.next_byte(segment, 0),
+            (Phase::Sending(segment, sent), Status::DataAck) => self.next_byte(segment, sent),
+            
+            (_, Status::AddressNack) => self.fail(TransferError::AddressNack),
+            (_, Status::DataNack) => self.fail(TransferError::DataNack),
+            (_, Status::ArbitrationLost) => self.fail(TransferError::ArbitrationLost),
+            (_, status) => self.fail(TransferError::UnexpectedStatus(status.code())),
+        }
+    }
+    
+    fn next_byte(&mut self, segment: Segment, sent: usize) -> Action {
+        let byte = match segment {
+            Segment::Head => self.head[..self.head_len].get(sent).copied(),
+            Segment::Body if sent == 0 => Some(DATA_STREAM),
+            Segment::Body => self.body.as_deref().and_then(|body| body.get(sent - 1).copied()),
+        };
+        
+        match byte {
+            Some(byte) => {
+                self.phase = Phase::Sending(segment, sent + 1);
+                Action::Write(byte)
+            },
+            None if segment == Segment::Head && self.body.is_some() => {
+                self.phase = Phase::Addressing(Segment::Body);
+                Action::Start
+            },
+            None => {
+                self.phase = Phase::Done;
+                Action::Stop
+            },
+        }
+    }
+    
+    fn fail(&mut self, error: TransferError) -> Action {
+        self.phase = Phase::Failed(error);
+        Action::Stop
+    }
+    
+    /// Whether the transfer has run to completion or failed.
+    pub fn is_finished(&self) -> bool {
+        matches!(self.phase, Phase::Done | Phase::Failed(_))
+    }
+    
+    /// How the transfer went, or `None` if it's still running.
+    pub fn result(&self) -> Option<Result<(), TransferError>> {
+        match self.phase {
+            Phase::Done => Some(Ok(())),
+            Phase::Failed(error) => Some(Err(error)),
+            _ => None,
+        }
+    }
+    
+    /// Consume the transfer and hand back the body buffer, if there was one.
+    pub fn into_buffer(self) -> Option<&'static mut [u8]> {
+        self.body
+    }
+}
+
+
+/// A frame in RAM that's drawn into through [`Display`], then handed to a
+/// [`Transfer::frame`] to be sent in the background.
+/// 
+/// While the transfer has the buffer nothing can draw into it, so
+/// [`BackgroundFrame::target`] returns `None` until the finished transfer is
+/// given back with [`BackgroundFrame::finish`].
+pub struct BackgroundFrame<S> {
+    address: u8,
+    
+    /// `None` while a transfer is sending it.
+    buffer: Option<&'static mut [u8]>,
+    
+    /// Whether anything was drawn since the frame was last handed out.
+    dirty: bool,
+    
+    size: PhantomData<S>,
+}
+
+impl<S: DisplaySize> BackgroundFrame<S> {
+    /// A blank frame for the display at `address`, in `buffer`, which has to
+    /// be exactly `S::BUFFER_SIZE` bytes. It starts out dirty, so the first
+    /// transfer clears whatever was left in the display's RAM.
+    pub fn new(address: u8, buffer: &'static mut [u8]) -> Result<Self, InvalidParameter> {
+        if buffer.len() != S::BUFFER_SIZE {
+            return Err(InvalidParameter::BufferLength);
+        }
+        buffer.fill(0);
+        
+        Ok(Self { address, buffer: Some(buffer), dirty: true, size: PhantomData })
+    }
+    
+    /// Whether a transfer has the buffer.
+    pub fn is_sending(&self) -> bool {
+        self.buffer.is_none()
+    }
+    
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+    
+    /// Draw into the frame, with `display` taking everything that isn't a
+    /// write to its RAM (contrast, orientation, ...). `None` while the frame
+    /// is being sent.
+    pub fn target<'a, D>(&'a mut self, display: &'a mut D) -> Option<FrameTarget<'a, D>>
+    where D: Display<Size = S> {
+        let buffer = self.buffer.as_deref_mut()?;
+        Some(FrameTarget { buffer, dirty: &mut self.dirty, display })
+    }
+    
+    /// Hand the buffer to a transfer of the whole frame, if anything was
+    /// drawn since the last one. The display has to be in horizontal
+    /// addressing mode.
+    pub fn transfer(&mut self) -> Option<Transfer> {
+        if !self.dirty {
+            return None;
+        }
+        
+        // can't fail, `new` checked the buffer's length
+        let transfer = Transfer::frame::<S>(self.address, self.buffer.take()?).ok()?;
+        self.dirty = false;
+        Some(transfer)
+    }
+    
+    /// Take the buffer back from `transfer`, once it's finished or if it
+    /// was never queued. A frame that didn't make it to the display stays
+    /// dirty, so it goes out again with the next transfer.
+    pub fn finish(&mut self, transfer: Transfer) -> Result<(), TransferError> {
+        let result = transfer.result();
+        if result != Some(Ok(())) {
+            self.dirty = true;
+        }
+        
+        self.buffer = transfer.into_buffer();
+        result.unwrap_or(Ok(()))
+    }
+}
+
+/// A [`BackgroundFrame`] being drawn into, created by
+/// [`BackgroundFrame::target`].
+pub struct FrameTarget<'a, D> {
+    buffer: &'a mut [u8],
+    dirty: &'a mut bool,
+    display: &'a mut D,
+}
+
+impl<D: Display> FrameTarget<'_, D> where D::Error: From<InvalidParameter> {
+    /// The bytes `col..col + len` of `page`, after checking they're on the panel.
+    fn run(&mut self, page: Page, col: u8, len: usize) -> Result<&mut [u8], D::Error> {
+        if page as u8 >= D::Size::PAGES {
+            return Err(InvalidParameter::Page.into());
+        }
+        check_page_run::<D::Size>(col, len)?;
+        
+        *self.dirty = true;
+        let start = page as usize * D::Size::WIDTH as usize + col as usize;
+        Ok(&mut self.buffer[start..start + len])
+    }
+}
+
+impl<D: Display> Display for FrameTarget<'_, D> where D::Error: From<InvalidParameter> {
+    type Error = D::Error;
+    type Size = D::Size;
+    
+    fn write_page(&mut self, page: Page, col: u8, data: &[u8]) -> Result<(), Self::Error> {
+        self.run(page, col, data.len())?.copy_from_slice(data);
+        Ok(())
+    }
+    
+    fn write_page_flash(&mut self, page: Page, col: u8, data: PmBytes) -> Result<(), Self::Error> {
+        data.load_into(self.run(page, col, data.len())?);
+        Ok(())
+    }
+    
+    fn set_display_on(&mut self, on: bool) -> Result<(), Self::Error> {
+        self.display.set_display_on(on)
+    }
+    
+    fn set_contrast(&mut self, contrast: u8) -> Result<(), Self::Error> {
+        self.display.set_contrast(contrast)
+    }
+    
+    fn set_inverted(&mut self, inverted: bool) -> Result<(), Self::Error> {
+        self.display.set_inverted(inverted)
+    }
+    
+    fn set_orientation(&mut self, orientation: Orientation) -> Result<(), Self::Error> {
+        self.display.set_orientation(orientation)
+    }
+}
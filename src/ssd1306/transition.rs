@@ -0,0 +1,215 @@
+//! Screen transitions done by the controller, not by redrawing.
+//! 
+//! Every [`Effect`] hides the old screen with a handful of commands per step
+//! (the start line, the multiplex ratio and the contrast), switches the
+//! display off, and lets the caller draw the new screen in the dark. The
+//! same steps then run backwards to reveal it. A whole transition costs a
+//! few dozen command bytes on top of drawing the new screen once.
+//! 
+//! Those three are all the effects use. None of them runs the hardware
+//! scroll, which only moves the picture continuously and can't be stepped
+//! or stopped at a given row.
+//! 
+//! Like [`Player`](super::animation::Player), a [`Transition`] never blocks:
+//! 
+//! ```ignore
+//! transition.start(Effect::Slide, contrast);
+//! loop {
+//!     if transition.update(&mut display, TICK_MS)? {
+//!         // the old screen is gone, draw the new one
+//!     }
+//!     delay_ms(TICK_MS);
+//! }
+//! ```
+
+use super::command::Command;
+use super::error::Ssd1306Error;
+use super::interface::Interface;
+use super::mode::AddressingMode;
+use super::size::DisplaySize;
+use super::ssd1306::SSD1306;
+
+/// Steps each way, from the old screen to dark and from dark to the new one.
+const STEPS: u8 = 8;
+
+/// Time between steps.
+const STEP_MS: u16 = 25;
+
+/// Rows of GDDRAM, whatever the panel shows of them.
+const RAM_ROWS: u8 = 64;
+
+/// Fewest rows the multiplex ratio can drive.
+const MIN_ROWS: u8 = 16;
+
+/// How the old screen goes and the new one comes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Effect {
+    /// The contrast ramps down to 0, and back up to the new screen.
+    Fade,
+    
+    /// The start line steps through all 64 rows of GDDRAM, so the picture
+    /// rolls and wraps around like a TV losing vertical hold, while fading
+    /// out. The new screen rolls back into place. On a panel shorter than
+    /// 64 rows, the RAM rows it doesn't show roll through as well.
+    Roll,
+    
+    /// The picture slides away from one edge as the rows behind it go
+    /// dark, and the new one slides back in. Which edge depends on the
+    /// display's orientation.
+    Slide,
+    
+    /// Rows go dark from one edge while the picture stays put, and the new
+    /// screen is uncovered the same way.
+    Wipe,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Phase {
+    Idle,
+    Hiding,
+    Revealing,
+}
+
+/// Runs one [`Effect`] at a time on an [`SSD1306`].
+pub struct Transition {
+    effect: Effect,
+    contrast: u8,
+    phase: Phase,
+    
+    /// How far the old screen has gone, from 0 (all there) to `STEPS`.
+    level: u8,
+    
+    /// Time left until the next step is due.
+    wait_ms: u16,
+}
+
+impl Transition {
+    pub const fn new() -> Self {
+        Self {
+            effect: Effect::Fade,
+            contrast: 0,
+            phase: Phase::Idle,
+            level: 0,
+            wait_ms: 0,
+        }
+    }
+    
+    /// Start hiding the current screen with `effect`. `contrast` is what the
+    /// new screen comes back at.
+    /// 
+    /// If a transition is already running it turns around from where it is
+    /// and keeps its effect, so it never jumps.
+    pub fn start(&mut self, effect: Effect, contrast: u8) {
+        if self.phase == Phase::Idle {
+            self.effect = effect;
+            self.level = 0;
+            self.wait_ms = 0;
+        }
+        self.contrast = contrast;
+        self.phase = Phase::Hiding;
+    }
+    
+    pub fn is_running(&self) -> bool {
+        self.phase != Phase::Idle
+    }
+    
+    /// Whether the old screen is on its way out. Don't draw anything until
+    /// [`update`](Self::update) says it's gone.
+    pub fn is_hiding(&self) -> bool {
+        self.phase == Phase::Hiding
+    }
+    
+    /// Let `elapsed_ms` pass, and take the next step if it's due. Returns
+    /// `true` once, when the old screen is gone and the display is off: draw
+    /// the new screen then, it's revealed from the next step on.
+    pub fn update<DI, S, M>(&mut self, display: &mut SSD1306<DI, S, M>, elapsed_ms: u16) -> Result<bool, Ssd1306Error<DI::Error>>
+    where DI: Interface, S: DisplaySize, M: AddressingMode {
+        if self.phase == Phase::Idle {
+            return Ok(false);
+        }
+        
+        self.wait_ms = self.wait_ms.saturating_sub(elapsed_ms);
+        if self.wait_ms > 0 {
+            return Ok(false);
+        }
+        self.wait_ms = STEP_MS;
+        
+        match self.phase {
+            Phase::Hiding if self.level < STEPS => {
+                if self.level == 0 {
+                    // a continuous scroll moves the start line by itself, and
+                    // the start line has to stay inside the scroll area, so
+                    // make that the whole panel again. It can't be more rows
+                    // than the multiplex ratio drives.
+                    display.send_commands([
+                        Command::EnableScrolling(false),
+                        Command::SetupVerticalScrollArea { top: 0, bottom: S::HEIGHT },
+                    ])?;
+                }
+                self.level += 1;
+                self.step(display, false)?;
+                Ok(false)
+            },
+            Phase::Hiding => {
+                display.set_display_on(false)?;
+                self.phase = Phase::Revealing;
+                Ok(true)
+            },
+            Phase::Revealing => {
+                let turn_on = self.level == STEPS;
+                self.level -= 1;
+                self.step(display, turn_on)?;
+                if self.level == 0 {
+                    self.phase = Phase::Idle;
+                }
+                Ok(false)
+            },
+            Phase::Idle => Ok(false),
+        }
+    }
+    
+    /// Send the commands for the current level, then switch the display on
+    /// if `turn_on`.
+    fn step<DI, S, M>(&self, display: &mut SSD1306<DI, S, M>, turn_on: bool) -> Result<(), Ssd1306Error<DI::Error>>
+    where DI: Interface, S: DisplaySize, M: AddressingMode {
+        let faded = (self.contrast as u16 * (STEPS - self.level) as u16 / STEPS as u16) as u8;
+        
+        // rows hidden at the edge driven by the highest COM lines
+        let hidden = ((S::HEIGHT - MIN_ROWS) as u16 * self.level as u16 / STEPS as u16) as u8;
+        // whether the start line moves with the hidden edge: that keeps the
+        // picture still with the COM scan reversed, and moves it along
+        // without
+        let follow = display.orientation().com_scan_reversed() == (self.effect == Effect::Wipe);
+        
+        let mut commands = [Command::NoOp; 3];
+        let mut len = match self.effect {
+            Effect::Fade => {
+                commands[0] = Command::SetContrast { contrast: faded };
+                1
+            },
+            Effect::Roll => {
+                commands[0] = Command::SetStartLine((RAM_ROWS / STEPS * self.level) % RAM_ROWS);
+                commands[1] = Command::SetContrast { contrast: faded };
+                2
+            },
+            Effect::Slide | Effect::Wipe => {
+                commands[0] = Command::SetMultiplexRatio { ratio: S::MUX_RATIO - hidden };
+                commands[1] = Command::SetStartLine(if follow { hidden } else { 0 });
+                2
+            },
+        };
+        
+        if turn_on {
+            commands[len] = Command::DisplayEnable(true);
+            len += 1;
+        }
+        
+        display.send_commands(commands[..len].iter())
+    }
+}
+
+impl Default for Transition {
+    fn default() -> Self {
+        Self::new()
+    }
+}
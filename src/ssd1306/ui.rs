@@ -0,0 +1,618 @@
+//! Retained-mode widgets for status screens.
+//! 
+//! Every widget owns a rectangle of whole pages and remembers what it last
+//! drew there. Setting a value only marks the widget dirty if it changes what
+//! would be on screen, and [`Widget::draw`] then rewrites that rectangle and
+//! nothing else, a page at a time. A screen is a handful of widgets that all
+//! get drawn each time round the main loop:
+//! 
+//! ```ignore
+//! let mut ammo = Number::new(Area::new(Page::Page0, 98, 30, 1), Align::Right);
+//! 
+//! loop {
+//!     ammo.set(count);
+//!     ammo.draw(&mut display)?; // only writes anything if `count` changed
+//! }
+//! ```
+
+use crate::progmem::PmStr;
+
+use super::bitmap::Bitmap;
+use super::command::Page;
+use super::display::Display;
+use super::error::InvalidParameter;
+use super::font::FONT_5X7;
+use super::size::DisplaySize;
+use super::strip::MAX_WIDTH;
+use super::text::render_line;
+
+/// A rectangle of the screen, `width` columns by `pages` pages.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Area {
+    pub page: Page,
+    pub col: u8,
+    pub width: u8,
+    pub pages: u8,
+}
+
+impl Area {
+    pub const fn new(page: Page, col: u8, width: u8, pages: u8) -> Self {
+        Self { page, col, width, pages }
+    }
+    
+    /// Height in pixels.
+    pub const fn height(&self) -> u8 {
+        self.pages * 8
+    }
+    
+    /// Check that the area is on a display of size `S`.
+    pub(super) fn check<S: DisplaySize>(&self) -> Result<(), InvalidParameter> {
+        if self.pages == 0 || self.page as u8 + self.pages > S::PAGES {
+            return Err(InvalidParameter::Page);
+        }
+        if self.width == 0 || self.col as usize + self.width as usize > S::WIDTH as usize {
+            return Err(InvalidParameter::Column);
+        }
+        Ok(())
+    }
+}
+
+/// How text sits inside its area.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Align {
+    Left,
+    Center,
+    Right,
+}
+
+impl Align {
+    /// Column where `content` columns of text start inside `width`. Text
+    /// that doesn't fit starts at the left edge and gets cut off.
+    pub(super) fn start(self, width: u8, content: usize) -> u8 {
+        let space = (width as usize).saturating_sub(content) as u8;
+        match self {
+            Align::Left => 0,
+            Align::Center => space / 2,
+            Align::Right => space,
+        }
+    }
+}
+
+/// A value as it was set, and as it was last drawn.
+#[derive(Debug, Clone, Copy)]
+struct Retained<T> {
+    value: T,
+    shown: Option<T>,
+}
+
+impl<T: Copy + PartialEq> Retained<T> {
+    const fn new(value: T) -> Self {
+        Self { value, shown: None }
+    }
+    
+    fn is_dirty(&self) -> bool {
+        self.shown != Some(self.value)
+    }
+    
+    fn mark_drawn(&mut self) {
+        self.shown = Some(self.value);
+    }
+    
+    fn invalidate(&mut self) {
+        self.shown = None;
+    }
+}
+
+/// Something that owns part of the screen and knows whether it has to be
+/// redrawn.
+pub trait Widget {
+    /// The part of the screen the widget draws on.
+    fn area(&self) -> Area;
+    
+    /// Whether what's on the display is out of date.
+    fn is_dirty(&self) -> bool;
+    
+    /// Forget what's on the display, so the next [`draw`](Widget::draw)
+    /// redraws the widget, e.g. after the screen was cleared.
+    fn invalidate(&mut self);
+    
+    /// Render page `page` of the area (0 is the top one) into `line`, which
+    /// is one byte per column of the area and starts out blank.
+    fn render(&self, page: u8, line: &mut [u8]);
+    
+    /// Record that what was last rendered is now on the display.
+    fn mark_drawn(&mut self);
+    
+    /// Rewrite the widget's area if it's dirty. Returns whether anything was
+    /// written.
+    fn draw<D>(&mut self, display: &mut D) -> Result<bool, D::Error>
+    where D: Display, D::Error: From<InvalidParameter> {
+        if !self.is_dirty() {
+            return Ok(false);
+        }
+        
+        let area = self.area();
+        area.check::<D::Size>()?;
+        
+        let mut line = [0; MAX_WIDTH];
+        let line = &mut line[..area.width as usize];
+        for offset in 0..area.pages {
+            line.fill(0);
+            self.render(offset, line);
+            display.write_page(Page::try_from(area.page as u8 + offset)?, area.col, line)?;
+        }
+        
+        self.mark_drawn();
+        Ok(true)
+    }
+}
+
+/// Blank the whole display. Widgets on it have to be invalidated afterwards.
+pub fn clear<D: Display>(display: &mut D) -> Result<(), D::Error> {
+    let blank = [0; MAX_WIDTH];
+    for page in 0..D::Size::PAGES {
+        if let Ok(page) = Page::try_from(page) {
+            display.write_page(page, 0, &blank[..D::Size::WIDTH as usize])?;
+        }
+    }
+    Ok(())
+}
+
+/// Pixel rows `start..end` of a column, as a bitmask with row 0 in bit 0.
+fn rows(start: u8, end: u8) -> u64 {
+    if start >= end {
+        return 0;
+    }
+    (u64::MAX >> (64 - (end - start))) << start
+}
+
+/// The byte of `column` that goes in page `page`.
+fn page_byte(column: u64, page: u8) -> u8 {
+    (column >> (8 * page)) as u8
+}
+
+/// Columns taken by `chars` characters of the small font, without the
+/// spacing column after the last one.
+pub(super) fn text_width(chars: usize) -> usize {
+    (chars * FONT_5X7.advance() as usize).saturating_sub(1)
+}
+
+/// Render `text` into `line` starting at `col`, clipped at the end of the
+/// line. Returns the column after the last character drawn.
+pub(super) fn render_pm_str(line: &mut [u8], col: u8, text: PmStr) -> u8 {
+    let mut col = col;
+    let _ = text.try_for_each_chunk(|chunk| -> Result<(), ()> {
+        col = render_line(&FONT_5X7, line, col, chunk);
+        Ok(())
+    });
+    col
+}
+
+/// Number of characters in `text`.
+pub(super) fn char_count(text: PmStr) -> usize {
+    text.as_bytes().iter().filter(|byte| byte & 0xC0 != 0x80).count()
+}
+
+/// A line of text from flash. Text goes in the top page of the area.
+pub struct Label {
+    area: Area,
+    align: Align,
+    text: Retained<PmStr>,
+}
+
+impl Label {
+    pub const fn new(area: Area, align: Align, text: PmStr) -> Self {
+        Self { area, align, text: Retained::new(text) }
+    }
+    
+    pub fn set(&mut self, text: PmStr) {
+        self.text.value = text;
+    }
+}
+
+impl Widget for Label {
+    fn area(&self) -> Area {
+        self.area
+    }
+    
+    fn is_dirty(&self) -> bool {
+        self.text.is_dirty()
+    }
+    
+    fn invalidate(&mut self) {
+        self.text.invalidate();
+    }
+    
+    fn render(&self, page: u8, line: &mut [u8]) {
+        if page > 0 {
+            return;
+        }
+        
+        let text = self.text.value;
+        let col = self.align.start(self.area.width, text_width(char_count(text)));
+        render_pm_str(line, col, text);
+    }
+    
+    fn mark_drawn(&mut self) {
+        self.text.mark_drawn();
+    }
+}
+
+/// A number in the small font, e.g. a percentage or a count.
+pub struct Number {
+    area: Area,
+    align: Align,
+    decimals: u8,
+    unit: Option<PmStr>,
+    value: Retained<i32>,
+}
+
+impl Number {
+    pub const fn new(area: Area, align: Align) -> Self {
+        Self { area, align, decimals: 0, unit: None, value: Retained::new(0) }
+    }
+    
+    /// Show the value as a fixed point number with `decimals` digits after
+    /// the point, e.g. 741 with 2 decimals is "7.41".
+    pub const fn with_decimals(self, decimals: u8) -> Self {
+        Self { decimals, ..self }
+    }
+    
+    /// Show `unit` right after the number, e.g. `%`.
+    pub const fn with_unit(self, unit: PmStr) -> Self {
+        Self { unit: Some(unit), ..self }
+    }
+    
+    pub fn set(&mut self, value: i32) {
+        self.value.value = value;
+    }
+    
+    pub fn value(&self) -> i32 {
+        self.value.value
+    }
+}
+
+/// Longest string [`format_number`] writes: a sign, 10 digits and a point.
+pub(super) const NUMBER_LEN: usize = 12;
+
+/// Format `value` in decimal into the end of `buffer`, with a point before
+/// the last `decimals` digits (at most 9).
+pub(super) fn format_number(value: i32, decimals: u8, buffer: &mut [u8; NUMBER_LEN]) -> &str {
+    let decimals = decimals.min(9) as usize;
+    let mut rest = value.unsigned_abs();
+    let mut start = buffer.len();
+    let mut digits = 0;
+    // there's always a digit in front of the point
+    while digits <= decimals || rest != 0 {
+        if digits == decimals && digits > 0 {
+            start -= 1;
+            buffer[start] = b'.';
+        }
+        start -= 1;
+        buffer[start] = b'0' + (rest % 10) as u8;
+        rest /= 10;
+        digits += 1;
+    }
+    if value < 0 {
+        start -= 1;
+        buffer[start] = b'-';
+    }
+    
+    // only ASCII digits, '.' and '-' were written
+    core::str::from_utf8(&buffer[start..]).unwrap_or("")
+}
+
+impl Widget for Number {
+    fn area(&self) -> Area {
+        self.area
+    }
+    
+    fn is_dirty(&self) -> bool {
+        self.value.is_dirty()
+    }
+    
+    fn invalidate(&mut self) {
+        self.value.invalidate();
+    }
+    
+    fn render(&self, page: u8, line: &mut [u8]) {
+        if page > 0 {
+            return;
+        }
+        
+        let mut buffer = [0; NUMBER_LEN];
+        let text = format_number(self.value.value, self.decimals, &mut buffer);
+        let chars = text.len() + self.unit.map_or(0, char_count);
+        let col = self.align.start(self.area.width, text_width(chars));
+        let col = render_line(&FONT_5X7, line, col, text);
+        if let Some(unit) = self.unit {
+            render_pm_str(line, col, unit);
+        }
+    }
+    
+    fn mark_drawn(&mut self) {
+        self.value.mark_drawn();
+    }
+}
+
+/// A bitmap, or nothing. The image is drawn from the top left corner of the
+/// area and cut off at its edges.
+pub struct Icon {
+    area: Area,
+    bitmap: Retained<Option<Bitmap>>,
+}
+
+impl Icon {
+    pub const fn new(area: Area, bitmap: Option<Bitmap>) -> Self {
+        Self { area, bitmap: Retained::new(bitmap) }
+    }
+    
+    pub fn set(&mut self, bitmap: Option<Bitmap>) {
+        self.bitmap.value = bitmap;
+    }
+}
+
+impl Widget for Icon {
+    fn area(&self) -> Area {
+        self.area
+    }
+    
+    fn is_dirty(&self) -> bool {
+        self.bitmap.is_dirty()
+    }
+    
+    fn invalidate(&mut self) {
+        self.bitmap.invalidate();
+    }
+    
+    fn render(&self, page: u8, line: &mut [u8]) {
+        if let Some(bitmap) = self.bitmap.value.filter(|bitmap| page < bitmap.pages()) {
+            bitmap.page(page).load_into(line);
+        }
+    }
+    
+    fn mark_drawn(&mut self) {
+        self.bitmap.mark_drawn();
+    }
+}
+
+/// A horizontal bar that fills up from the left, with an outline around it.
+/// 
+/// Only redraws when the filled part changes by at least a column, so
+/// feeding it every reading is cheap.
+pub struct ProgressBar {
+    area: Area,
+    max: u16,
+    value: u16,
+    filled: Retained<u8>,
+}
+
+impl ProgressBar {
+    /// A bar that's full at `max`.
+    pub const fn new(area: Area, max: u16) -> Self {
+        Self { area, max, value: 0, filled: Retained::new(0) }
+    }
+    
+    /// Columns inside the outline.
+    fn inner_width(&self) -> u8 {
+        self.area.width.saturating_sub(4)
+    }
+    
+    /// Set the value, clamped to `0..=max`.
+    pub fn set(&mut self, value: u16) {
+        self.value = value.min(self.max);
+        self.filled.value = if self.max == 0 {
+            0
+        } else {
+            (self.inner_width() as u32 * self.value as u32 / self.max as u32) as u8
+        };
+    }
+    
+    pub fn value(&self) -> u16 {
+        self.value
+    }
+}
+
+impl Widget for ProgressBar {
+    fn area(&self) -> Area {
+        self.area
+    }
+    
+    fn is_dirty(&self) -> bool {
+        self.filled.is_dirty()
+    }
+    
+    fn invalidate(&mut self) {
+        self.filled.invalidate();
+    }
+    
+    fn render(&self, page: u8, line: &mut [u8]) {
+        let h = self.area.height();
+        let last = line.len().saturating_sub(1);
+        let outline = rows(0, h);
+        let frame = rows(0, 1) | rows(h - 1, h);
+        // one blank row and column between the outline and the fill
+        let fill = frame | rows(2, h.saturating_sub(2));
+        
+        for (x, byte) in line.iter_mut().enumerate() {
+            let column = if x == 0 || x == last {
+                outline
+            } else if x >= 2 && x < 2 + self.filled.value as usize {
+                fill
+            } else {
+                frame
+            };
+            *byte = page_byte(column, page);
+        }
+    }
+    
+    fn mark_drawn(&mut self) {
+        self.filled.mark_drawn();
+    }
+}
+
+/// Columns per segment of a [`BatteryGauge`], not counting the gap after it.
+const BATTERY_SEGMENT: u8 = 3;
+
+/// A battery symbol with the terminal on the right, filled with one segment
+/// per step of charge.
+pub struct BatteryGauge {
+    area: Area,
+    lit: Retained<u8>,
+}
+
+impl BatteryGauge {
+    pub const fn new(area: Area) -> Self {
+        Self { area, lit: Retained::new(0) }
+    }
+    
+    /// How many segments fit in the body, which is the area minus the 2
+    /// columns of the terminal.
+    pub fn segments(&self) -> u8 {
+        // the outline, a blank column and a gap after every segment
+        (self.area.width.saturating_sub(2 + 3) / (BATTERY_SEGMENT + 1)).max(1)
+    }
+    
+    /// Set the charge in percent. Any charge above 0 lights at least one
+    /// segment, so an almost empty battery still shows something.
+    pub fn set_percent(&mut self, percent: u8) {
+        let percent = percent.min(100) as u16;
+        let segments = self.segments() as u16;
+        self.lit.value = ((percent * segments).div_ceil(100)) as u8;
+    }
+}
+
+impl Widget for BatteryGauge {
+    fn area(&self) -> Area {
+        self.area
+    }
+    
+    fn is_dirty(&self) -> bool {
+        self.lit.is_dirty()
+    }
+    
+    fn invalidate(&mut self) {
+        self.lit.invalidate();
+    }
+    
+    fn render(&self, page: u8, line: &mut [u8]) {
+        let h = self.area.height();
+        let body_end = line.len().saturating_sub(3);
+        let outline = rows(0, h);
+        let frame = rows(0, 1) | rows(h - 1, h);
+        let fill = frame | rows(2, h.saturating_sub(2));
+        let terminal = rows(h / 4, h - h / 4);
+        
+        for (x, byte) in line.iter_mut().enumerate() {
+            let column = if x == 0 || x == body_end {
+                outline
+            } else if x > body_end {
+                terminal
+            } else {
+                // segments start after the outline and a blank column
+                let inside = (x as u8).wrapping_sub(2);
+                let segment = inside / (BATTERY_SEGMENT + 1);
+                let lit = x >= 2 && inside % (BATTERY_SEGMENT + 1) < BATTERY_SEGMENT && segment < self.lit.value;
+                if lit { fill } else { frame }
+            };
+            *byte = page_byte(column, page);
+        }
+    }
+    
+    fn mark_drawn(&mut self) {
+        self.lit.mark_drawn();
+    }
+}
+
+/// A small graph of the last `N` samples, newest on the right, scaled so
+/// `min` is the bottom row and `max` the top one.
+pub struct Sparkline<const N: usize> {
+    area: Area,
+    min: i16,
+    max: i16,
+    samples: [i16; N],
+    
+    /// Index of the oldest sample.
+    head: usize,
+    len: usize,
+    
+    dirty: bool,
+}
+
+impl<const N: usize> Sparkline<N> {
+    pub const fn new(area: Area, min: i16, max: i16) -> Self {
+        Self { area, min, max, samples: [0; N], head: 0, len: 0, dirty: true }
+    }
+    
+    /// Add a sample, dropping the oldest one once there are `N`.
+    pub fn push(&mut self, sample: i16) {
+        if N == 0 {
+            return;
+        }
+        
+        if self.len < N {
+            self.samples[(self.head + self.len) % N] = sample;
+            self.len += 1;
+        } else {
+            self.samples[self.head] = sample;
+            self.head = (self.head + 1) % N;
+        }
+        self.dirty = true;
+    }
+    
+    /// Drop all samples.
+    pub fn clear(&mut self) {
+        self.len = 0;
+        self.dirty = true;
+    }
+    
+    /// The samples, oldest first.
+    pub fn samples(&self) -> impl Iterator<Item = i16> + '_ {
+        (0..self.len).map(move |index| self.samples[(self.head + index) % N])
+    }
+    
+    /// Pixel row for `sample`, 0 at the top.
+    fn row(&self, sample: i16) -> u8 {
+        let bottom = self.area.height() as i32 - 1;
+        let range = (self.max as i32 - self.min as i32).max(1);
+        let value = (sample.clamp(self.min, self.max) as i32 - self.min as i32).min(range);
+        (bottom - value * bottom / range) as u8
+    }
+}
+
+impl<const N: usize> Widget for Sparkline<N> {
+    fn area(&self) -> Area {
+        self.area
+    }
+    
+    fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+    
+    fn invalidate(&mut self) {
+        self.dirty = true;
+    }
+    
+    fn render(&self, page: u8, line: &mut [u8]) {
+        // the newest samples that fit, right-aligned
+        let shown = self.len.min(line.len());
+        let skip = self.len - shown;
+        let start = line.len() - shown;
+        
+        let mut previous = None;
+        for (byte, sample) in line[start..].iter_mut().zip(self.samples().skip(skip)) {
+            let row = self.row(sample);
+            // join each point to the one before, so steep changes stay visible
+            let (top, bottom) = match previous {
+                Some(previous) => (row.min(previous), row.max(previous)),
+                None => (row, row),
+            };
+            *byte = page_byte(rows(top, bottom + 1), page);
+            previous = Some(row);
+        }
+    }
+    
+    fn mark_drawn(&mut self) {
+        self.dirty = false;
+    }
+}
@@ -0,0 +1,190 @@
+//! Interrupt-driven TWI (I2C) master.
+//! 
+//! Transfers are queued with [`Twi::queue`] and streamed out from the TWI
+//! interrupt a byte at a time, so pushing a whole frame to the display doesn't
+//! hold up the main loop, which collects them with [`Twi::take_finished`].
+//! [`Twi`] also implements the blocking I2C traits by queueing a transfer and
+//! waiting for it, so the display driver can use it for commands and small
+//! writes.
+
+use arduino_hal::pac::TWI as TwiPeripheral;
+use avr_device::interrupt;
+use avr_hal_generic::clock::Clock;
+use avr_hal_generic::port::{Pin, mode};
+use core::cell::RefCell;
+use embedded_hal::blocking::i2c;
+
+use crate::ssd1306::transfer::{Action, Status, Transfer, TransferError};
+
+type SdaPinType = Pin<mode::Input<mode::PullUp>, arduino_hal::hal::port::PC4>;
+type SclPinType = Pin<mode::Input<mode::PullUp>, arduino_hal::hal::port::PC5>;
+
+struct Bus {
+    twi: TwiPeripheral,
+    
+    /// The transfer the ISR is working through.
+    current: Option<Transfer>,
+    
+    /// The last transfer to finish, waiting to be collected.
+    finished: Option<Transfer>,
+    
+    /// Called from the ISR once `current` finishes.
+    on_complete: Option<fn()>,
+    
+    _pins: (SdaPinType, SclPinType),
+}
+
+static BUS: interrupt::Mutex<RefCell<Option<Bus>>> = interrupt::Mutex::new(RefCell::new(None));
+
+
+/// Interrupt handler for the TWI
+/// 
+/// NOTE: this fires after every START, address and data byte
+#[avr_device::interrupt(atmega328p)]
+#[allow(non_snake_case)]
+fn TWI() {
+    let callback = interrupt::free(|cs| {
+        let mut bus = BUS.borrow(cs).borrow_mut();
+        let bus = bus.as_mut()?;
+        let transfer = bus.current.as_mut()?;
+        
+        let status = Status::from_twsr(bus.twi.twsr.read().bits());
+        perform(&bus.twi, transfer.on_status(status));
+        
+        if !transfer.is_finished() {
+            return None;
+        }
+        bus.finished = bus.current.take();
+        bus.on_complete.take()
+    });
+    
+    // run the callback outside the critical section so it can queue the next transfer
+    if let Some(callback) = callback {
+        callback();
+    }
+}
+
+#[inline(always)]
+fn perform(twi: &TwiPeripheral, action: Action) {
+    match action {
+        Action::Start => {
+            // the last STOP is still going out until TWSTO clears, and a
+            // START written before then gets lost
+            while twi.twcr.read().twsto().bit_is_set() {}
+            twi.twcr.write(|w| w.twint().set_bit().twsta().set_bit().twen().set_bit().twie().set_bit())
+        },
+        Action::Write(byte) => {
+            twi.twdr.write(|w| w.bits(byte));
+            twi.twcr.write(|w| w.twint().set_bit().twen().set_bit().twie().set_bit());
+        },
+        // no interrupt follows a STOP, so leave TWIE off until the next transfer
+        Action::Stop => twi.twcr.write(|w| w.twint().set_bit().twsto().set_bit().twen().set_bit()),
+    }
+}
+
+/// Handle to the interrupt-driven TWI, returned by [`setup`].
+#[derive(Clone, Copy)]
+pub struct Twi {
+    _private: (),
+}
+
+impl Twi {
+    /// Start streaming `transfer` in the background.
+    /// 
+    /// `on_complete` runs in interrupt context once the transfer finishes.
+    /// If the bus is still busy, or the last finished transfer hasn't been
+    /// collected with [`Twi::take_finished`] yet, the transfer is handed back.
+    pub fn queue(&self, mut transfer: Transfer, on_complete: Option<fn()>) -> Result<(), Transfer> {
+        interrupt::free(|cs| {
+            let mut bus = BUS.borrow(cs).borrow_mut();
+            let bus = bus.as_mut().expect("TWI not set up");
+            
+            if bus.current.is_some() || bus.finished.is_some() {
+                return Err(transfer);
+            }
+            
+            perform(&bus.twi, transfer.start());
+            bus.current = Some(transfer);
+            bus.on_complete = on_complete;
+            Ok(())
+        })
+    }
+    
+    /// Whether a transfer is still being streamed out.
+    pub fn is_busy(&self) -> bool {
+        interrupt::free(|cs| {
+            BUS.borrow(cs).borrow().as_ref().is_some_and(|bus| bus.current.is_some())
+        })
+    }
+    
+    /// Collect the last finished transfer, to check its result and get its
+    /// buffer back.
+    pub fn take_finished(&self) -> Option<Transfer> {
+        interrupt::free(|cs| {
+            BUS.borrow(cs).borrow_mut().as_mut().and_then(|bus| bus.finished.take())
+        })
+    }
+    
+    /// Make `transfer` the one [`Twi::take_finished`] returns next.
+    fn put_back(&self, transfer: Transfer) {
+        interrupt::free(|cs| {
+            if let Some(bus) = BUS.borrow(cs).borrow_mut().as_mut() {
+                bus.finished = Some(transfer);
+            }
+        })
+    }
+}
+
+impl i2c::Write for Twi {
+    type Error = TransferError;
+    
+    /// Queue the write and spin until it's done.
+    /// 
+    /// Any transfer still running in the background is waited out first, and
+    /// put back for [`Twi::take_finished`] afterwards.
+    /// 
+    /// NOTE: this needs interrupts to be enabled
+    fn write(&mut self, address: u8, bytes: &[u8]) -> Result<(), TransferError> {
+        let transfer = Transfer::write(address, bytes)?;
+        
+        while self.is_busy() {}
+        let background = self.take_finished();
+        
+        // only a completion callback could have queued something in between
+        let result = match self.queue(transfer, None) {
+            Ok(()) => loop {
+                if let Some(finished) = self.take_finished() {
+                    break finished.result().unwrap_or(Ok(()));
+                }
+            },
+            Err(_) => Err(TransferError::Busy),
+        };
+        
+        if let Some(background) = background {
+            self.put_back(background);
+        }
+        result
+    }
+}
+
+#[inline(always)]
+pub fn setup(twi: TwiPeripheral, sda: SdaPinType, scl: SclPinType, speed: u32) -> Twi {
+    // SCL = F_CPU / (16 + 2 * TWBR), with the prescaler at 1
+    let twbr = (arduino_hal::DefaultClock::FREQ / speed - 16) / 2;
+    twi.twbr.write(|w| w.bits(twbr as u8));
+    twi.twsr.write(|w| w.twps().prescaler_1());
+    twi.twcr.write(|w| w.twen().set_bit());
+    
+    // SAFETY: interrupts are disabled so this is safe
+    BUS
+        .borrow(unsafe{interrupt::CriticalSection::new()})
+        .replace(Some(Bus {
+            twi,
+            current: None,
+            finished: None,
+            on_complete: None,
+            _pins: (sda, scl),
+        }));
+    
+    Twi { _private: () }
+}
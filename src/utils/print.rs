@@ -1,12 +1,26 @@
 use core::cell::RefCell;
 use avr_device::interrupt;
+use embedded_hal::serial::Read;
 
 type Console = arduino_hal::hal::usart::Usart0<arduino_hal::DefaultClock>;
 pub static CONSOLE: interrupt::Mutex<RefCell<Option<Console>>> =
     avr_device::interrupt::Mutex::new(RefCell::new(None));
 
+/// Print to the serial console.
+/// 
+/// A plain string without any arguments is kept in flash until it's printed
+/// (and isn't treated as a format string, so `{{` stays as it is).
 #[macro_export]
 macro_rules! print {
+    ($s:literal) => {
+        avr_device::interrupt::free(
+            |cs| {
+                if let Some(console) = $crate::utils::print::CONSOLE.borrow(cs).borrow_mut().as_mut() {
+                    let _ = $crate::pm_str!($s).write_to(console);
+                }
+            },
+        )
+    };
     ($($t:tt)*) => {
         avr_device::interrupt::free(
             |cs| {
@@ -18,8 +32,19 @@ macro_rules! print {
     };
 }
 
+/// Print a line to the serial console. Like [`print!`], plain strings stay in
+/// flash.
 #[macro_export]
 macro_rules! println {
+    ($s:literal) => {
+        avr_device::interrupt::free(
+            |cs| {
+                if let Some(console) = $crate::utils::print::CONSOLE.borrow(cs).borrow_mut().as_mut() {
+                    let _ = $crate::pm_str!(concat!($s, "\n")).write_to(console);
+                }
+            },
+        )
+    };
     ($($t:tt)*) => {
         avr_device::interrupt::free(
             |cs| {
@@ -31,6 +56,12 @@ macro_rules! println {
     };
 }
 
+/// The next byte received on the console, if one is waiting.
+pub fn read_byte() -> Option<u8> {
+    avr_device::interrupt::free(|cs| {
+        CONSOLE.borrow(cs).borrow_mut().as_mut()?.read().ok()
+    })
+}
 
 pub fn put_console(console: Console) {
     avr_device::interrupt::free(|cs| {